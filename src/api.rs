@@ -1,16 +1,134 @@
 use actix_web::{web, HttpResponse, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use url::Url;
 
-use crate::learning_profile::{ProfileDatabase, SiteProfile};
-use crate::scraper::{ScrapingConfig, ScrapingSession, WebScraper};
-use crate::structure_analyzer::{StructureAnalysis, StructureAnalyzer};
-use crate::utils::get_random_user_agent;
+use crate::deep_scraper::DeepScrapeResult;
+use crate::learning_profile::{ProfileStore, SiteProfile};
+use crate::scraper::{ContentDiff, ScrapingConfig, ScrapingResult, ScrapingSession, WebScraper};
+use crate::auto_selectors::{AutoSelectors, DetectedContent, SelectorDetector};
+use crate::structure_analyzer::{
+    generate_selector, ConfidenceLevel, ExtractionMode, ScoringWeights, SeoAudit, StructureAnalysis, StructureAnalyzer,
+};
+use crate::utils::{get_random_user_agent, normalize_whitespace, sanitize_html, DomainRateLimiter, WhitespacePolicy};
 
 #[derive(Clone)]
 pub struct AppState {
     pub sessions: Arc<Mutex<Vec<ScrapingSession>>>,
-    pub profiles: Arc<Mutex<ProfileDatabase>>,
+    /// Per-domain totals of every deep crawl so far. Full crawl results aren't
+    /// kept: they hold every page's content and would grow without bound.
+    pub deep_stats: Arc<Mutex<BTreeMap<String, DomainStats>>>,
+    pub profiles: Arc<Mutex<Box<dyn ProfileStore>>>,
+    /// Shared by every scrape so concurrent requests can't hammer one host
+    pub rate_limiter: Arc<DomainRateLimiter>,
+    /// Fetches in flight at once across all batch requests (`BATCH_CONCURRENCY`)
+    pub batch_limiter: Arc<Semaphore>,
+    /// Deadline for a whole `/api/scrape` request unless the request sets its own
+    /// (`SCRAPE_TIMEOUT_SECS`)
+    pub scrape_timeout_secs: Option<f64>,
+}
+
+/// Batch fetches allowed in flight at once when `BATCH_CONCURRENCY` isn't set
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+
+/// Run `tasks` concurrently, each holding a permit of the shared batch limiter
+/// and, when `limit` is set, of a per-request limiter too. Results keep input order.
+async fn join_bounded<F, T>(shared: &Semaphore, limit: Option<usize>, tasks: impl IntoIterator<Item = F>) -> Vec<T>
+where
+    F: std::future::Future<Output = T>,
+{
+    let local = limit.filter(|n| *n > 0).map(Semaphore::new);
+
+    futures::future::join_all(tasks.into_iter().map(|task| {
+        let local = local.as_ref();
+        async move {
+            // Take the per-request permit first so waiting on it doesn't tie up a shared one
+            let _local = match local {
+                Some(semaphore) => semaphore.acquire().await.ok(),
+                None => None,
+            };
+            let _shared = shared.acquire().await.ok();
+            task.await
+        }
+    })).await
+}
+
+/// Totals for one domain across every stored session and deep crawl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainStats {
+    pub domain: String,
+    pub pages_scraped: usize,
+    pub links_found: usize,
+    pub errors: usize,
+    /// errors / (pages scraped + errors)
+    pub error_rate: f64,
+    pub last_scraped: Option<String>,
+}
+
+impl AppState {
+    /// Fold a finished deep crawl into the per-domain totals
+    pub fn record_deep_crawl(&self, result: &DeepScrapeResult) {
+        tally(&mut self.deep_stats.lock().unwrap(), &result.results, &result.errors);
+    }
+
+    /// Aggregate stored scrape sessions and deep crawl totals per domain
+    pub fn domain_stats(&self) -> Vec<DomainStats> {
+        let mut stats = self.deep_stats.lock().unwrap().clone();
+        for session in self.sessions.lock().unwrap().iter() {
+            tally(&mut stats, &session.results, &session.errors);
+        }
+
+        stats.into_values()
+            .map(|mut s| {
+                let attempts = s.pages_scraped + s.errors;
+                if attempts > 0 {
+                    s.error_rate = s.errors as f64 / attempts as f64;
+                }
+                s
+            })
+            .collect()
+    }
+}
+
+impl DomainStats {
+    fn empty(domain: String) -> Self {
+        Self {
+            domain,
+            pages_scraped: 0,
+            links_found: 0,
+            errors: 0,
+            error_rate: 0.0,
+            last_scraped: None,
+        }
+    }
+}
+
+/// Add pages and errors to per-domain totals; error rates are left for the caller
+fn tally(stats: &mut BTreeMap<String, DomainStats>, results: &[ScrapingResult], errors: &[String]) {
+    for result in results {
+        let Some(domain) = domain_of(&result.url) else { continue };
+        let entry = stats.entry(domain.clone()).or_insert_with(|| DomainStats::empty(domain));
+
+        entry.pages_scraped += 1;
+        entry.links_found += result.content.links.len();
+        if entry.last_scraped.as_deref().is_none_or(|last| result.timestamp.as_str() > last) {
+            entry.last_scraped = Some(result.timestamp.clone());
+        }
+    }
+
+    // Error strings lead with the failing URL ("Failed to scrape <url>: ..." or "<url>: ...")
+    for error in errors {
+        let domain = error.split_whitespace().find_map(|token| domain_of(token.trim_end_matches(':')));
+        if let Some(domain) = domain {
+            stats.entry(domain.clone()).or_insert_with(|| DomainStats::empty(domain)).errors += 1;
+        }
+    }
+}
+
+fn domain_of(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(|host| host.to_string())
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,7 +141,42 @@ pub struct ScrapeRequest {
     #[serde(default = "default_rate_limit")]
     pub rate_limit: f64,
     #[serde(default)]
+    pub burst: Option<u32>,
+    #[serde(default)]
     pub custom_selectors: Option<crate::auto_selectors::AutoSelectors>,
+    #[serde(default)]
+    pub prefer_amp: bool,
+    #[serde(default)]
+    pub whitespace_policy: crate::utils::WhitespacePolicy,
+    #[serde(default)]
+    pub title_policy: crate::auto_selectors::TitlePolicy,
+    #[serde(default)]
+    pub stop_selector: Option<String>,
+    #[serde(default)]
+    pub noscript_fallback: bool,
+    #[serde(default = "crate::auto_selectors::default_max_content_blocks")]
+    pub max_content_blocks: usize,
+    #[serde(default = "crate::auto_selectors::default_max_block_chars")]
+    pub max_block_chars: usize,
+    #[serde(default)]
+    pub capture_headers: bool,
+    #[serde(default)]
+    pub field_selectors: Option<std::collections::HashMap<String, String>>,
+    #[serde(default)]
+    pub ignored_query_params: Vec<String>,
+    #[serde(default)]
+    pub extra_headers: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub force_encoding: Option<String>,
+    #[serde(default)]
+    pub store_raw_html: bool,
+    #[serde(default)]
+    pub item_selector: Option<String>,
+    #[serde(default)]
+    pub concurrency: usize,
+    /// Overrides the server's `SCRAPE_TIMEOUT_SECS`
+    #[serde(default)]
+    pub scrape_timeout_secs: Option<f64>,
 }
 
 fn default_rate_limit() -> f64 {
@@ -56,7 +209,26 @@ pub async fn scrape_handler(
         enable_pagination: req.enable_pagination,
         max_pages: req.max_pages,
         rate_limit: req.rate_limit,
+        concurrency: req.concurrency,
+        burst: req.burst,
         custom_selectors: req.custom_selectors.clone(),
+        prefer_amp: req.prefer_amp,
+        whitespace_policy: req.whitespace_policy,
+        title_policy: req.title_policy,
+        stop_selector: req.stop_selector.clone(),
+        item_selector: req.item_selector.clone(),
+        noscript_fallback: req.noscript_fallback,
+        max_content_blocks: req.max_content_blocks,
+        max_block_chars: req.max_block_chars,
+        capture_headers: req.capture_headers,
+        field_selectors: req.field_selectors.clone(),
+        ignored_query_params: req.ignored_query_params.clone(),
+        extra_headers: req.extra_headers.clone(),
+        force_encoding: req.force_encoding.clone(),
+        store_raw_html: req.store_raw_html,
+        max_duration_secs: req.scrape_timeout_secs.or(state.scrape_timeout_secs),
+        domain_rate_limiter: Some(state.rate_limiter.clone()),
+        ..ScrapingConfig::default()
     };
 
     let scraper = match WebScraper::new(&config, true) {
@@ -81,14 +253,20 @@ pub async fn scrape_handler(
                 session.total_links_found
             );
 
-            Ok(HttpResponse::Ok().json(ScrapeResponse {
-                success: true,
-                message: format!(
+            let message = if session.timed_out {
+                format!("Timed out; returning {} page(s) scraped so far", session.total_pages_scraped)
+            } else {
+                format!(
                     "Successfully scraped {} pages with {} links and {} images",
                     session.total_pages_scraped,
                     session.total_links_found,
                     session.total_images_found
-                ),
+                )
+            };
+
+            Ok(HttpResponse::Ok().json(ScrapeResponse {
+                success: true,
+                message,
                 session: Some(session),
             }))
         }
@@ -103,6 +281,287 @@ pub async fn scrape_handler(
     }
 }
 
+// URL Validation API
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateRequest {
+    pub urls: Vec<String>,
+    /// Most URLs checked at once for this request, on top of the server-wide limit
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UrlValidation {
+    pub url: String,
+    pub reachable: bool,
+    pub final_url: Option<String>,
+    pub status_code: Option<u16>,
+    pub content_type: Option<String>,
+    pub is_html: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateResponse {
+    pub success: bool,
+    pub message: String,
+    pub results: Vec<UrlValidation>,
+}
+
+/// Check reachability without extracting anything: HEAD first, GET if HEAD is rejected
+async fn validate_url(client: &reqwest::Client, rate_limiter: &DomainRateLimiter, url: &str) -> UrlValidation {
+    let mut validation = UrlValidation {
+        url: url.to_string(),
+        reachable: false,
+        final_url: None,
+        status_code: None,
+        content_type: None,
+        is_html: false,
+        error: None,
+    };
+
+    rate_limiter.wait(url).await;
+    let mut response = client
+        .head(url)
+        .header("User-Agent", get_random_user_agent())
+        .send()
+        .await;
+
+    let rejects_head = matches!(&response, Ok(r)
+        if r.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED
+            || r.status() == reqwest::StatusCode::NOT_IMPLEMENTED);
+
+    if rejects_head {
+        // The body is never read, so this stays cheap
+        rate_limiter.wait(url).await;
+        response = client
+            .get(url)
+            .header("User-Agent", get_random_user_agent())
+            .send()
+            .await;
+    }
+
+    match response {
+        Ok(r) => {
+            validation.reachable = r.status().is_success();
+            validation.final_url = Some(r.url().to_string());
+            validation.status_code = Some(r.status().as_u16());
+            validation.content_type = r.headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+            validation.is_html = validation.content_type.as_deref()
+                .is_some_and(|ct| ct.contains("text/html") || ct.contains("application/xhtml+xml"));
+        }
+        Err(e) => {
+            validation.error = Some(e.to_string());
+        }
+    }
+
+    validation
+}
+
+pub async fn validate_handler(
+    state: web::Data<AppState>,
+    req: web::Json<ValidateRequest>,
+) -> Result<HttpResponse> {
+    log::info!("Received validation request for {} URL(s)", req.urls.len());
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| {
+            log::error!("Failed to create HTTP client: {}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?;
+
+    let results = join_bounded(
+        &state.batch_limiter,
+        req.concurrency,
+        req.urls.iter().map(|url| validate_url(&client, &state.rate_limiter, url)),
+    ).await;
+
+    let reachable = results.iter().filter(|r| r.reachable).count();
+
+    Ok(HttpResponse::Ok().json(ValidateResponse {
+        success: true,
+        message: format!("{} of {} URLs reachable", reachable, results.len()),
+        results,
+    }))
+}
+
+// Selector Testing API
+
+/// Matches echoed back by the selector tester
+const SELECTOR_TEST_SAMPLES: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectorTestRequest {
+    pub url: String,
+    pub selector: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectorMatch {
+    pub text: String,
+    pub html: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectorTestResponse {
+    pub success: bool,
+    pub message: String,
+    pub match_count: usize,
+    /// The first few matches, in document order
+    pub matches: Vec<SelectorMatch>,
+}
+
+/// Fetch a page for the selector tools; an unreachable page becomes a 502 response
+async fn fetch_html(rate_limiter: &DomainRateLimiter, url: &str) -> Result<std::result::Result<String, HttpResponse>> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| {
+            log::error!("Failed to create HTTP client: {}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?;
+
+    rate_limiter.wait(url).await;
+    let response = match client
+        .get(url)
+        .header("User-Agent", get_random_user_agent())
+        .send()
+        .await
+    {
+        Ok(r) if r.status().is_success() => r,
+        Ok(r) => {
+            return Ok(Err(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("HTTP error: {}", r.status())
+            }))));
+        }
+        Err(e) => {
+            return Ok(Err(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("Failed to fetch URL: {}", e)
+            }))));
+        }
+    };
+
+    let html = response.text().await.map_err(|e| {
+        log::error!("Failed to read response body: {}", e);
+        actix_web::error::ErrorBadGateway(e)
+    })?;
+
+    Ok(Ok(html))
+}
+
+pub async fn selector_test_handler(
+    state: web::Data<AppState>,
+    req: web::Json<SelectorTestRequest>,
+) -> Result<HttpResponse> {
+    let selector = match scraper::Selector::parse(&req.selector) {
+        Ok(selector) => selector,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Invalid selector '{}': {:?}", req.selector, e)
+            })));
+        }
+    };
+
+    let html = match fetch_html(&state.rate_limiter, &req.url).await? {
+        Ok(html) => html,
+        Err(response) => return Ok(response),
+    };
+
+    let document = scraper::Html::parse_document(&html);
+    let mut match_count = 0;
+    let mut matches = Vec::new();
+
+    for element in document.select(&selector) {
+        match_count += 1;
+        if matches.len() < SELECTOR_TEST_SAMPLES {
+            let text: String = element.text().collect();
+            matches.push(SelectorMatch {
+                text: normalize_whitespace(&text, WhitespacePolicy::Collapse),
+                html: sanitize_html(&element.html()),
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(SelectorTestResponse {
+        success: true,
+        message: format!("{} match(es) for '{}'", match_count, req.selector),
+        match_count,
+        matches,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectorInferRequest {
+    pub url: String,
+    /// Text the user can see on the page
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectorInferResponse {
+    pub success: bool,
+    pub message: String,
+    /// Selector matching exactly the element holding the text
+    pub selector: String,
+    /// Full text of that element
+    pub element_text: String,
+    /// Elements on the page that contain the text
+    pub candidates: usize,
+}
+
+pub async fn selector_infer_handler(
+    state: web::Data<AppState>,
+    req: web::Json<SelectorInferRequest>,
+) -> Result<HttpResponse> {
+    let needle = normalize_whitespace(&req.text, WhitespacePolicy::Collapse).to_lowercase();
+    if needle.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "text must not be empty"
+        })));
+    }
+
+    let html = match fetch_html(&state.rate_limiter, &req.url).await? {
+        Ok(html) => html,
+        Err(response) => return Ok(response),
+    };
+
+    let document = scraper::Html::parse_document(&html);
+    let body = scraper::Selector::parse("body *").unwrap();
+    let holders: Vec<(scraper::ElementRef, String)> = document.select(&body)
+        .map(|element| {
+            let text: String = element.text().collect();
+            (element, normalize_whitespace(&text, WhitespacePolicy::Collapse))
+        })
+        .filter(|(_, text)| text.to_lowercase().contains(&needle))
+        .collect();
+
+    // Every ancestor of the text's element contains it too: the shortest, then
+    // deepest, text is the most specific
+    let Some((element, element_text)) = holders.iter()
+        .min_by_key(|(element, text)| (text.len(), std::cmp::Reverse(element.ancestors().count())))
+    else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Text not found on {}", req.url)
+        })));
+    };
+
+    let selector = generate_selector(&document, *element);
+
+    Ok(HttpResponse::Ok().json(SelectorInferResponse {
+        success: true,
+        message: format!("Inferred selector from {} candidate element(s)", holders.len()),
+        selector,
+        element_text: element_text.clone(),
+        candidates: holders.len(),
+    }))
+}
+
 pub async fn get_sessions(state: web::Data<AppState>) -> Result<HttpResponse> {
     let sessions = state.sessions.lock().unwrap();
     Ok(HttpResponse::Ok().json(&*sessions))
@@ -124,8 +583,13 @@ pub async fn get_session(
     }
 }
 
+pub async fn get_domain_stats(state: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(state.domain_stats()))
+}
+
 pub async fn clear_sessions(state: web::Data<AppState>) -> Result<HttpResponse> {
     state.sessions.lock().unwrap().clear();
+    state.deep_stats.lock().unwrap().clear();
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "message": "All sessions cleared"
     })))
@@ -142,6 +606,10 @@ pub struct AnalyzeRequest {
     pub detect_comments: bool,
     #[serde(default)]
     pub debug_mode: bool,
+    #[serde(default)]
+    pub include_empty_sections: bool,
+    #[serde(default)]
+    pub min_confidence: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -151,6 +619,13 @@ pub struct AnalyzeResponse {
     pub analysis: Option<StructureAnalysis>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RescoreRequest {
+    pub analysis: StructureAnalysis,
+    #[serde(default)]
+    pub weights: ScoringWeights,
+}
+
 // Profile Management API
 
 pub async fn get_profiles(state: web::Data<AppState>) -> Result<HttpResponse> {
@@ -187,6 +662,82 @@ pub async fn get_profile(
     }
 }
 
+pub async fn get_profile_selectors(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let profiles = state.profiles.lock().unwrap();
+
+    match profiles.get_by_id(&id) {
+        Ok(Some(profile)) => Ok(HttpResponse::Ok().json(profile.to_auto_selectors())),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Profile not found"
+        }))),
+        Err(e) => {
+            log::error!("Failed to get profile: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to retrieve profile: {}", e)
+            })))
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProfileVerifyRequest {
+    /// Page to check against; defaults to the profile domain's home page
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+pub async fn verify_profile_handler(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: Option<web::Json<ProfileVerifyRequest>>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+
+    let profile = {
+        let profiles = state.profiles.lock().unwrap();
+        match profiles.get_by_id(&id) {
+            Ok(Some(profile)) => profile,
+            Ok(None) => {
+                return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Profile not found"
+                })));
+            }
+            Err(e) => {
+                log::error!("Failed to get profile: {}", e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Failed to retrieve profile: {}", e)
+                })));
+            }
+        }
+    };
+
+    let url = req.and_then(|req| req.into_inner().url)
+        .unwrap_or_else(|| format!("https://{}/", profile.domain));
+
+    let html = match fetch_html(&state.rate_limiter, &url).await? {
+        Ok(html) => html,
+        Err(response) => return Ok(response),
+    };
+
+    let profiles = state.profiles.lock().unwrap();
+    match profiles.verify_profile(&id, &html) {
+        Ok(Some(verification)) => Ok(HttpResponse::Ok().json(verification)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Profile not found"
+        }))),
+        Err(e) => {
+            log::error!("Failed to verify profile: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to verify profile: {}", e)
+            })))
+        }
+    }
+}
+
 pub async fn get_profile_by_domain(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -274,6 +825,7 @@ pub async fn analyze_handler(
             actix_web::error::ErrorInternalServerError(e)
         })?;
 
+    state.rate_limiter.wait(&req.url).await;
     let response = client
         .get(&req.url)
         .header("User-Agent", user_agent)
@@ -303,7 +855,9 @@ pub async fn analyze_handler(
         StructureAnalyzer::with_options(min_len, req.detect_comments, req.debug_mode)
     } else {
         StructureAnalyzer::new()
-    };
+    }
+    .with_empty_sections(req.include_empty_sections)
+    .with_min_confidence(req.min_confidence);
 
     let analysis = analyzer.analyze(&html, &req.url);
 
@@ -342,6 +896,158 @@ pub async fn analyze_handler(
     }))
 }
 
+pub async fn rescore_handler(req: web::Json<RescoreRequest>) -> Result<HttpResponse> {
+    let req = req.into_inner();
+    let analysis = StructureAnalyzer::new().rescore(&req.analysis, req.weights);
+
+    Ok(HttpResponse::Ok().json(AnalyzeResponse {
+        success: true,
+        message: format!("Rescored {} sections", analysis.sections.len()),
+        analysis: Some(analysis),
+    }))
+}
+
+/// Two scrapes of the same URL, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffRequest {
+    pub previous: ScrapingResult,
+    pub current: ScrapingResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffResponse {
+    pub success: bool,
+    pub message: String,
+    pub diff: Option<ContentDiff>,
+}
+
+pub async fn diff_handler(req: web::Json<DiffRequest>) -> Result<HttpResponse> {
+    let req = req.into_inner();
+    if req.previous.url != req.current.url {
+        return Ok(HttpResponse::BadRequest().json(DiffResponse {
+            success: false,
+            message: format!("Results are for different URLs: {} and {}", req.previous.url, req.current.url),
+            diff: None,
+        }));
+    }
+
+    let diff = req.current.content_diff(&req.previous);
+    Ok(HttpResponse::Ok().json(DiffResponse {
+        success: true,
+        message: format!("{} blocks added, {} removed", diff.added.len(), diff.removed.len()),
+        diff: Some(diff),
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrapeAnalyzeRequest {
+    pub url: String,
+    #[serde(default)]
+    pub min_content_length: Option<usize>,
+    #[serde(default)]
+    pub detect_comments: bool,
+    #[serde(default)]
+    pub include_empty_sections: bool,
+}
+
+/// Extraction and analysis of one page combined
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrapeAnalyzeRecommendation {
+    pub suggested_mode: ExtractionMode,
+    pub confidence_level: ConfidenceLevel,
+    /// Default selectors with the analysis' best picks tried first; ready to
+    /// pass as `custom_selectors` to `/api/scrape`
+    pub selectors: AutoSelectors,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrapeAnalyzeResponse {
+    pub success: bool,
+    pub message: String,
+    pub content: DetectedContent,
+    pub analysis: StructureAnalysis,
+    pub recommendation: ScrapeAnalyzeRecommendation,
+}
+
+/// Fetch a page once, then both extract its content and analyze its structure
+pub async fn scrape_analyze_handler(
+    state: web::Data<AppState>,
+    req: web::Json<ScrapeAnalyzeRequest>,
+) -> Result<HttpResponse> {
+    log::info!("Received scrape-and-analyze request for: {}", req.url);
+
+    let html = match fetch_html(&state.rate_limiter, &req.url).await? {
+        Ok(html) => html,
+        Err(response) => return Ok(response),
+    };
+
+    let content = SelectorDetector::new().detect(&html, &req.url);
+
+    let analyzer = match req.min_content_length {
+        Some(min_len) => StructureAnalyzer::with_options(min_len, req.detect_comments, false),
+        None => StructureAnalyzer::new(),
+    }
+    .with_empty_sections(req.include_empty_sections);
+    let analysis = analyzer.analyze(&html, &req.url);
+
+    let mut selectors = AutoSelectors::default();
+    let recommendations = &analysis.recommendations;
+    if let Some(ref title) = recommendations.best_title {
+        selectors.title.insert(0, title.clone());
+    }
+    if let Some(ref main) = recommendations.best_main_content {
+        selectors.content.insert(0, main.clone());
+    }
+
+    let recommendation = ScrapeAnalyzeRecommendation {
+        suggested_mode: recommendations.suggested_mode,
+        confidence_level: recommendations.confidence_level.clone(),
+        selectors,
+    };
+
+    Ok(HttpResponse::Ok().json(ScrapeAnalyzeResponse {
+        success: true,
+        message: format!(
+            "Extracted {} content block(s); {} section(s) found",
+            content.content.len(),
+            analysis.sections.len()
+        ),
+        content,
+        analysis,
+        recommendation,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditRequest {
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditResponse {
+    pub success: bool,
+    pub message: String,
+    pub audit: SeoAudit,
+}
+
+pub async fn audit_handler(
+    state: web::Data<AppState>,
+    req: web::Json<AuditRequest>,
+) -> Result<HttpResponse> {
+    let html = match fetch_html(&state.rate_limiter, &req.url).await? {
+        Ok(html) => html,
+        Err(response) => return Ok(response),
+    };
+
+    let audit = StructureAnalyzer::new().audit(&html, &req.url);
+
+    Ok(HttpResponse::Ok().json(AuditResponse {
+        success: true,
+        message: format!("Audit complete: {} issue(s) found", audit.issues.len()),
+        audit,
+    }))
+}
+
 // Deep Scraping Handlers
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -349,6 +1055,8 @@ pub struct DeepScrapeRequest {
     pub start_urls: Vec<String>,
     #[serde(default = "default_depth")]
     pub max_depth: usize,
+    #[serde(default)]
+    pub per_domain_max_depth: std::collections::HashMap<String, usize>,
     #[serde(default = "default_max_pages")]
     pub max_pages: usize,
     #[serde(default = "default_stay_in_domain")]
@@ -367,6 +1075,50 @@ pub struct DeepScrapeRequest {
     pub filter_navigation: bool,
     #[serde(default = "default_min_content_length")]
     pub min_content_length: usize,
+    #[serde(default)]
+    pub retry_failed: bool,
+    #[serde(default)]
+    pub skip_soft_404: bool,
+    #[serde(default)]
+    pub collapse_duplicates: bool,
+    #[serde(default)]
+    pub max_duration_secs: Option<f64>,
+    #[serde(default)]
+    pub fold_index_pages: bool,
+    #[serde(default)]
+    pub sitemap_urls: Vec<String>,
+    #[serde(default = "crate::deep_scraper::default_excluded_extensions")]
+    pub excluded_extensions: Vec<String>,
+    #[serde(default)]
+    pub allowed_extensions: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_consecutive_errors: Option<usize>,
+    #[serde(default)]
+    pub max_total_errors: Option<usize>,
+    #[serde(default)]
+    pub path_prefixes: Vec<String>,
+    #[serde(default)]
+    pub preferred_language: Option<String>,
+    #[serde(default)]
+    pub adaptive_delay: bool,
+    #[serde(default)]
+    pub respect_ratelimit_headers: bool,
+    #[serde(default)]
+    pub upgrade_insecure: bool,
+    #[serde(default)]
+    pub send_referer: bool,
+    #[serde(default)]
+    pub extra_headers: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub concurrency: usize,
+    #[serde(default)]
+    pub global_max_inflight: usize,
+    #[serde(default)]
+    pub prune_unproductive: bool,
+    #[serde(default)]
+    pub follow_json_links: bool,
+    #[serde(default)]
+    pub classify_pages: bool,
 }
 
 fn default_depth() -> usize { 2 }
@@ -393,6 +1145,7 @@ pub struct DeepScrapeResponse {
 }
 
 pub async fn deep_scrape_handler(
+    state: web::Data<AppState>,
     req: web::Json<DeepScrapeRequest>,
 ) -> Result<HttpResponse> {
     log::info!("Received deep scrape request: {} start URLs, max depth: {}",
@@ -402,6 +1155,7 @@ pub async fn deep_scrape_handler(
     let config = crate::deep_scraper::DeepScrapeConfig {
         start_urls: req.start_urls.clone(),
         max_depth: req.max_depth,
+        per_domain_max_depth: req.per_domain_max_depth.clone(),
         max_pages: req.max_pages,
         stay_in_domain: req.stay_in_domain,
         stay_in_subdomain: req.stay_in_subdomain,
@@ -411,6 +1165,29 @@ pub async fn deep_scrape_handler(
         custom_selectors: req.custom_selectors.clone(),
         filter_navigation: req.filter_navigation,
         min_content_length: req.min_content_length,
+        retry_failed: req.retry_failed,
+        skip_soft_404: req.skip_soft_404,
+        collapse_duplicates: req.collapse_duplicates,
+        max_duration_secs: req.max_duration_secs,
+        fold_index_pages: req.fold_index_pages,
+        sitemap_urls: req.sitemap_urls.clone(),
+        excluded_extensions: req.excluded_extensions.clone(),
+        allowed_extensions: req.allowed_extensions.clone(),
+        max_consecutive_errors: req.max_consecutive_errors,
+        max_total_errors: req.max_total_errors,
+        path_prefixes: req.path_prefixes.clone(),
+        preferred_language: req.preferred_language.clone(),
+        adaptive_delay: req.adaptive_delay,
+        respect_ratelimit_headers: req.respect_ratelimit_headers,
+        upgrade_insecure: req.upgrade_insecure,
+        send_referer: req.send_referer,
+        extra_headers: req.extra_headers.clone(),
+        concurrency: req.concurrency,
+        global_max_inflight: req.global_max_inflight,
+        prune_unproductive: req.prune_unproductive,
+        follow_json_links: req.follow_json_links,
+        classify_pages: req.classify_pages,
+        domain_rate_limiter: Some(state.rate_limiter.clone()),
     };
 
     // Create deep scraper
@@ -419,6 +1196,8 @@ pub async fn deep_scrape_handler(
     // Execute deep scrape
     let result = scraper.scrape().await;
 
+    state.record_deep_crawl(&result);
+
     let success = result.status == crate::deep_scraper::CrawlStatus::Completed ||
                  result.status == crate::deep_scraper::CrawlStatus::PartiallyCompleted;
 
@@ -433,3 +1212,391 @@ pub async fn deep_scrape_handler(
         result: Some(result),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning_profile::MemoryProfileStore;
+    use std::collections::HashMap;
+    use crate::test_support::{MockResponse, MockServer};
+    use actix_web::{test, App};
+
+    fn test_state() -> AppState {
+        AppState {
+            sessions: Arc::new(Mutex::new(Vec::new())),
+            deep_stats: Arc::new(Mutex::new(BTreeMap::new())),
+            profiles: Arc::new(Mutex::new(Box::new(MemoryProfileStore::new()) as Box<dyn ProfileStore>)),
+            rate_limiter: Arc::new(DomainRateLimiter::default()),
+            batch_limiter: Arc::new(Semaphore::new(DEFAULT_BATCH_CONCURRENCY)),
+            scrape_timeout_secs: None,
+        }
+    }
+
+    fn page(url: &str, timestamp: &str, link_count: usize) -> ScrapingResult {
+        let links: String = (0..link_count).map(|i| format!(r#"<a href="/p{}">Link {}</a>"#, i, i)).collect();
+        let html = format!("<html><body>{}</body></html>", links);
+
+        ScrapingResult {
+            url: url.to_string(),
+            timestamp: timestamp.to_string(),
+            status: "success".to_string(),
+            content: SelectorDetector::new().detect(&html, url),
+            page_number: 1,
+            quality_score: 0.0,
+            headers: HashMap::new(),
+            final_url: None,
+            fields: HashMap::new(),
+            timings: Default::default(),
+            item_count: None,
+            raw_html: None,
+            json: None,
+        }
+    }
+
+    fn session(results: Vec<ScrapingResult>, errors: Vec<String>) -> ScrapingSession {
+        ScrapingSession {
+            start_time: String::new(),
+            config: ScrapingConfig::default(),
+            total_pages_scraped: results.len(),
+            total_links_found: results.iter().map(|r| r.content.links.len()).sum(),
+            total_images_found: 0,
+            results,
+            errors,
+            cursor: None,
+            total_items_expected: None,
+            items_collected: 0,
+            items_shortfall: None,
+            timed_out: false,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_domain_stats_aggregate_across_sessions() {
+        let state = test_state();
+        {
+            let mut sessions = state.sessions.lock().unwrap();
+            sessions.push(session(
+                vec![
+                    page("https://a.example/one", "2025-01-01T10:00:00+00:00", 2),
+                    page("https://b.example/one", "2025-01-01T11:00:00+00:00", 1),
+                ],
+                vec!["Failed to scrape https://a.example/broken: HTTP error: 500".to_string()],
+            ));
+            sessions.push(session(
+                vec![page("https://a.example/two", "2025-01-02T10:00:00+00:00", 3)],
+                Vec::new(),
+            ));
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .route("/api/stats/domains", web::get().to(get_domain_stats))
+        ).await;
+
+        let req = test::TestRequest::get().uri("/api/stats/domains").to_request();
+        let stats: Vec<DomainStats> = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(stats.len(), 2);
+
+        let a = &stats[0];
+        assert_eq!(a.domain, "a.example");
+        assert_eq!(a.pages_scraped, 2);
+        assert_eq!(a.links_found, 5);
+        assert_eq!(a.errors, 1);
+        assert!((a.error_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(a.last_scraped.as_deref(), Some("2025-01-02T10:00:00+00:00"));
+
+        let b = &stats[1];
+        assert_eq!(b.domain, "b.example");
+        assert_eq!(b.pages_scraped, 1);
+        assert_eq!(b.links_found, 1);
+        assert_eq!(b.errors, 0);
+        assert_eq!(b.error_rate, 0.0);
+    }
+
+    #[actix_web::test]
+    async fn test_deep_crawls_are_kept_as_totals_and_cleared_with_sessions() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/missing" => MockResponse::status(404),
+            _ => MockResponse::html(r#"<html><body><h1>Home</h1><a href="/missing">Gone</a></body></html>"#),
+        }).await;
+        let crawl = crate::deep_scraper::DeepScraper::new(crate::deep_scraper::DeepScrapeConfig {
+            start_urls: vec![server.url("/")],
+            max_depth: 1,
+            rate_limit: 100.0,
+            min_content_length: 0,
+            ..Default::default()
+        }).scrape().await;
+
+        let state = test_state();
+        state.record_deep_crawl(&crawl);
+        state.record_deep_crawl(&crawl);
+
+        let stats = state.domain_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].pages_scraped, 2);
+        assert_eq!(stats[0].links_found, 2);
+        assert_eq!(stats[0].errors, 2);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state.clone()))
+                .route("/api/sessions", web::delete().to(clear_sessions))
+        ).await;
+        test::call_service(&app, test::TestRequest::delete().uri("/api/sessions").to_request()).await;
+
+        assert!(state.domain_stats().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_scrape_handler_returns_partial_session_on_timeout() {
+        let server = MockServer::start(|request| {
+            MockResponse::html(&format!("<html><body><h1>{}</h1></body></html>", request.path))
+                .with_delay(std::time::Duration::from_millis(300))
+        }).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_state()))
+                .route("/api/scrape", web::post().to(scrape_handler))
+        ).await;
+
+        let started = std::time::Instant::now();
+        let req = test::TestRequest::post()
+            .uri("/api/scrape")
+            .set_json(serde_json::json!({
+                "urls": [server.url("/a"), server.url("/b"), server.url("/c")],
+                "scrape_timeout_secs": 0.45
+            }))
+            .to_request();
+        let response: ScrapeResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert!(started.elapsed() < std::time::Duration::from_millis(900));
+        assert!(response.success);
+        let session = response.session.unwrap();
+        assert!(session.timed_out);
+        assert_eq!(session.results.len(), 1);
+        assert_eq!(session.results[0].url, server.url("/a"));
+    }
+
+    #[actix_web::test]
+    async fn test_validate_reports_per_url_status() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/ok" => MockResponse::html("<html><body>fine</body></html>"),
+            _ => MockResponse::status(404),
+        }).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_state()))
+                .route("/api/scrape/validate", web::post().to(validate_handler))
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/scrape/validate")
+            .set_json(serde_json::json!({
+                "urls": [server.url("/ok"), server.url("/missing"), "http://127.0.0.1:1/"]
+            }))
+            .to_request();
+        let response: ValidateResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.results.len(), 3);
+
+        let ok = &response.results[0];
+        assert!(ok.reachable);
+        assert!(ok.is_html);
+        assert_eq!(ok.status_code, Some(200));
+        assert_eq!(ok.final_url.as_deref(), Some(server.url("/ok").as_str()));
+
+        let missing = &response.results[1];
+        assert!(!missing.reachable);
+        assert_eq!(missing.status_code, Some(404));
+
+        let unreachable = &response.results[2];
+        assert!(!unreachable.reachable);
+        assert!(unreachable.status_code.is_none());
+        assert!(unreachable.error.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_validate_respects_concurrency_cap() {
+        let delay = std::time::Duration::from_millis(150);
+        let server = MockServer::start(move |_| {
+            MockResponse::html("<html><body>slow</body></html>").with_delay(delay)
+        }).await;
+
+        // Every URL shares a host, so lift the host pacing to measure the batch cap alone
+        let state = AppState { rate_limiter: Arc::new(DomainRateLimiter::new(0.0)), ..test_state() };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .route("/api/scrape/validate", web::post().to(validate_handler))
+        ).await;
+
+        let urls: Vec<String> = (0..8).map(|i| server.url(&format!("/page/{}", i))).collect();
+        let req = test::TestRequest::post()
+            .uri("/api/scrape/validate")
+            .set_json(serde_json::json!({ "urls": urls, "concurrency": 2 }))
+            .to_request();
+        let response: ValidateResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(response.results.len(), 8);
+        assert!(response.results.iter().all(|r| r.reachable));
+
+        assert_eq!(server.requests().len(), 8);
+        server.assert_max_in_flight(2, delay);
+    }
+
+    #[actix_web::test]
+    async fn test_direct_fetches_share_the_host_limiter() {
+        let server = MockServer::start(|_| {
+            MockResponse::html(r#"<html><body><h1 class="title">Hello</h1></body></html>"#)
+        }).await;
+
+        let state = web::Data::new(AppState { rate_limiter: Arc::new(DomainRateLimiter::new(5.0)), ..test_state() });
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/api/scrape/validate", web::post().to(validate_handler))
+                .route("/api/selectors/test", web::post().to(selector_test_handler))
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/scrape/validate")
+            .set_json(serde_json::json!({ "urls": [server.url("/a")] }))
+            .to_request();
+        let response: ValidateResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(response.results[0].reachable);
+
+        let req = test::TestRequest::post()
+            .uri("/api/selectors/test")
+            .set_json(serde_json::json!({ "url": server.url("/b"), "selector": ".title" }))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let gap = requests[1].received - requests[0].received;
+        assert!(gap >= std::time::Duration::from_millis(150), "second fetch came after {:?}", gap);
+    }
+
+    #[actix_web::test]
+    async fn test_selector_against_live_page() {
+        let server = MockServer::start(|_| {
+            MockResponse::html(r#"<html><body><ul>
+                <li class="item"><b>First</b>   item</li>
+                <li class="item">Second item</li>
+                <li class="item">Third item</li>
+            </ul></body></html>"#)
+        }).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_state()))
+                .route("/api/selectors/test", web::post().to(selector_test_handler))
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/selectors/test")
+            .set_json(serde_json::json!({ "url": server.url("/"), "selector": "li.item" }))
+            .to_request();
+        let response: SelectorTestResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.match_count, 3);
+        assert_eq!(response.matches[0].text, "First item");
+        assert!(response.matches[0].html.starts_with("<li class=\"item\">"));
+
+        let req = test::TestRequest::post()
+            .uri("/api/selectors/test")
+            .set_json(serde_json::json!({ "url": server.url("/"), "selector": "li[" }))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_infer_selector_from_visible_text() {
+        let page = r#"<html><body>
+            <div class="card"><h2>Kettle</h2><span class="price">$25</span></div>
+            <div class="card"><h2>Toaster</h2><span class="price">$40 <small>incl. VAT</small></span></div>
+        </body></html>"#;
+        let server = MockServer::start(move |_| MockResponse::html(page)).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_state()))
+                .route("/api/selectors/infer", web::post().to(selector_infer_handler))
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/selectors/infer")
+            .set_json(serde_json::json!({ "url": server.url("/"), "text": "$40  incl." }))
+            .to_request();
+        let response: SelectorInferResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(response.element_text, "$40 incl. VAT");
+
+        let document = scraper::Html::parse_document(page);
+        let selector = scraper::Selector::parse(&response.selector).unwrap();
+        let matches: Vec<String> = document.select(&selector).map(|e| e.text().collect()).collect();
+        assert_eq!(matches, vec!["$40 incl. VAT".to_string()]);
+    }
+
+    #[actix_web::test]
+    async fn test_scrape_analyze_fetches_once() {
+        let server = MockServer::start(|_| MockResponse::html(&format!(
+            "<html><head><title>Combined</title></head><body><nav><a href='/'>Home</a></nav>\
+             <article class='story'><h1>Combined</h1>{}</article></body></html>",
+            format!("<p>{}</p>", "A paragraph of story text for both extraction and analysis. ".repeat(6)).repeat(4)
+        ))).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_state()))
+                .route("/api/scrape-analyze", web::post().to(scrape_analyze_handler))
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/scrape-analyze")
+            .set_json(serde_json::json!({ "url": server.url("/") }))
+            .to_request();
+        let response: ScrapeAnalyzeResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(server.hits("/"), 1);
+        assert_eq!(response.content.title.as_deref(), Some("Combined"));
+        assert!(!response.content.content.is_empty());
+        assert!(!response.analysis.sections.is_empty());
+
+        let best = response.analysis.recommendations.best_main_content.clone().unwrap();
+        assert_eq!(response.recommendation.selectors.content[0], best);
+    }
+
+    #[actix_web::test]
+    async fn test_diff_endpoint_reports_content_changes() {
+        let app = test::init_service(App::new().route("/api/diff", web::post().to(diff_handler))).await;
+
+        let mut previous = page("https://news.example/story", "2026-10-15T08:00:00Z", 0);
+        previous.content.title = Some("Storm warning".to_string());
+        previous.content.content = vec!["Heavy rain expected.".to_string()];
+        let mut current = previous.clone();
+        current.content.title = Some("Storm warning (updated)".to_string());
+        current.content.content.push("Update: roads closed.".to_string());
+
+        let req = test::TestRequest::post()
+            .uri("/api/diff")
+            .set_json(serde_json::json!({ "previous": previous, "current": current }))
+            .to_request();
+        let response: DiffResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(response.success);
+        let diff = response.diff.unwrap();
+        assert_eq!(diff.added, vec!["Update: roads closed.".to_string()]);
+        assert!(diff.title_changed.is_some());
+
+        current.url = "https://news.example/other".to_string();
+        let req = test::TestRequest::post()
+            .uri("/api/diff")
+            .set_json(serde_json::json!({ "previous": previous, "current": current }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,6 +1,24 @@
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use crate::json_ld;
+use crate::processors::ContentProcessor;
+use crate::utils::{cached_selector, normalize_whitespace, rewrite_urls, sanitize_html, WhitespacePolicy};
+
+// "of 250 items" is a listing total; a bare "3 items" may be a cart, so only
+// "results"/"matches" count without "of"
+static TOTAL_ITEMS_MARKER: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"(?i)\bof\s+(\d{1,3}(?:,\d{3})+|\d+)\s+(?:results|items|products|listings|matches)\b|\b(\d{1,3}(?:,\d{3})+|\d+)\s+(?:results|matches)\b",
+    ).unwrap()
+});
+
+// "4.5 out of 5" in a star widget's label
+static RATING_LABEL: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"(\d+(?:\.\d+)?)\s*out of\s*(\d+(?:\.\d+)?)").unwrap());
 
 /// Automatic selector detection with intelligent heuristics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +28,16 @@ pub struct AutoSelectors {
     pub links: Vec<String>,
     pub images: Vec<String>,
     pub metadata: Vec<String>,
+    /// Question/answer containers: `dl` lists and `details` blocks
+    #[serde(default = "default_faq_selectors")]
+    pub faqs: Vec<String>,
+}
+
+fn default_faq_selectors() -> Vec<String> {
+    vec![
+        "dl".to_string(),
+        "details".to_string(),
+    ]
 }
 
 impl Default for AutoSelectors {
@@ -48,17 +76,132 @@ impl Default for AutoSelectors {
                 "meta[name='keywords']".to_string(),
                 "meta[name='author']".to_string(),
             ],
+            faqs: default_faq_selectors(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DetectedContent {
     pub title: Option<String>,
     pub content: Vec<String>,
     pub links: Vec<LinkData>,
     pub images: Vec<ImageData>,
     pub metadata: HashMap<String, String>,
+    /// HTML of the main content element, with links made absolute and unsafe markup removed
+    #[serde(default)]
+    pub main_content_html: Option<String>,
+    /// The article as one string: the main content element's headings, paragraphs
+    /// and list items (else all content blocks), separated by blank lines
+    #[serde(default)]
+    pub article_text: String,
+    /// `<link rel="canonical">` target, made absolute
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    /// `<link rel="amphtml">` target, usually a lighter boilerplate-free copy
+    #[serde(default)]
+    pub amp_url: Option<String>,
+    /// `<link rel="alternate" media="...">` target for handheld screens
+    #[serde(default)]
+    pub mobile_url: Option<String>,
+    /// Question/answer pairs from definition lists, `<details>` and FAQPage JSON-LD
+    #[serde(default)]
+    pub faqs: Vec<(String, String)>,
+    #[serde(default)]
+    pub rating: Option<RatingData>,
+    /// schema.org Recipe or HowTo data, else `.ingredients`/`.instructions` lists
+    #[serde(default)]
+    pub recipe: Option<RecipeData>,
+    /// Prices from JSON-LD offers, `itemprop="price"` and `product:price` meta tags
+    #[serde(default)]
+    pub prices: Vec<PriceData>,
+    /// Translations from `<link rel="alternate" hreflang>`: (language, url)
+    #[serde(default)]
+    pub alternates: Vec<(String, String)>,
+    /// The `hreflang="x-default"` fallback page
+    #[serde(default)]
+    pub x_default: Option<String>,
+    /// Whether the page looks gated behind a paywall or login
+    #[serde(default)]
+    pub access: AccessState,
+    /// Content hit `max_content_blocks` or a block was cut at `max_block_chars`
+    #[serde(default)]
+    pub content_truncated: bool,
+    /// Words across all content blocks
+    #[serde(default)]
+    pub word_count: usize,
+    /// Characters across all content blocks
+    #[serde(default)]
+    pub char_count: usize,
+    /// Best icon advertised by the page, else the site's `/favicon.ico`
+    #[serde(default)]
+    pub site_icon: Option<String>,
+    /// `<meta name="theme-color">` value
+    #[serde(default)]
+    pub theme_color: Option<String>,
+    /// Declared page language (`<html lang>`, `Content-Language` or `og:locale`), lowercased
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Platform ("twitter", "github", ...) -> first profile link to it on the page
+    #[serde(default)]
+    pub social_links: HashMap<String, String>,
+    /// Size of the whole listing, from ItemList `numberOfItems` JSON-LD or a
+    /// "of 1,234 results" style marker
+    #[serde(default)]
+    pub total_items: Option<usize>,
+    /// Entries in the `itemListElement` of an ItemList declaring `numberOfItems`:
+    /// the part of the listing on this page
+    #[serde(default)]
+    pub listed_items: Option<usize>,
+    /// Served as a normal page but looks like an error page ("Page not found")
+    #[serde(default)]
+    pub soft_404: bool,
+    /// Product attributes ("Weight" -> "1.2 kg") from definition lists and
+    /// two-column spec tables
+    #[serde(default)]
+    pub specs: HashMap<String, String>,
+}
+
+/// Whether the full page content was served, or only a teaser in front of a wall
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessState {
+    #[default]
+    Open,
+    LikelyPaywalled,
+    LoginRequired,
+}
+
+/// A price found on the page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceData {
+    pub amount: f64,
+    /// ISO 4217 code, uppercased, when the page states one
+    pub currency: Option<String>,
+    /// Where it was found: "json_ld", "microdata" or "meta"
+    pub source: String,
+}
+
+/// Ingredients and steps of a recipe or how-to guide. Times are kept as published,
+/// usually ISO 8601 durations like `PT30M`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecipeData {
+    pub name: Option<String>,
+    /// Recipe ingredients, or a how-to's supplies
+    pub ingredients: Vec<String>,
+    pub steps: Vec<String>,
+    pub prep_time: Option<String>,
+    pub cook_time: Option<String>,
+    pub total_time: Option<String>,
+    pub recipe_yield: Option<String>,
+}
+
+/// Aggregate rating of the page's subject, e.g. 4.6 out of 5 from 89 reviews
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RatingData {
+    pub value: f64,
+    pub count: Option<u64>,
+    pub scale: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,82 +218,309 @@ pub struct ImageData {
     pub title: Option<String>,
 }
 
+/// How the page title is chosen among the title selectors' matches
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitlePolicy {
+    /// First non-empty match in selector order
+    FirstMatch,
+    /// Score every candidate and keep the best: favors og:title and h1 of a
+    /// sensible length over `<title>` with a site suffix, all-caps or tiny titles
+    #[default]
+    BestCandidate,
+}
+
+/// Extracted content blocks, in selector order
+#[derive(Default)]
+struct ContentBlocks {
+    text: Vec<String>,
+    /// Per block: whether it lies inside another extracted block
+    /// (a `<p>` of an extracted `<article>`)
+    nested: Vec<bool>,
+    truncated: bool,
+}
+
 pub struct SelectorDetector {
     selectors: AutoSelectors,
+    whitespace: WhitespacePolicy,
+    title_policy: TitlePolicy,
+    processors: Vec<Arc<dyn ContentProcessor>>,
+    noscript_fallback: bool,
+    max_content_blocks: usize,
+    max_block_chars: usize,
 }
 
+pub const DEFAULT_MAX_CONTENT_BLOCKS: usize = 2_000;
+pub const DEFAULT_MAX_BLOCK_CHARS: usize = 50_000;
+
+pub fn default_max_content_blocks() -> usize { DEFAULT_MAX_CONTENT_BLOCKS }
+pub fn default_max_block_chars() -> usize { DEFAULT_MAX_BLOCK_CHARS }
+
 impl SelectorDetector {
     pub fn new() -> Self {
+        Self::with_custom_selectors(AutoSelectors::default())
+    }
+
+    pub fn with_custom_selectors(selectors: AutoSelectors) -> Self {
         Self {
-            selectors: AutoSelectors::default(),
+            selectors,
+            whitespace: WhitespacePolicy::default(),
+            title_policy: TitlePolicy::default(),
+            processors: Vec::new(),
+            noscript_fallback: false,
+            max_content_blocks: DEFAULT_MAX_CONTENT_BLOCKS,
+            max_block_chars: DEFAULT_MAX_BLOCK_CHARS,
         }
     }
 
-    pub fn with_custom_selectors(selectors: AutoSelectors) -> Self {
-        Self { selectors }
+    pub fn with_title_policy(mut self, policy: TitlePolicy) -> Self {
+        self.title_policy = policy;
+        self
+    }
+
+    pub fn with_whitespace_policy(mut self, policy: WhitespacePolicy) -> Self {
+        self.whitespace = policy;
+        self
+    }
+
+    /// Run these processors, in order, over every extracted content block
+    pub fn with_processors(mut self, processors: Vec<Arc<dyn ContentProcessor>>) -> Self {
+        self.processors = processors;
+        self
+    }
+
+    /// When the visible content is sparse (a JavaScript-rendered shell), also
+    /// extract content from the page's `<noscript>` fallbacks
+    pub fn with_noscript_fallback(mut self, enabled: bool) -> Self {
+        self.noscript_fallback = enabled;
+        self
+    }
+
+    /// Keep at most `max_blocks` content blocks of at most `max_chars` characters
+    /// each, so pathological pages can't blow up memory
+    pub fn with_content_limits(mut self, max_blocks: usize, max_chars: usize) -> Self {
+        self.max_content_blocks = max_blocks;
+        self.max_block_chars = max_chars;
+        self
     }
 
     /// Detect and extract content from HTML using intelligent heuristics
     pub fn detect(&self, html: &str, base_url: &str) -> DetectedContent {
-        let document = Html::parse_document(html);
+        self.detect_document(&Html::parse_document(html), base_url)
+    }
+
+    /// `detect` on an already parsed document
+    pub fn detect_document(&self, document: &Html, base_url: &str) -> DetectedContent {
+        let (alternates, x_default) = self.detect_alternates(document, base_url);
+        let ContentBlocks { text: mut content, mut nested, truncated: content_truncated } = self.detect_content(document);
+        let sparse = outer_blocks(&content, &nested).map(|block| block.split_whitespace().count()).sum::<usize>()
+            < SPARSE_CONTENT_WORDS;
+        if self.noscript_fallback && sparse {
+            let fallback = self.detect_noscript_content(document);
+            for (block, is_nested) in fallback.text.into_iter().zip(fallback.nested) {
+                if !content.contains(&block) {
+                    content.push(block);
+                    nested.push(is_nested);
+                }
+            }
+        }
+        let word_count = outer_blocks(&content, &nested).map(|block| block.split_whitespace().count()).sum();
+        let char_count = outer_blocks(&content, &nested).map(|block| block.chars().count()).sum();
+        let links = self.detect_links(document, base_url);
+        let article_text = self.detect_article_text(document, &content);
+        let title = self.detect_title(document);
+        let soft_404 = is_soft_404(title.as_deref(), &content, word_count);
+        // Parsed once, shared by every detector that reads structured data
+        let structured = json_ld::items(document);
 
         DetectedContent {
-            title: self.detect_title(&document),
-            content: self.detect_content(&document),
-            links: self.detect_links(&document, base_url),
-            images: self.detect_images(&document, base_url),
-            metadata: self.detect_metadata(&document),
+            title,
+            soft_404,
+            access: self.detect_access(document, &structured, word_count),
+            content,
+            social_links: social_links(&links),
+            links,
+            images: self.detect_images(document, base_url),
+            metadata: self.detect_metadata(document),
+            main_content_html: self.detect_main_content_html(document, base_url),
+            article_text,
+            canonical_url: self.detect_canonical_url(document, base_url),
+            amp_url: self.detect_amp_url(document, base_url),
+            mobile_url: self.detect_mobile_url(document, base_url),
+            faqs: self.detect_faqs(document, &structured),
+            specs: self.detect_specs(document),
+            rating: self.detect_rating(document, &structured),
+            recipe: self.detect_recipe(document, &structured),
+            prices: self.detect_prices(document, &structured),
+            alternates,
+            x_default,
+            content_truncated,
+            word_count,
+            char_count,
+            language: self.detect_language(document),
+            site_icon: self.detect_site_icon(document, base_url),
+            theme_color: self.detect_theme_color(document),
+            total_items: self.detect_total_items(document, &structured),
+            listed_items: self.detect_listed_items(&structured),
         }
     }
 
     fn detect_title(&self, document: &Html) -> Option<String> {
+        let mut candidates = Vec::new();
+
         for selector_str in &self.selectors.title {
-            if let Ok(selector) = Selector::parse(selector_str) {
+            if let Some(selector) = cached_selector(selector_str) {
                 if let Some(element) = document.select(&selector).next() {
                     let text = if selector_str.starts_with("meta") {
                         // For meta tags, get the content attribute
                         element.value().attr("content")
-                            .map(|s| s.to_string())
+                            .map(|s| normalize_whitespace(s, self.whitespace))
                     } else {
                         // For regular elements, get text content
                         let text: String = element.text().collect();
-                        if text.trim().is_empty() {
-                            None
-                        } else {
-                            Some(text.trim().to_string())
-                        }
+                        Some(normalize_whitespace(&text, self.whitespace))
                     };
 
-                    if let Some(t) = text {
-                        if !t.is_empty() {
+                    if let Some(t) = text.filter(|t| !t.is_empty()) {
+                        if self.title_policy == TitlePolicy::FirstMatch {
                             return Some(t);
                         }
+                        candidates.push((title_quality(selector_str, &t), t));
                     }
                 }
             }
         }
-        None
+
+        // Ties go to the earlier selector
+        candidates.into_iter()
+            .rev()
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, title)| title)
     }
 
-    fn detect_content(&self, document: &Html) -> Vec<String> {
+    /// Content blocks, and whether the block or size limits cut any of them
+    fn detect_content(&self, document: &Html) -> ContentBlocks {
         let mut content = Vec::new();
+        let mut elements = Vec::new();
         let mut seen = std::collections::HashSet::new();
+        let mut truncated = false;
+        // Raw text is mostly whitespace on bloated pages; leave room for it to collapse
+        let raw_limit = self.max_block_chars.saturating_mul(4);
 
-        for selector_str in &self.selectors.content {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                for element in document.select(&selector) {
-                    let text: String = element.text().collect();
-                    let trimmed = text.trim();
+        'selectors: for selector_str in &self.selectors.content {
+            if let Some(selector) = cached_selector(selector_str) {
+                for element in document.select(&selector).filter(|e| !is_hidden(*e)) {
+                    let text = visible_text_capped(element, raw_limit);
+                    let mut text = self.processors.iter().fold(
+                        normalize_whitespace(&text, self.whitespace),
+                        |text, processor| processor.process(&text),
+                    );
+
+                    if let Some((cut, _)) = text.char_indices().nth(self.max_block_chars) {
+                        text.truncate(cut);
+                        truncated = true;
+                    }
 
                     // Skip empty, duplicate, or too short content
-                    if !trimmed.is_empty() && trimmed.len() > 10 && seen.insert(trimmed.to_string()) {
-                        content.push(trimmed.to_string());
+                    if !text.is_empty() && text.len() > 10 && seen.insert(text.clone()) {
+                        if content.len() == self.max_content_blocks {
+                            truncated = true;
+                            break 'selectors;
+                        }
+                        content.push(text);
+                        elements.push(element);
+                    }
+                }
+            }
+        }
+
+        let kept: std::collections::HashSet<_> = elements.iter().map(|element| element.id()).collect();
+        let nested = elements.iter()
+            .map(|element| element.ancestors().any(|ancestor| kept.contains(&ancestor.id())))
+            .collect();
+
+        ContentBlocks { text: content, nested, truncated }
+    }
+
+    /// Content of `<noscript>` fallbacks. With scripting enabled the parser keeps
+    /// their markup as raw text, so it is parsed again as a fragment.
+    fn detect_noscript_content(&self, document: &Html) -> ContentBlocks {
+        let mut fallback = ContentBlocks::default();
+        let Some(selector) = cached_selector("noscript") else {
+            return fallback;
+        };
+
+        for noscript in document.select(&selector) {
+            let markup = if noscript.children().any(|child| child.value().is_element()) {
+                noscript.inner_html()
+            } else {
+                noscript.text().collect()
+            };
+            let blocks = self.detect_content(&Html::parse_fragment(&markup));
+            fallback.text.extend(blocks.text);
+            fallback.nested.extend(blocks.nested);
+        }
+
+        fallback
+    }
+
+    /// First content element with real text, in content-selector priority order,
+    /// sanitized so it is safe to render
+    fn detect_main_content_html(&self, document: &Html, base_url: &str) -> Option<String> {
+        self.main_content_element(document)
+            .map(|element| sanitize_html(&rewrite_urls(&element.html(), base_url)))
+    }
+
+    fn main_content_element<'a>(&self, document: &'a Html) -> Option<ElementRef<'a>> {
+        for selector_str in &self.selectors.content {
+            if let Some(selector) = cached_selector(selector_str) {
+                for element in document.select(&selector).filter(|e| !is_hidden(*e)) {
+                    if visible_text(element).trim().len() > 10 {
+                        return Some(element);
                     }
                 }
             }
         }
 
-        content
+        None
+    }
+
+    /// Text blocks of the main content container in document order. When the page
+    /// has no container (its "main content" is a lone paragraph), the content blocks.
+    fn detect_article_text(&self, document: &Html, content: &[String]) -> String {
+        const BLOCKS: &str = "h1, h2, h3, h4, h5, h6, p, li, blockquote, pre";
+        let is_block = |element: ElementRef| {
+            matches!(element.value().name(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "li" | "blockquote" | "pre")
+        };
+
+        let container = self.main_content_element(document).filter(|element| !is_block(*element));
+        let (Some(container), Some(selector)) = (container, cached_selector(BLOCKS)) else {
+            return content.join("\n\n");
+        };
+
+        let blocks: Vec<String> = container.select(&selector)
+            .filter(|element| !is_hidden(*element))
+            // A paragraph inside a list item or quote is already part of that block
+            .filter(|element| {
+                !element.ancestors()
+                    .take_while(|ancestor| ancestor.id() != container.id())
+                    .filter_map(ElementRef::wrap)
+                    .any(is_block)
+            })
+            .map(|element| normalize_whitespace(&visible_text(element), self.whitespace))
+            .filter(|text| !text.is_empty())
+            .collect();
+
+        if blocks.is_empty() {
+            normalize_whitespace(&visible_text(container), self.whitespace)
+        } else {
+            blocks.join("\n\n")
+        }
+    }
+
+    /// Only the links of a page, skipping the rest of the extraction
+    pub fn detect_links_only(&self, html: &str, base_url: &str) -> Vec<LinkData> {
+        self.detect_links(&Html::parse_document(html), base_url)
     }
 
     fn detect_links(&self, document: &Html, base_url: &str) -> Vec<LinkData> {
@@ -158,15 +528,23 @@ impl SelectorDetector {
         let mut seen = std::collections::HashSet::new();
 
         for selector_str in &self.selectors.links {
-            if let Ok(selector) = Selector::parse(selector_str) {
+            if let Some(selector) = cached_selector(selector_str) {
                 for element in document.select(&selector) {
-                    if let Some(href) = element.value().attr("href") {
+                    if let Some(href) = element.value().attr("href").map(str::trim) {
+                        if is_inert_href(href) {
+                            continue;
+                        }
+
                         let text: String = element.text().collect();
                         let text = text.trim();
 
                         // Resolve relative URLs
                         let absolute_url = if href.starts_with("http://") || href.starts_with("https://") {
                             href.to_string()
+                        } else if href.starts_with("//") {
+                            // Protocol-relative: reuse the page's scheme
+                            let scheme = url::Url::parse(base_url).map(|b| b.scheme().to_string()).unwrap_or_else(|_| "https".to_string());
+                            format!("{}:{}", scheme, href)
                         } else if let Ok(base) = url::Url::parse(base_url) {
                             base.join(href).map(|u| u.to_string()).unwrap_or_else(|_| href.to_string())
                         } else {
@@ -201,7 +579,7 @@ impl SelectorDetector {
         let mut seen = std::collections::HashSet::new();
 
         for selector_str in &self.selectors.images {
-            if let Ok(selector) = Selector::parse(selector_str) {
+            if let Some(selector) = cached_selector(selector_str) {
                 for element in document.select(&selector) {
                     // Try both src and data-src attributes
                     let src = element.value().attr("src")
@@ -239,7 +617,7 @@ impl SelectorDetector {
         let mut metadata = HashMap::new();
 
         for selector_str in &self.selectors.metadata {
-            if let Ok(selector) = Selector::parse(selector_str) {
+            if let Some(selector) = cached_selector(selector_str) {
                 for element in document.select(&selector) {
                     if let Some(content) = element.value().attr("content") {
                         // Extract the metadata name/property
@@ -256,6 +634,731 @@ impl SelectorDetector {
 
         metadata
     }
+
+    fn detect_canonical_url(&self, document: &Html, base_url: &str) -> Option<String> {
+        let selector = cached_selector("link[rel='canonical'][href]")?;
+        document.select(&selector)
+            .filter_map(|element| element.value().attr("href"))
+            .map(|href| resolve_url(base_url, href))
+            .next()
+    }
+
+    fn detect_amp_url(&self, document: &Html, base_url: &str) -> Option<String> {
+        let selector = cached_selector("link[rel='amphtml'][href]")?;
+        document.select(&selector)
+            .filter_map(|element| element.value().attr("href"))
+            .map(|href| resolve_url(base_url, href))
+            .next()
+    }
+
+    fn detect_mobile_url(&self, document: &Html, base_url: &str) -> Option<String> {
+        let selector = cached_selector("link[rel='alternate'][media][href]")?;
+
+        for element in document.select(&selector) {
+            let media = element.value().attr("media").unwrap_or("").to_lowercase();
+
+            // Mobile alternates target narrow screens or handheld devices
+            if media.contains("max-width") || media.contains("handheld") {
+                if let Some(href) = element.value().attr("href") {
+                    return Some(resolve_url(base_url, href));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn detect_alternates(&self, document: &Html, base_url: &str) -> (Vec<(String, String)>, Option<String>) {
+        let mut alternates = Vec::new();
+        let mut x_default = None;
+
+        if let Some(selector) = cached_selector("link[rel='alternate'][hreflang][href]") {
+            for element in document.select(&selector) {
+                let lang = element.value().attr("hreflang").unwrap_or("").trim();
+                let href = element.value().attr("href").unwrap_or("");
+                let url = resolve_url(base_url, href);
+
+                if lang.eq_ignore_ascii_case("x-default") {
+                    x_default = Some(url);
+                } else if !lang.is_empty() {
+                    alternates.push((lang.to_string(), url));
+                }
+            }
+        }
+
+        (alternates, x_default)
+    }
+
+    /// Largest declared icon wins; apple-touch-icons without `sizes` count as 180px
+    fn detect_site_icon(&self, document: &Html, base_url: &str) -> Option<String> {
+        let mut best: Option<(u32, String)> = None;
+
+        if let Some(selector) = cached_selector("link[rel][href]") {
+            for element in document.select(&selector) {
+                let rel = element.value().attr("rel").unwrap_or("").to_lowercase();
+                let rels: Vec<&str> = rel.split_whitespace().collect();
+                let touch = rels.iter().any(|r| r.starts_with("apple-touch-icon"));
+                if !touch && !rels.contains(&"icon") {
+                    continue;
+                }
+
+                let declared = element.value().attr("sizes").and_then(icon_size);
+                let size = declared.unwrap_or(if touch { 180 } else { 0 });
+                if best.as_ref().is_none_or(|(best_size, _)| size > *best_size) {
+                    let href = element.value().attr("href").unwrap_or("");
+                    best = Some((size, resolve_url(base_url, href)));
+                }
+            }
+        }
+
+        best.map(|(_, url)| url).or_else(|| {
+            let base = url::Url::parse(base_url).ok()?;
+            matches!(base.scheme(), "http" | "https")
+                .then(|| base.join("/favicon.ico").ok())
+                .flatten()
+                .map(|u| u.to_string())
+        })
+    }
+
+    fn detect_theme_color(&self, document: &Html) -> Option<String> {
+        let selector = cached_selector("meta[name='theme-color'][content]")?;
+        document.select(&selector)
+            .filter_map(|element| element.value().attr("content"))
+            .map(|color| color.trim().to_string())
+            .find(|color| !color.is_empty())
+    }
+
+    fn detect_total_items(&self, document: &Html, structured: &[Value]) -> Option<usize> {
+        let declared = structured.iter()
+            .filter(|item| json_ld::has_type(item, "ItemList"))
+            .find_map(|item| json_ld::number(item.get("numberOfItems")))
+            .filter(|count| *count >= 0.0)
+            .map(|count| count as usize);
+        if declared.is_some() {
+            return declared;
+        }
+
+        let body = cached_selector("body")?;
+        let text: String = document.select(&body).next()?.text().collect::<Vec<_>>().join(" ");
+        let cap = TOTAL_ITEMS_MARKER.captures(&text)?;
+        cap.get(1).or_else(|| cap.get(2))?.as_str().replace(',', "").parse().ok()
+    }
+
+    fn detect_listed_items(&self, structured: &[Value]) -> Option<usize> {
+        structured.iter()
+            .find(|item| json_ld::has_type(item, "ItemList") && item.get("numberOfItems").is_some())
+            .map(|item| json_ld::values(item.get("itemListElement")).len())
+    }
+
+    fn detect_language(&self, document: &Html) -> Option<String> {
+        let sources = [
+            ("html[lang]", "lang"),
+            ("meta[http-equiv='Content-Language' i]", "content"),
+            ("meta[property='og:locale']", "content"),
+        ];
+
+        sources.iter()
+            .filter_map(|(selector_str, attr)| {
+                let selector = cached_selector(selector_str)?;
+                document.select(&selector).next()?.value().attr(attr)
+                    .and_then(|v| v.split(',').next())
+                    .map(|v| v.trim().to_string())
+            })
+            .find(|lang| !lang.is_empty())
+            .map(|lang| lang.replace('_', "-").to_lowercase())
+    }
+
+    fn detect_faqs(&self, document: &Html, structured: &[Value]) -> Vec<(String, String)> {
+        let mut faqs = Vec::new();
+
+        for selector_str in &self.selectors.faqs {
+            if let Some(selector) = cached_selector(selector_str) {
+                for element in document.select(&selector) {
+                    match element.value().name() {
+                        "dl" => faqs.extend(definition_pairs(element)),
+                        "details" => faqs.extend(details_pair(element)),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // schema.org FAQPage: mainEntity -> Question { name, acceptedAnswer { text } }
+        for item in structured {
+            if !json_ld::has_type(item, "FAQPage") {
+                continue;
+            }
+
+            for question in json_ld::values(item.get("mainEntity")) {
+                let name = json_ld::text(question.get("name"));
+                let answer = json_ld::values(question.get("acceptedAnswer"))
+                    .into_iter()
+                    .find_map(|a| json_ld::text(a.get("text")));
+
+                if let (Some(q), Some(a)) = (name, answer) {
+                    faqs.push((q, a));
+                }
+            }
+        }
+
+        faqs
+    }
+
+    /// Key/value pairs from `<dl>` lists (except FAQ-style questions) and from
+    /// tables whose every row is one label cell and one value cell. The first
+    /// value seen for a key wins.
+    fn detect_specs(&self, document: &Html) -> HashMap<String, String> {
+        let mut specs = HashMap::new();
+        let mut add = |key: &str, value: &str| {
+            let key = normalize_whitespace(key, WhitespacePolicy::Collapse);
+            let key = key.trim_end_matches(':').trim_end().to_string();
+            let value = normalize_whitespace(value, WhitespacePolicy::Collapse);
+            if !key.is_empty() && !value.is_empty() && !key.ends_with('?') && value.chars().count() <= MAX_SPEC_VALUE_CHARS {
+                specs.entry(key).or_insert(value);
+            }
+        };
+
+        if let Some(selector) = cached_selector("dl") {
+            for list in document.select(&selector) {
+                for (key, value) in definition_pairs(list) {
+                    add(&key, &value);
+                }
+            }
+        }
+
+        let (Some(tables), Some(rows), Some(cells)) =
+            (cached_selector("table"), cached_selector("tr"), cached_selector("th, td"))
+        else {
+            return specs;
+        };
+        for table in document.select(&tables) {
+            let pairs: Vec<Vec<String>> = table.select(&rows)
+                .map(|row| row.select(&cells).map(|cell| cell.text().collect()).collect())
+                .collect();
+            if pairs.is_empty() || pairs.iter().any(|cells| cells.len() != 2) {
+                continue;
+            }
+            for cells in &pairs {
+                add(&cells[0], &cells[1]);
+            }
+        }
+
+        specs
+    }
+
+    /// Infer a paywall or login wall from explicit markers, or from thin content
+    /// next to a subscribe call-to-action or a password form
+    fn detect_access(&self, document: &Html, structured: &[Value], word_count: usize) -> AccessState {
+        const THIN_CONTENT_WORDS: usize = 150;
+
+        let matches = |selector: &str| {
+            cached_selector(selector)
+                .map(|s| document.select(&s).next().is_some())
+                .unwrap_or(false)
+        };
+
+        // Publishers mark gated articles for search engines with isAccessibleForFree
+        let gated_json_ld = structured.iter().any(|item| {
+            item.get("isAccessibleForFree").is_some_and(|v| {
+                v.as_bool() == Some(false) || v.as_str().is_some_and(|s| s.eq_ignore_ascii_case("false"))
+            })
+        });
+
+        if gated_json_ld || matches(".paywall, #paywall, [class*='paywall'], [data-paywall], .subscriber-only, .premium-content") {
+            return AccessState::LikelyPaywalled;
+        }
+
+        let thin = word_count < THIN_CONTENT_WORDS;
+
+        if thin && matches("form input[type='password']") {
+            return AccessState::LoginRequired;
+        }
+
+        let subscribe_cta = cached_selector("a, button").is_some_and(|selector| {
+            document.select(&selector).any(|element| {
+                let text = element.text().collect::<String>().to_lowercase();
+                text.contains("subscribe") || text.contains("subscription")
+            })
+        });
+        let noarchive = matches("meta[name='robots'][content*='noarchive']");
+
+        if subscribe_cta && (thin || noarchive) {
+            return AccessState::LikelyPaywalled;
+        }
+
+        AccessState::Open
+    }
+
+    /// Aggregate rating from JSON-LD, then microdata, then star widgets' aria labels
+    fn detect_rating(&self, document: &Html, structured: &[Value]) -> Option<RatingData> {
+        for item in structured {
+            let rating = if json_ld::has_type(item, "AggregateRating") {
+                Some(item)
+            } else {
+                item.get("aggregateRating")
+            };
+
+            if let Some(rating) = rating {
+                if let Some(value) = json_ld::number(rating.get("ratingValue")) {
+                    let count = json_ld::number(rating.get("reviewCount"))
+                        .or_else(|| json_ld::number(rating.get("ratingCount")));
+
+                    return Some(RatingData {
+                        value,
+                        count: count.map(|c| c as u64),
+                        scale: json_ld::number(rating.get("bestRating")).unwrap_or(5.0),
+                    });
+                }
+            }
+        }
+
+        if let Some(selector) = cached_selector("[itemprop='aggregateRating']") {
+            if let Some(element) = document.select(&selector).next() {
+                let value = itemprop_number(element, "ratingValue");
+                if let Some(value) = value {
+                    let count = itemprop_number(element, "reviewCount")
+                        .or_else(|| itemprop_number(element, "ratingCount"));
+
+                    return Some(RatingData {
+                        value,
+                        count: count.map(|c| c as u64),
+                        scale: itemprop_number(element, "bestRating").unwrap_or(5.0),
+                    });
+                }
+            }
+        }
+
+        // Star widgets: aria-label="4.5 out of 5 stars"
+        let selector = cached_selector("[aria-label*='out of'], .stars[aria-label], .rating[aria-label]")?;
+        for element in document.select(&selector) {
+            let label = element.value().attr("aria-label").unwrap_or("");
+            if let Some(captures) = RATING_LABEL.captures(label) {
+                if let (Ok(value), Ok(scale)) = (captures[1].parse(), captures[2].parse()) {
+                    return Some(RatingData { value, count: None, scale });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Recipe from JSON-LD, then microdata, then common ingredient/instruction list classes
+    fn detect_recipe(&self, document: &Html, structured: &[Value]) -> Option<RecipeData> {
+        for item in structured {
+            if !json_ld::has_type(item, "Recipe") && !json_ld::has_type(item, "HowTo") {
+                continue;
+            }
+
+            let mut steps = Vec::new();
+            for key in ["recipeInstructions", "step"] {
+                for step in json_ld::values(item.get(key)) {
+                    collect_steps(step, &mut steps);
+                }
+            }
+
+            let ingredients = ["recipeIngredient", "ingredients", "supply"].iter()
+                .flat_map(|key| json_ld::values(item.get(*key)))
+                .filter_map(|value| json_ld::text(Some(value)))
+                .collect();
+
+            return Some(RecipeData {
+                name: json_ld::text(item.get("name")),
+                ingredients,
+                steps,
+                prep_time: json_ld::text(item.get("prepTime")),
+                cook_time: json_ld::text(item.get("cookTime")),
+                total_time: json_ld::text(item.get("totalTime")),
+                recipe_yield: json_ld::text(item.get("recipeYield")).or_else(|| json_ld::text(item.get("yield"))),
+            });
+        }
+
+        let texts = |scope: ElementRef, selector: &str| -> Vec<String> {
+            cached_selector(selector)
+                .map(|s| {
+                    scope.select(&s)
+                        .map(|e| normalize_whitespace(&visible_text(e), self.whitespace))
+                        .filter(|text| !text.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        if let Some(selector) = cached_selector("[itemtype*='schema.org/Recipe'], [itemtype*='schema.org/HowTo']") {
+            if let Some(scope) = document.select(&selector).next() {
+                let property = |name: &str| itemprop_text(scope, name);
+                return Some(RecipeData {
+                    name: property("name"),
+                    ingredients: texts(scope, "[itemprop='recipeIngredient'], [itemprop='ingredients'], [itemprop='supply']"),
+                    steps: texts(scope, "[itemprop='recipeInstructions'], [itemprop='step']"),
+                    prep_time: property("prepTime"),
+                    cook_time: property("cookTime"),
+                    total_time: property("totalTime"),
+                    recipe_yield: property("recipeYield"),
+                });
+            }
+        }
+
+        let root = document.root_element();
+        let ingredients = texts(root, ".ingredients li, .recipe-ingredients li");
+        let steps = texts(root, ".instructions li, .recipe-instructions li, .directions li");
+        if ingredients.is_empty() && steps.is_empty() {
+            return None;
+        }
+
+        Some(RecipeData { ingredients, steps, ..RecipeData::default() })
+    }
+
+    /// Prices in JSON-LD, microdata and meta tag order; each amount and currency once
+    fn detect_prices(&self, document: &Html, structured: &[Value]) -> Vec<PriceData> {
+        let mut prices: Vec<PriceData> = Vec::new();
+        let mut push = |amount: Option<f64>, currency: Option<String>, source: &str| {
+            let Some(amount) = amount else { return };
+            let currency = currency.map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty());
+            if !prices.iter().any(|p| p.amount == amount && p.currency == currency) {
+                prices.push(PriceData { amount, currency, source: source.to_string() });
+            }
+        };
+
+        // Offer, or AggregateOffer with a low/high range, standalone or under `offers`
+        for item in structured {
+            let offers = if json_ld::has_type(item, "Offer") || json_ld::has_type(item, "AggregateOffer") {
+                vec![item]
+            } else {
+                json_ld::values(item.get("offers"))
+            };
+
+            for offer in offers {
+                let currency = json_ld::text(offer.get("priceCurrency"));
+                for key in ["price", "lowPrice", "highPrice"] {
+                    push(json_ld::number(offer.get(key)), currency.clone(), "json_ld");
+                }
+            }
+        }
+
+        if let Some(selector) = cached_selector("[itemprop='price']") {
+            for element in document.select(&selector) {
+                // `content` is machine-readable ("1000.00"); visible text is formatted for a locale
+                let amount = match element.value().attr("content") {
+                    Some(content) => content.trim().parse().ok(),
+                    None => parse_price(&element.text().collect::<String>()),
+                };
+
+                // The currency sits next to the price, inside the same item scope
+                let currency = element.ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .find(|ancestor| ancestor.value().attr("itemscope").is_some())
+                    .and_then(|scope| itemprop_text(scope, "priceCurrency"));
+
+                push(amount, currency, "microdata");
+            }
+        }
+
+        let meta = |property: &str| {
+            cached_selector(&format!("meta[property='{}'][content]", property))
+                .and_then(|selector| document.select(&selector).next())
+                .and_then(|element| element.value().attr("content").map(str::to_string))
+        };
+        for prefix in ["product:price", "og:price"] {
+            let amount = meta(&format!("{}:amount", prefix)).and_then(|raw| raw.trim().parse().ok());
+            push(amount, meta(&format!("{}:currency", prefix)), "meta");
+        }
+
+        prices
+    }
+}
+
+/// Amount from a displayed price such as "$1,299.00", "1.299,00 €" or "19,90".
+/// The last `.` or `,` is the decimal separator when at most two digits follow it,
+/// so this is only for visible text; machine-readable values are plain numbers.
+fn parse_price(raw: &str) -> Option<f64> {
+    let number: String = raw.chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | ' ' | '\u{a0}'))
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    let normalized = match number.rfind(['.', ',']) {
+        Some(separator) if number.len() - separator - 1 <= 2 => {
+            let (whole, fraction) = number.split_at(separator);
+            format!("{}.{}", whole.replace(['.', ','], ""), &fraction[1..])
+        }
+        _ => number.replace(['.', ','], ""),
+    };
+
+    normalized.parse().ok()
+}
+
+/// Instruction text from a plain string, a HowToStep, or a HowToSection of steps
+fn collect_steps(value: &serde_json::Value, steps: &mut Vec<String>) {
+    if let Some(elements) = value.get("itemListElement") {
+        for element in json_ld::values(Some(elements)) {
+            collect_steps(element, steps);
+        }
+        return;
+    }
+
+    let text = match value {
+        serde_json::Value::Object(map) => json_ld::text(map.get("text")).or_else(|| json_ld::text(map.get("name"))),
+        _ => json_ld::text(Some(value)),
+    };
+    steps.extend(text);
+}
+
+/// Hosts of each social platform, without `www.`/`m.` prefixes
+const SOCIAL_PLATFORMS: &[(&str, &[&str])] = &[
+    ("twitter", &["twitter.com", "x.com"]),
+    ("facebook", &["facebook.com", "fb.com"]),
+    ("instagram", &["instagram.com"]),
+    ("linkedin", &["linkedin.com"]),
+    ("youtube", &["youtube.com", "youtu.be"]),
+    ("github", &["github.com"]),
+];
+
+/// First path segments of share buttons and other non-profile pages
+const NON_PROFILE_PATHS: &[&str] = &["share", "sharer", "sharer.php", "intent", "shareArticle", "watch", "search", "login"];
+
+/// Profile links among `links`, keyed by platform
+fn social_links(links: &[LinkData]) -> HashMap<String, String> {
+    let mut profiles = HashMap::new();
+
+    for link in links {
+        let Ok(url) = url::Url::parse(&link.href) else { continue };
+        let Some(host) = url.host_str() else { continue };
+        let host = host.trim_start_matches("www.").trim_start_matches("m.");
+
+        let Some((platform, _)) = SOCIAL_PLATFORMS.iter().find(|(_, hosts)| hosts.contains(&host)) else {
+            continue;
+        };
+
+        let first_segment = url.path_segments()
+            .and_then(|mut segments| segments.next())
+            .unwrap_or("");
+        if first_segment.is_empty() || NON_PROFILE_PATHS.contains(&first_segment) {
+            continue;
+        }
+
+        profiles.entry(platform.to_string()).or_insert_with(|| link.href.clone());
+    }
+
+    profiles
+}
+
+/// Heuristic quality of a title candidate, given the selector that found it
+fn title_quality(selector: &str, title: &str) -> f64 {
+    let mut score = if selector.contains("og:title") {
+        1.0
+    } else if selector == "h1" {
+        0.9
+    } else if selector == "title" {
+        0.5
+    } else if selector == "h2" {
+        0.4
+    } else {
+        0.6
+    };
+
+    // Document titles usually carry a site suffix: "Article | Site"
+    if selector == "title" && [" | ", " - ", " – ", " — ", " :: "].iter().any(|sep| title.contains(sep)) {
+        score -= 0.2;
+    }
+
+    let length = title.chars().count();
+    if length < 10 || title.split_whitespace().count() < 2 {
+        score -= 0.4;
+    } else if length > 120 {
+        score -= 0.3;
+    }
+
+    let letters: Vec<char> = title.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() >= 4 && letters.iter().all(|c| c.is_uppercase()) {
+        score -= 0.3;
+    }
+
+    score
+}
+
+/// Numeric microdata property inside `scope`, from its `content` attribute or text
+fn itemprop_number(scope: ElementRef, property: &str) -> Option<f64> {
+    let selector = cached_selector(&format!("[itemprop='{}']", property))?;
+    let element = scope.select(&selector).next()?;
+
+    let raw = match element.value().attr("content") {
+        Some(content) => content.to_string(),
+        None => element.text().collect::<String>(),
+    };
+
+    raw.trim().replace(',', "").parse().ok()
+}
+
+fn itemprop_text(scope: ElementRef, property: &str) -> Option<String> {
+    let selector = cached_selector(&format!("[itemprop='{}']", property))?;
+    let element = scope.select(&selector).next()?;
+
+    let raw = element.value().attr("content")
+        .or_else(|| element.value().attr("datetime"))
+        .map(str::to_string)
+        .unwrap_or_else(|| element.text().collect());
+
+    let text = raw.trim().to_string();
+    if text.is_empty() { None } else { Some(text) }
+}
+
+/// Longer "values" are prose, not attributes
+const MAX_SPEC_VALUE_CHARS: usize = 200;
+
+/// Pair each `<dt>` with the `<dd>` that follows it
+fn definition_pairs(list: ElementRef) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let selector = match cached_selector("dt, dd") {
+        Some(s) => s,
+        None => return pairs,
+    };
+
+    let mut term: Option<String> = None;
+    for element in list.select(&selector) {
+        let text: String = element.text().collect();
+        let text = text.trim().to_string();
+
+        match element.value().name() {
+            "dt" => term = Some(text),
+            "dd" => {
+                if let Some(t) = term.take() {
+                    if !t.is_empty() && !text.is_empty() {
+                        pairs.push((t, text));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pairs
+}
+
+/// A `<details>` block's `<summary>` is the question, the remaining text the answer
+fn details_pair(details: ElementRef) -> Option<(String, String)> {
+    let selector = cached_selector("summary")?;
+    let summary = details.select(&selector).next()?;
+    let summary_text: String = summary.text().collect();
+
+    let full_text: String = details.text().collect();
+    let answer = full_text.replacen(&summary_text, "", 1).trim().to_string();
+    let question = summary_text.trim().to_string();
+
+    if question.is_empty() || answer.is_empty() {
+        None
+    } else {
+        Some((question, answer))
+    }
+}
+
+/// Below this many words the visible content looks like an unrendered app shell
+const SPARSE_CONTENT_WORDS: usize = 50;
+
+/// Phrases error pages use, matched lowercased against the title and content
+const SOFT_404_MARKERS: &[&str] = &["404", "not found", "page doesn't exist", "page doesn’t exist", "page does not exist"];
+
+/// Real pages that merely mention "not found" have more to say than this
+const SOFT_404_MAX_WORDS: usize = 150;
+
+/// An error page served with a 200 status: an error phrase in the title or
+/// content, and little else on the page
+fn is_soft_404(title: Option<&str>, content: &[String], word_count: usize) -> bool {
+    if word_count >= SOFT_404_MAX_WORDS {
+        return false;
+    }
+
+    let has_marker = |text: &str| {
+        let text = text.to_lowercase();
+        SOFT_404_MARKERS.iter().any(|marker| text.contains(marker))
+    };
+    title.is_some_and(has_marker) || content.iter().any(|block| has_marker(block))
+}
+
+/// Content blocks not inside another block; a nested block's text is already
+/// part of its container's, so counting it again would inflate totals
+fn outer_blocks<'a>(content: &'a [String], nested: &'a [bool]) -> impl Iterator<Item = &'a String> {
+    content.iter().zip(nested).filter(|(_, nested)| !**nested).map(|(block, _)| block)
+}
+
+/// Whether the element sits in a `<template>`, a `<noscript>` (whose markup is
+/// unparsed text) or a subtree hidden by `hidden` / inline style
+pub(crate) fn is_hidden(element: ElementRef) -> bool {
+    std::iter::once(element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .any(hides_subtree)
+}
+
+/// Text of the element, leaving out template and hidden descendants
+pub(crate) fn visible_text(element: ElementRef) -> String {
+    visible_text_capped(element, usize::MAX)
+}
+
+/// `visible_text`, no longer collected once it reaches `limit` bytes
+fn visible_text_capped(element: ElementRef, limit: usize) -> String {
+    let mut text = String::new();
+    if !is_hidden(element) {
+        collect_visible_text(element, &mut text, limit);
+    }
+    text
+}
+
+fn collect_visible_text(element: ElementRef, out: &mut String, limit: usize) {
+    for child in element.children() {
+        if out.len() >= limit {
+            return;
+        }
+        if let Some(text) = child.value().as_text() {
+            out.push_str(text);
+        } else if let Some(child) = ElementRef::wrap(child) {
+            if !hides_subtree(child) {
+                collect_visible_text(child, out, limit);
+            }
+        }
+    }
+}
+
+pub(crate) fn hides_subtree(element: ElementRef) -> bool {
+    let el = element.value();
+    if matches!(el.name(), "template" | "noscript") || el.attr("hidden").is_some() {
+        return true;
+    }
+
+    el.attr("style").is_some_and(|style| {
+        let style: String = style.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        style.split(';').any(|rule| rule == "display:none" || rule == "visibility:hidden")
+    })
+}
+
+/// Largest edge of an icon `sizes` attribute (`16x16 32x32`); `any` (SVG) beats every bitmap
+fn icon_size(sizes: &str) -> Option<u32> {
+    sizes.split_whitespace()
+        .filter_map(|size| {
+            if size.eq_ignore_ascii_case("any") {
+                return Some(u32::MAX);
+            }
+            let size = size.to_lowercase();
+            let (w, h) = size.split_once('x')?;
+            Some(w.parse::<u32>().ok()?.max(h.parse().ok()?))
+        })
+        .max()
+}
+
+/// Hrefs that run script or embed data instead of pointing at a page
+fn is_inert_href(href: &str) -> bool {
+    let scheme = href.split(':').next().unwrap_or("").to_ascii_lowercase();
+    href.contains(':') && matches!(scheme.as_str(), "javascript" | "data" | "blob" | "vbscript")
+}
+
+/// Resolve a possibly relative URL against the page URL
+fn resolve_url(base_url: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        href.to_string()
+    } else if let Ok(base) = url::Url::parse(base_url) {
+        base.join(href).map(|u| u.to_string()).unwrap_or_else(|_| href.to_string())
+    } else {
+        href.to_string()
+    }
 }
 
 impl Default for SelectorDetector {
@@ -297,4 +1400,495 @@ mod tests {
         assert!(!result.links.is_empty());
         assert!(!result.images.is_empty());
     }
+
+    #[test]
+    fn test_amp_and_mobile_detection() {
+        let html = r#"
+            <html>
+                <head>
+                    <link rel="amphtml" href="/article/amp">
+                    <link rel="alternate" media="only screen and (max-width: 640px)" href="https://m.example.com/article">
+                </head>
+                <body><p>Body</p></body>
+            </html>
+        "#;
+
+        let detector = SelectorDetector::new();
+        let result = detector.detect(html, "https://example.com/article");
+
+        assert_eq!(result.amp_url.as_deref(), Some("https://example.com/article/amp"));
+        assert_eq!(result.mobile_url.as_deref(), Some("https://m.example.com/article"));
+    }
+
+    #[test]
+    fn test_spec_extraction() {
+        let html = r#"
+            <html><body>
+                <h1>Trail Backpack</h1>
+                <dl class="specs">
+                    <dt>Weight:</dt><dd>1.2 kg</dd>
+                    <dt>Material</dt><dd>Recycled   nylon</dd>
+                    <dt>Dimensions</dt><dd>55 x 30 x 20 cm</dd>
+                </dl>
+                <table>
+                    <tr><th>Volume</th><td>28 L</td></tr>
+                    <tr><th>Weight</th><td>1.3 kg</td></tr>
+                </table>
+                <table>
+                    <tr><th>Size</th><th>Torso</th><th>Hip</th></tr>
+                    <tr><td>M</td><td>45 cm</td><td>80 cm</td></tr>
+                </table>
+                <dl><dt>Is it waterproof?</dt><dd>Water resistant.</dd></dl>
+            </body></html>
+        "#;
+
+        let specs = SelectorDetector::new().detect(html, "https://shop.example/backpack").specs;
+        assert_eq!(specs.get("Weight").map(String::as_str), Some("1.2 kg"));
+        assert_eq!(specs.get("Material").map(String::as_str), Some("Recycled nylon"));
+        assert_eq!(specs.get("Dimensions").map(String::as_str), Some("55 x 30 x 20 cm"));
+        assert_eq!(specs.get("Volume").map(String::as_str), Some("28 L"));
+        assert!(!specs.contains_key("Size") && !specs.contains_key("M"));
+        assert!(!specs.contains_key("Is it waterproof?"));
+        assert_eq!(specs.len(), 4);
+    }
+
+    #[test]
+    fn test_faq_extraction() {
+        let html = r#"
+            <html><body>
+                <dl>
+                    <dt>What is Rust?</dt>
+                    <dd>A systems programming language.</dd>
+                </dl>
+                <details>
+                    <summary>Is it fast?</summary>
+                    <p>Yes, comparable to C++.</p>
+                </details>
+            </body></html>
+        "#;
+
+        let detector = SelectorDetector::new();
+        let result = detector.detect(html, "https://example.com/faq");
+
+        assert_eq!(result.faqs, vec![
+            ("What is Rust?".to_string(), "A systems programming language.".to_string()),
+            ("Is it fast?".to_string(), "Yes, comparable to C++.".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_aggregate_rating_from_json_ld() {
+        let html = r#"
+            <html><head>
+                <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Walnut Desk",
+                    "aggregateRating": {
+                        "@type": "AggregateRating",
+                        "ratingValue": "4.6",
+                        "reviewCount": 89
+                    }
+                }
+                </script>
+            </head><body><h1>Walnut Desk</h1></body></html>
+        "#;
+
+        let detector = SelectorDetector::new();
+        let rating = detector.detect(html, "https://shop.example.com/desk").rating.unwrap();
+
+        assert_eq!(rating.value, 4.6);
+        assert_eq!(rating.count, Some(89));
+        assert_eq!(rating.scale, 5.0);
+    }
+
+    #[test]
+    fn test_recipe_from_json_ld() {
+        let html = r#"
+            <html><head>
+                <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "Recipe",
+                    "name": "Pancakes",
+                    "recipeYield": "4 servings",
+                    "totalTime": "PT20M",
+                    "recipeIngredient": ["2 eggs", "250 ml milk", "120 g flour"],
+                    "recipeInstructions": [
+                        {"@type": "HowToStep", "text": "Whisk the eggs and milk."},
+                        {"@type": "HowToSection", "name": "Cooking", "itemListElement": [
+                            {"@type": "HowToStep", "text": "Stir in the flour."},
+                            "Fry in a hot pan."
+                        ]}
+                    ]
+                }
+                </script>
+            </head><body><h1>Pancakes</h1></body></html>
+        "#;
+
+        let recipe = SelectorDetector::new().detect(html, "https://food.example.com/pancakes").recipe.unwrap();
+
+        assert_eq!(recipe.name.as_deref(), Some("Pancakes"));
+        assert_eq!(recipe.ingredients, vec!["2 eggs", "250 ml milk", "120 g flour"]);
+        assert_eq!(recipe.steps, vec!["Whisk the eggs and milk.", "Stir in the flour.", "Fry in a hot pan."]);
+        assert_eq!(recipe.total_time.as_deref(), Some("PT20M"));
+        assert_eq!(recipe.recipe_yield.as_deref(), Some("4 servings"));
+
+        let fallback = r#"<html><body>
+            <ul class="ingredients"><li>Salt</li></ul>
+            <ol class="instructions"><li>Season to taste.</li></ol>
+        </body></html>"#;
+        let recipe = SelectorDetector::new().detect(fallback, "https://food.example.com/").recipe.unwrap();
+        assert_eq!(recipe.ingredients, vec!["Salt"]);
+        assert_eq!(recipe.steps, vec!["Season to taste."]);
+    }
+
+    #[test]
+    fn test_social_links_by_platform() {
+        let html = r#"
+            <html><body>
+                <article><p>Post body</p><a href="https://twitter.com/intent/tweet?url=x">Share</a></article>
+                <footer>
+                    <a href="https://twitter.com/acme_inc">Twitter</a>
+                    <a href="https://www.github.com/acme">GitHub</a>
+                    <a href="/about">About</a>
+                </footer>
+            </body></html>
+        "#;
+
+        let result = SelectorDetector::new().detect(html, "https://acme.example.com/");
+
+        assert_eq!(result.social_links.len(), 2);
+        assert_eq!(result.social_links.get("twitter").map(String::as_str), Some("https://twitter.com/acme_inc"));
+        assert_eq!(result.social_links.get("github").map(String::as_str), Some("https://www.github.com/acme"));
+    }
+
+    #[test]
+    fn test_content_limits_bound_extraction() {
+        let paragraphs: String = (0..5_000)
+            .map(|i| format!("<p>Paragraph number {} with enough text to count.</p>", i))
+            .collect();
+        let html = format!("<html><body>{}<div class=\"content\">{}</div></body></html>", paragraphs, "word ".repeat(500));
+
+        let result = SelectorDetector::new()
+            .with_content_limits(100, 1_000)
+            .detect(&html, "https://example.com/");
+
+        assert!(result.content.len() <= 100);
+        assert!(result.content.iter().all(|block| block.chars().count() <= 1_000));
+        assert!(result.content_truncated);
+
+        let small = SelectorDetector::new().detect("<html><body><p>Just one short paragraph here.</p></body></html>", "https://example.com/");
+        assert!(!small.content_truncated);
+    }
+
+    #[test]
+    fn test_prices_from_offer_and_markup() {
+        let html = r#"
+            <html><head>
+                <meta property="product:price:amount" content="1299.00">
+                <meta property="product:price:currency" content="sek">
+                <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Walnut Desk",
+                    "offers": {"@type": "Offer", "price": "1299.00", "priceCurrency": "SEK"}
+                }
+                </script>
+            </head><body>
+                <div itemscope itemtype="https://schema.org/Offer">
+                    <span itemprop="price">1.049,50 €</span>
+                    <meta itemprop="priceCurrency" content="EUR">
+                </div>
+            </body></html>
+        "#;
+
+        let prices = SelectorDetector::new().detect(html, "https://shop.example.com/desk").prices;
+
+        assert_eq!(prices, vec![
+            PriceData { amount: 1299.0, currency: Some("SEK".to_string()), source: "json_ld".to_string() },
+            PriceData { amount: 1049.5, currency: Some("EUR".to_string()), source: "microdata".to_string() },
+        ]);
+        assert_eq!(parse_price("$1,299"), Some(1299.0));
+        assert_eq!(parse_price("19,90 kr"), Some(19.9));
+    }
+
+    #[test]
+    fn test_machine_readable_prices_skip_locale_heuristic() {
+        let html = r#"
+            <html><head>
+                <script type="application/ld+json">
+                {"@type": "Offer", "price": "0.125", "priceCurrency": "USD"}
+                </script>
+                <meta property="product:price:amount" content="1.000">
+                <meta property="product:price:currency" content="GBP">
+            </head><body>
+                <div itemscope itemtype="https://schema.org/Offer">
+                    <span itemprop="price" content="2.500">2,50 €</span>
+                    <meta itemprop="priceCurrency" content="EUR">
+                </div>
+            </body></html>
+        "#;
+
+        let prices = SelectorDetector::new().detect(html, "https://shop.example.com/bolt").prices;
+        let amounts: Vec<f64> = prices.iter().map(|p| p.amount).collect();
+
+        assert_eq!(amounts, vec![0.125, 2.5, 1.0]);
+    }
+
+    #[test]
+    fn test_article_text_joins_main_content_blocks() {
+        let html = r#"
+            <html><body>
+                <nav><p>Menu text outside the article</p></nav>
+                <article>
+                    <h1>Release notes</h1>
+                    <p>The first   paragraph.</p>
+                    <ul><li><p>A nested point.</p></li></ul>
+                    <p hidden>Hidden draft</p>
+                    <p>The last paragraph.</p>
+                </article>
+            </body></html>
+        "#;
+
+        let result = SelectorDetector::new().detect(html, "https://example.com/notes");
+
+        assert_eq!(
+            result.article_text,
+            "Release notes\n\nThe first paragraph.\n\nA nested point.\n\nThe last paragraph."
+        );
+
+        let loose = "<html><body><p>Only loose paragraphs here.</p><p>And a second one.</p></body></html>";
+        let result = SelectorDetector::new().detect(loose, "https://example.com/");
+        assert_eq!(result.article_text, "Only loose paragraphs here.\n\nAnd a second one.");
+    }
+
+    #[test]
+    fn test_hreflang_alternates() {
+        let html = r#"
+            <html><head>
+                <link rel="alternate" hreflang="en" href="https://example.com/en/page">
+                <link rel="alternate" hreflang="de-DE" href="/de/seite">
+                <link rel="alternate" hreflang="x-default" href="https://example.com/page">
+            </head><body></body></html>
+        "#;
+
+        let detector = SelectorDetector::new();
+        let result = detector.detect(html, "https://example.com/en/page");
+
+        assert_eq!(result.alternates, vec![
+            ("en".to_string(), "https://example.com/en/page".to_string()),
+            ("de-DE".to_string(), "https://example.com/de/seite".to_string()),
+        ]);
+        assert_eq!(result.x_default.as_deref(), Some("https://example.com/page"));
+    }
+
+    #[test]
+    fn test_content_whitespace_is_collapsed_by_default() {
+        let html = "<html><body><p>Caf\u{e9}&nbsp;&nbsp;menu\n\n   for   today</p></body></html>";
+
+        let detector = SelectorDetector::new();
+        let result = detector.detect(html, "https://example.com");
+        assert_eq!(result.content, vec!["Caf\u{e9} menu for today".to_string()]);
+
+        let raw = SelectorDetector::new().with_whitespace_policy(WhitespacePolicy::Raw);
+        let result = raw.detect(html, "https://example.com");
+        assert_eq!(result.content, vec!["Caf\u{e9}\u{a0}\u{a0}menu\n\n   for   today".to_string()]);
+    }
+
+    #[test]
+    fn test_access_state() {
+        let detector = SelectorDetector::new();
+
+        let paywalled = r#"
+            <html><body>
+                <article><h1>Markets Today</h1><p>The first paragraph is free to read for everyone.</p></article>
+                <div class="paywall">Subscribe to keep reading</div>
+            </body></html>
+        "#;
+        assert_eq!(detector.detect(paywalled, "https://example.com").access, AccessState::LikelyPaywalled);
+
+        let login = r#"
+            <html><body>
+                <p>Please sign in to continue.</p>
+                <form><input type="text" name="user"><input type="password" name="pass"></form>
+            </body></html>
+        "#;
+        assert_eq!(detector.detect(login, "https://example.com").access, AccessState::LoginRequired);
+
+        let open = "<html><body><article><p>An ordinary article that anyone can read.</p></article></body></html>";
+        assert_eq!(detector.detect(open, "https://example.com").access, AccessState::Open);
+    }
+
+    #[test]
+    fn test_title_policy_prefers_better_candidate() {
+        let html = r#"
+            <html><head>
+                <title>HOME | MegaSite</title>
+                <meta property="og:title" content="How to Grow Tomatoes on a Balcony">
+            </head><body></body></html>
+        "#;
+
+        let best = SelectorDetector::new().detect(html, "https://example.com");
+        assert_eq!(best.title.as_deref(), Some("How to Grow Tomatoes on a Balcony"));
+
+        let first = SelectorDetector::new()
+            .with_title_policy(TitlePolicy::FirstMatch)
+            .detect(html, "https://example.com");
+        assert_eq!(first.title.as_deref(), Some("HOME | MegaSite"));
+    }
+
+    #[test]
+    fn test_word_count_and_language() {
+        let html = r#"
+            <html lang="en_GB"><body>
+                <article>
+                    <p>The quick brown fox jumps over the lazy dog.</p>
+                    <p>Pack my box with five dozen liquor jugs.</p>
+                </article>
+            </body></html>
+        "#;
+
+        let result = SelectorDetector::new().detect(html, "https://example.com");
+
+        // article block plus its two paragraphs, but their words are counted once
+        assert_eq!(result.content.len(), 3);
+        assert_eq!(result.word_count, 17);
+        assert_eq!(result.char_count, result.content[0].chars().count());
+        assert_eq!(result.language.as_deref(), Some("en-gb"));
+    }
+
+    #[test]
+    fn test_soft_404_detection() {
+        let detector = SelectorDetector::new();
+
+        let missing = r#"<html><head><title>Page not found | Shop</title></head><body>
+            <h1>Oops!</h1><p>We looked everywhere but couldn't find that.</p>
+        </body></html>"#;
+        assert!(detector.detect(missing, "https://shop.example/gone").soft_404);
+
+        let article = format!(
+            "<html><head><title>Lost luggage guide</title></head><body><p>If your bag was not found, {}</p></body></html>",
+            "file a claim with the airline and keep every receipt. ".repeat(20)
+        );
+        assert!(!detector.detect(&article, "https://travel.example/luggage").soft_404);
+    }
+
+    #[test]
+    fn test_total_items_from_marker_or_item_list() {
+        let detector = SelectorDetector::new();
+
+        let marker = r#"<html><body><p>Showing 1-20 of 1,234 results</p></body></html>"#;
+        assert_eq!(detector.detect(marker, "https://shop.example/").total_items, Some(1234));
+
+        let item_list = r#"<html><head><script type="application/ld+json">
+            {"@context": "https://schema.org", "@type": "ItemList", "numberOfItems": "87",
+             "itemListElement": [{"@type": "ListItem", "position": 1}, {"@type": "ListItem", "position": 2}]}
+        </script></head><body><p>12 items in your cart</p></body></html>"#;
+        let detected = detector.detect(item_list, "https://shop.example/");
+        assert_eq!(detected.total_items, Some(87));
+        assert_eq!(detected.listed_items, Some(2));
+
+        let plain = "<html><body><p>No counts here.</p></body></html>";
+        assert_eq!(detector.detect(plain, "https://shop.example/").total_items, None);
+
+        let cart = "<html><body><p>You have 3 items in your cart</p></body></html>";
+        assert_eq!(detector.detect(cart, "https://shop.example/").total_items, None);
+        assert_eq!(detector.detect(cart, "https://shop.example/").listed_items, None);
+    }
+
+    #[test]
+    fn test_site_icon_and_theme_color() {
+        let html = r##"
+            <html><head>
+                <link rel="shortcut icon" href="/favicon-16.png">
+                <link rel="icon" sizes="32x32" href="/icons/32.png">
+                <link rel="apple-touch-icon" href="/icons/touch.png">
+                <link rel="icon" sizes="96x96" href="/icons/96.png">
+                <meta name="theme-color" content="#0a84ff">
+            </head><body></body></html>
+        "##;
+
+        let detector = SelectorDetector::new();
+        let result = detector.detect(html, "https://example.com/blog/post");
+        assert_eq!(result.site_icon.as_deref(), Some("https://example.com/icons/touch.png"));
+        assert_eq!(result.theme_color.as_deref(), Some("#0a84ff"));
+
+        let sized = r#"<html><head>
+            <link rel="apple-touch-icon" href="/touch.png">
+            <link rel="icon" sizes="192x192" href="/icon-192.png">
+        </head></html>"#;
+        assert_eq!(
+            detector.detect(sized, "https://example.com/").site_icon.as_deref(),
+            Some("https://example.com/icon-192.png")
+        );
+
+        let bare = detector.detect("<html><body></body></html>", "https://example.com/a/b");
+        assert_eq!(bare.site_icon.as_deref(), Some("https://example.com/favicon.ico"));
+        assert!(bare.theme_color.is_none());
+    }
+
+    #[test]
+    fn test_protocol_relative_and_script_links() {
+        let html = r#"
+            <html><body>
+                <a href="//cdn.example.net/file.html">Mirror</a>
+                <a href="javascript:void(0)">Menu</a>
+                <a href=" JavaScript:openCart()">Cart</a>
+                <a href="data:text/html,hello">Inline</a>
+                <a href="/about">About</a>
+            </body></html>
+        "#;
+
+        let result = SelectorDetector::new().detect(html, "http://example.com/page");
+        let hrefs: Vec<&str> = result.links.iter().map(|l| l.href.as_str()).collect();
+
+        assert_eq!(hrefs, vec!["http://cdn.example.net/file.html", "http://example.com/about"]);
+        assert!(result.links[0].is_external);
+    }
+
+    #[test]
+    fn test_noscript_fallback_for_sparse_pages() {
+        let html = r#"
+            <html><body>
+                <div id="app"></div>
+                <noscript>
+                    <article><p>The full story is served here for readers without JavaScript.</p></article>
+                </noscript>
+            </body></html>
+        "#;
+
+        let off = SelectorDetector::new().detect(html, "https://example.com");
+        assert!(off.content.is_empty());
+
+        let on = SelectorDetector::new()
+            .with_noscript_fallback(true)
+            .detect(html, "https://example.com");
+        assert!(on.content.iter().any(|c| c == "The full story is served here for readers without JavaScript."));
+        assert!(on.content.iter().all(|c| !c.contains('<')));
+    }
+
+    #[test]
+    fn test_template_and_hidden_content_excluded() {
+        let html = r#"
+            <html><body>
+                <article>
+                    <p>Visible paragraph that readers actually see.</p>
+                    <template><p>Template paragraph that never renders.</p></template>
+                    <p style="display: none">Hidden paragraph behind inline style.</p>
+                    <div hidden><p>Hidden paragraph behind the attribute.</p></div>
+                </article>
+            </body></html>
+        "#;
+
+        let result = SelectorDetector::new().detect(html, "https://example.com");
+        let all = result.content.join(" ");
+
+        assert!(all.contains("Visible paragraph"));
+        assert!(!all.contains("Template paragraph"));
+        assert!(!all.contains("inline style"));
+        assert!(!all.contains("the attribute"));
+    }
 }
@@ -1,20 +1,29 @@
 use crate::auto_selectors::AutoSelectors;
 use crate::scraper::{ScrapingConfig, ScrapingResult, WebScraper};
-use crate::utils::normalize_url;
+use crate::structure_analyzer::StructureAnalyzer;
+use crate::utils::{content_hash, normalize_url_with_options, DomainRateLimiter, FetchCache};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
 use url::Url;
 
 /// Configuration for deep scraping
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeepScrapeConfig {
-    /// Starting URLs
+    /// Starting URLs. A `{start..end}` or `{start..end..step}` range expands into
+    /// one seed per number (`/item/{1..100}`); a zero-padded start (`{001..100}`)
+    /// pads every number to its width.
     pub start_urls: Vec<String>,
 
-    /// Maximum depth to crawl (0 = only start URLs)
+    /// Maximum depth to crawl (0 = only start URLs and sitemap entries)
     pub max_depth: usize,
 
+    /// Depth caps for individual hosts ("partner.example.com" => 1); other hosts use `max_depth`
+    #[serde(default)]
+    pub per_domain_max_depth: HashMap<String, usize>,
+
     /// Maximum total pages to scrape
     pub max_pages: usize,
 
@@ -39,8 +48,117 @@ pub struct DeepScrapeConfig {
     /// Filter out navigation/footer links
     pub filter_navigation: bool,
 
-    /// Minimum content length to consider a page valuable
+    /// Minimum extracted content length (characters) to store a page. Thinner
+    /// pages are still crawled for links but only listed in `thin_urls`
     pub min_content_length: usize,
+
+    /// Don't store pages that look like error pages served with a 200 status;
+    /// they are listed in `soft_404_urls` instead
+    #[serde(default)]
+    pub skip_soft_404: bool,
+
+    /// Retry failed URLs once more after the main crawl
+    #[serde(default)]
+    pub retry_failed: bool,
+
+    /// Record pages with identical content as aliases instead of storing them again,
+    /// as well as pages whose `rel=canonical` leads to an already stored page
+    #[serde(default)]
+    pub collapse_duplicates: bool,
+
+    /// Wall-time budget for the whole crawl in seconds (None = unlimited)
+    #[serde(default)]
+    pub max_duration_secs: Option<f64>,
+
+    /// Treat `/dir/index.html` (and similar) as the same page as `/dir`
+    #[serde(default)]
+    pub fold_index_pages: bool,
+
+    /// Sitemaps (or sitemap indexes) whose `<loc>` entries are seeded at depth 0
+    /// alongside `start_urls`. With `max_depth: 0` exactly these seeds are scraped.
+    #[serde(default)]
+    pub sitemap_urls: Vec<String>,
+
+    /// Never follow links to files with these extensions ("pdf", ".ZIP"; case-insensitive,
+    /// query strings ignored)
+    #[serde(default = "default_excluded_extensions")]
+    pub excluded_extensions: Vec<String>,
+
+    /// When set, only follow links whose extension is listed. Extensionless
+    /// paths (`/docs/intro`) are always followed.
+    #[serde(default)]
+    pub allowed_extensions: Option<Vec<String>>,
+
+    /// Abort after this many failed pages in a row (e.g. the site started blocking us)
+    #[serde(default)]
+    pub max_consecutive_errors: Option<usize>,
+
+    /// Abort once this many pages have failed in total
+    #[serde(default)]
+    pub max_total_errors: Option<usize>,
+
+    /// Only follow links whose path starts with one of these (e.g. "/docs/").
+    /// Empty means no restriction.
+    #[serde(default)]
+    pub path_prefixes: Vec<String>,
+
+    /// Also follow hreflang alternates for this language (e.g. "de" matches "de-AT")
+    #[serde(default)]
+    pub preferred_language: Option<String>,
+
+    /// Rewrite `http://` links to `https://` when the host is known to serve https:
+    /// an https start URL or an https page linking to it on the same host
+    #[serde(default)]
+    pub upgrade_insecure: bool,
+
+    /// Send the linking page as `Referer` when fetching a discovered link
+    #[serde(default)]
+    pub send_referer: bool,
+
+    /// Store `application/json` responses as parsed JSON and crawl the
+    /// URL-looking strings inside them
+    #[serde(default)]
+    pub follow_json_links: bool,
+
+    /// Headers sent with every page request (e.g. `Cookie`, `Accept-Language`)
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+
+    /// Stretch the delay between requests to a host as its responses slow down:
+    /// `1 / rate_limit` plus the host's recent average response time
+    #[serde(default)]
+    pub adaptive_delay: bool,
+
+    /// Slow down as `X-RateLimit-Remaining` runs low, and wait for
+    /// `X-RateLimit-Reset` once it reaches zero, or for `Retry-After` when given.
+    /// Error responses such as 429s are honoured too.
+    #[serde(default)]
+    pub respect_ratelimit_headers: bool,
+
+    /// Pages fetched at once; 0 or 1 fetches one at a time. Each batch is still
+    /// followed by the `rate_limit` pause.
+    #[serde(default)]
+    pub concurrency: usize,
+
+    /// Most requests (pages, their AMP versions and sitemaps) in flight at once
+    /// across the whole crawl; 0 = unlimited. Unlike `rate_limit` this bounds
+    /// concurrency, not pacing.
+    #[serde(default)]
+    pub global_max_inflight: usize,
+
+    /// On a re-crawl (`incremental_against`), don't follow links from pages whose
+    /// branch yielded no stored or unchanged page last time
+    #[serde(default)]
+    pub prune_unproductive: bool,
+
+    /// Run the structure analyzer on every page to fill `summary.pages_by_mode`.
+    /// It parses and scores each page a second time, so it is off by default.
+    #[serde(default)]
+    pub classify_pages: bool,
+
+    /// Process-wide per-host limiter shared with other scrapers
+    #[serde(skip)]
+    pub domain_rate_limiter: Option<Arc<DomainRateLimiter>>,
 }
 
 impl Default for DeepScrapeConfig {
@@ -48,6 +166,7 @@ impl Default for DeepScrapeConfig {
         Self {
             start_urls: Vec::new(),
             max_depth: 2,
+            per_domain_max_depth: HashMap::new(),
             max_pages: 50,
             stay_in_domain: true,
             stay_in_subdomain: false,
@@ -64,10 +183,41 @@ impl Default for DeepScrapeConfig {
             custom_selectors: None,
             filter_navigation: true,
             min_content_length: 200,
+            skip_soft_404: false,
+            retry_failed: false,
+            collapse_duplicates: false,
+            max_duration_secs: None,
+            fold_index_pages: false,
+            sitemap_urls: Vec::new(),
+            excluded_extensions: default_excluded_extensions(),
+            allowed_extensions: None,
+            max_consecutive_errors: None,
+            max_total_errors: None,
+            path_prefixes: Vec::new(),
+            preferred_language: None,
+            adaptive_delay: false,
+            respect_ratelimit_headers: false,
+            upgrade_insecure: false,
+            send_referer: false,
+            follow_json_links: false,
+            extra_headers: HashMap::new(),
+            concurrency: 0,
+            global_max_inflight: 0,
+            prune_unproductive: false,
+            classify_pages: false,
+            domain_rate_limiter: None,
         }
     }
 }
 
+/// Downloads and media that never hold crawlable HTML
+pub fn default_excluded_extensions() -> Vec<String> {
+    ["pdf", "zip", "gz", "tar", "exe", "dmg", "jpg", "jpeg", "png", "gif", "webp", "svg", "mp3", "mp4", "avi", "mov"]
+        .iter()
+        .map(|ext| ext.to_string())
+        .collect()
+}
+
 /// Represents a URL in the crawl queue
 #[derive(Debug, Clone)]
 struct CrawlItem {
@@ -76,6 +226,16 @@ struct CrawlItem {
     parent_url: Option<String>,
 }
 
+/// Running totals of a crawl, updated as each page's outcome is handled
+#[derive(Debug, Default)]
+struct CrawlTally {
+    pages_crawled: usize,
+    links_discovered: usize,
+    links_filtered: usize,
+    failed: Vec<CrawlItem>,
+    consecutive_errors: usize,
+}
+
 /// Link scoring for intelligent filtering
 #[derive(Debug, Clone)]
 pub struct LinkScore {
@@ -100,6 +260,75 @@ pub struct DeepScrapeResult {
     pub domains_visited: Vec<String>,
     pub errors: Vec<String>,
     pub status: CrawlStatus,
+    /// Duplicate URL -> URL of the first page seen with the same content
+    #[serde(default)]
+    pub duplicates: HashMap<String, String>,
+    #[serde(default)]
+    pub elapsed_seconds: f64,
+    /// Why the crawl stopped: "max_pages", "max_duration", "max_consecutive_errors",
+    /// "max_total_errors" or "queue_empty"
+    #[serde(default)]
+    pub budget_exhausted_reason: Option<String>,
+    /// URL -> content hash of every page fetched, for later incremental runs
+    #[serde(default)]
+    pub content_hashes: HashMap<String, String>,
+    /// Pages whose content matched the previous run (incremental crawls only)
+    #[serde(default)]
+    pub unchanged_urls: Vec<String>,
+    /// Pages seen in the previous run whose content has since changed
+    #[serde(default)]
+    pub changed_urls: Vec<String>,
+    /// Pages crawled but not stored for having less content than `min_content_length`
+    #[serde(default)]
+    pub thin_urls: Vec<String>,
+    /// Pages crawled but not stored for looking like soft 404s (`skip_soft_404`)
+    #[serde(default)]
+    pub soft_404_urls: Vec<String>,
+    #[serde(default)]
+    pub summary: CrawlSummary,
+    /// URL -> valuable pages (stored or unchanged) in the branch rooted at it,
+    /// the page itself included
+    #[serde(default)]
+    pub branch_yield: HashMap<String, usize>,
+    /// Link cycles found while crawling, each from the ancestor linked back to
+    /// down to the page linking to it (`[A, B]` for A -> B -> A)
+    #[serde(default)]
+    pub cycles: Vec<Vec<String>>,
+    /// URL -> cache validators of every stored or unchanged page, sent back as
+    /// conditional request headers by later incremental runs
+    #[serde(default)]
+    pub validators: HashMap<String, PageValidators>,
+}
+
+/// `ETag` and `Last-Modified` response headers of a page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl PageValidators {
+    fn from_headers(headers: &HashMap<String, String>) -> Option<Self> {
+        let validators = Self {
+            etag: headers.get("etag").cloned(),
+            last_modified: headers.get("last-modified").cloned(),
+        };
+        (validators.etag.is_some() || validators.last_modified.is_some()).then_some(validators)
+    }
+}
+
+/// Crawl health snapshot, computed when the crawl ends
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrawlSummary {
+    /// Pages scraped at each depth, indexed by depth
+    pub pages_per_depth: Vec<usize>,
+    /// Mean `quality_score` of the stored results
+    pub average_quality: Option<f64>,
+    /// Pages scraped per suggested extraction mode ("article", "list_page", ...);
+    /// empty unless `classify_pages` is set
+    pub pages_by_mode: BTreeMap<String, usize>,
+    /// Errors per category: "http_4xx", "http_5xx", "network" or "other"
+    pub errors_by_category: BTreeMap<String, usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,6 +339,9 @@ pub struct CrawlNode {
     pub children: Vec<String>,
     pub scraped: bool,
     pub error: Option<String>,
+    /// "Next page" link detected on a listing page, whether or not it was followed
+    #[serde(default)]
+    pub next_page: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -128,12 +360,45 @@ pub struct DeepScraper {
     results: Arc<Mutex<Vec<ScrapingResult>>>,
     crawl_tree: Arc<Mutex<Vec<CrawlNode>>>,
     errors: Arc<Mutex<Vec<String>>>,
+    content_hashes: Arc<Mutex<HashMap<String, String>>>,
+    duplicates: Arc<Mutex<HashMap<String, String>>>,
+    /// URL -> the different URL its `rel=canonical` names
+    canonicals: Arc<Mutex<HashMap<String, String>>>,
+    /// URL -> content hash from the run this crawl is incremental against
+    previous_hashes: HashMap<String, String>,
+    /// URL -> branch yield from the run this crawl is incremental against
+    previous_yield: HashMap<String, usize>,
+    /// URL -> cache validators from the run this crawl is incremental against
+    previous_validators: HashMap<String, PageValidators>,
+    /// URL -> pages crawled from it in the run this crawl is incremental against
+    previous_children: HashMap<String, Vec<String>>,
+    validators: Arc<Mutex<HashMap<String, PageValidators>>>,
+    page_hashes: Arc<Mutex<HashMap<String, String>>>,
+    unchanged: Arc<Mutex<Vec<String>>>,
+    thin: Arc<Mutex<Vec<String>>>,
+    soft_404s: Arc<Mutex<Vec<String>>>,
+    changed: Arc<Mutex<Vec<String>>>,
+    /// URL -> suggested extraction mode of every page fetched
+    page_modes: Arc<Mutex<HashMap<String, String>>>,
+    /// URL -> "next page" link detected on it
+    next_pages: Arc<Mutex<HashMap<String, String>>>,
+    cycles: Arc<Mutex<Vec<Vec<String>>>>,
+    /// Bodies fetched during this crawl, shared by every page's scraper
+    fetch_cache: Arc<FetchCache>,
+    /// Enforces `global_max_inflight`
+    inflight: Option<Arc<Semaphore>>,
+    /// Host -> most recent response times, for `adaptive_delay`
+    latencies: Arc<Mutex<HashMap<String, VecDeque<Duration>>>>,
 }
 
 impl DeepScraper {
-    pub fn new(config: DeepScrapeConfig) -> Self {
+    pub fn new(mut config: DeepScrapeConfig) -> Self {
         let queue = Arc::new(Mutex::new(VecDeque::new()));
 
+        config.start_urls = config.start_urls.iter()
+            .flat_map(|url| expand_seed(url))
+            .collect();
+
         // Initialize queue with start URLs
         {
             let mut q = queue.lock().unwrap();
@@ -153,7 +418,47 @@ impl DeepScraper {
             results: Arc::new(Mutex::new(Vec::new())),
             crawl_tree: Arc::new(Mutex::new(Vec::new())),
             errors: Arc::new(Mutex::new(Vec::new())),
+            content_hashes: Arc::new(Mutex::new(HashMap::new())),
+            duplicates: Arc::new(Mutex::new(HashMap::new())),
+            canonicals: Arc::new(Mutex::new(HashMap::new())),
+            previous_hashes: HashMap::new(),
+            previous_yield: HashMap::new(),
+            previous_validators: HashMap::new(),
+            previous_children: HashMap::new(),
+            validators: Arc::new(Mutex::new(HashMap::new())),
+            page_hashes: Arc::new(Mutex::new(HashMap::new())),
+            unchanged: Arc::new(Mutex::new(Vec::new())),
+            thin: Arc::new(Mutex::new(Vec::new())),
+            soft_404s: Arc::new(Mutex::new(Vec::new())),
+            changed: Arc::new(Mutex::new(Vec::new())),
+            page_modes: Arc::new(Mutex::new(HashMap::new())),
+            next_pages: Arc::new(Mutex::new(HashMap::new())),
+            cycles: Arc::new(Mutex::new(Vec::new())),
+            fetch_cache: Arc::new(FetchCache::default()),
+            inflight: (config.global_max_inflight > 0).then(|| Arc::new(Semaphore::new(config.global_max_inflight))),
+            latencies: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Re-crawl incrementally: pages whose content hash matches `previous` are
+    /// recorded in `unchanged_urls` instead of being stored again. Their links
+    /// are still followed so new pages behind them are found, unless
+    /// `prune_unproductive` is set and their branch yielded nothing last time.
+    ///
+    /// Pages that came with an `ETag` or `Last-Modified` header are requested
+    /// conditionally; a `304 Not Modified` answer counts as unchanged without
+    /// downloading the page, and the links crawled from it last time are followed.
+    pub fn incremental_against(mut self, previous: &DeepScrapeResult) -> Self {
+        self.previous_hashes = previous.content_hashes.clone();
+        self.previous_yield = previous.branch_yield.clone();
+        self.previous_validators = previous.validators.clone();
+        self.previous_children.clear();
+        for node in &previous.crawl_tree {
+            if let Some(ref parent) = node.parent {
+                self.previous_children.entry(parent.clone()).or_default().push(node.url.clone());
+            }
         }
+        self
     }
 
     /// Execute the deep scraping
@@ -164,74 +469,78 @@ impl DeepScraper {
         log::info!("🔍 Starting deep scrape: {} URLs, max depth: {}",
             self.config.start_urls.len(), self.config.max_depth);
 
-        let mut pages_crawled = 0;
-        let mut links_discovered = 0;
-        let mut links_filtered = 0;
+        let mut tally = CrawlTally::default();
+        let started = std::time::Instant::now();
+        let mut stop_reason = "queue_empty";
 
-        while pages_crawled < self.config.max_pages {
-            // Get next URL from queue
-            let item = {
-                let mut queue = self.queue.lock().unwrap();
-                queue.pop_front()
-            };
+        self.seed_from_sitemaps().await;
 
-            let item = match item {
-                Some(i) => i,
-                None => break, // Queue empty, done
-            };
+        loop {
+            if tally.pages_crawled >= self.config.max_pages {
+                stop_reason = "max_pages";
+                break;
+            }
 
-            // Skip if already visited
-            {
-                let mut visited = self.visited.lock().unwrap();
-                if visited.contains(&item.url) {
-                    continue;
+            if let Some(limit) = self.config.max_duration_secs {
+                if started.elapsed().as_secs_f64() >= limit {
+                    stop_reason = "max_duration";
+                    break;
                 }
-                visited.insert(item.url.clone());
             }
 
-            log::info!("📄 Scraping [depth {}]: {}", item.depth, item.url);
-
-            // Scrape the page
-            match self.scrape_page(&item).await {
-                Ok((result, links)) => {
-                    links_discovered += links.len();
-
-                    // Filter and queue links
-                    if item.depth < self.config.max_depth {
-                        let filtered_links = self.filter_links(&item.url, links);
-                        links_filtered += links_discovered - filtered_links.len();
-
-                        self.enqueue_links(&item.url, &filtered_links, item.depth + 1);
-                    }
+            let batch = self.next_batch(self.config.max_pages - tally.pages_crawled);
+            if batch.is_empty() {
+                break; // Queue empty, done
+            }
 
-                    // Store result
-                    let mut results = self.results.lock().unwrap();
-                    results.push(result);
-                    pages_crawled += 1;
+            let outcomes = futures::future::join_all(batch.iter().map(|item| self.scrape_page(item))).await;
 
-                    // Update crawl tree
-                    self.update_crawl_tree(&item, None);
+            // Pause the server asked for through its rate limit headers
+            let mut ratelimit_pause = None;
+            let mut abort = None;
+            for (item, outcome) in batch.iter().zip(outcomes) {
+                let failures = tally.failed.len();
+                ratelimit_pause = ratelimit_pause.max(self.handle_outcome(item, outcome, &mut tally));
+                if abort.is_some() || tally.failed.len() == failures {
+                    continue;
                 }
-                Err(e) => {
-                    log::error!("❌ Failed to scrape {}: {}", item.url, e);
-                    let mut errors = self.errors.lock().unwrap();
-                    errors.push(format!("{}: {}", item.url, e));
 
-                    // Mark as error in crawl tree
-                    self.update_crawl_tree(&item, Some(e.to_string()));
+                if self.config.max_consecutive_errors.is_some_and(|max| tally.consecutive_errors >= max) {
+                    log::warn!("🛑 Aborting after {} consecutive errors", tally.consecutive_errors);
+                    abort = Some("max_consecutive_errors");
+                } else if self.config.max_total_errors.is_some_and(|max| tally.failed.len() >= max) {
+                    log::warn!("🛑 Aborting after {} errors", tally.failed.len());
+                    abort = Some("max_total_errors");
                 }
             }
+            if let Some(reason) = abort {
+                stop_reason = reason;
+                break;
+            }
 
             // Respect rate limit
-            let delay = std::time::Duration::from_secs_f64(1.0 / self.config.rate_limit);
+            let mut delay = batch.iter().map(|item| self.polite_delay(&item.url)).max().unwrap_or_default();
+            if let Some(pause) = ratelimit_pause.filter(|pause| *pause > delay) {
+                log::info!("⏳ Rate limit nearly used up, pausing {:.1}s", pause.as_secs_f64());
+                delay = pause;
+            }
             tokio::time::sleep(delay).await;
         }
 
+        // Retrying is pointless when the crawl was aborted for failing
+        let aborted = matches!(stop_reason, "max_consecutive_errors" | "max_total_errors");
+        if self.config.retry_failed && !tally.failed.is_empty() && !aborted {
+            let failed = std::mem::take(&mut tally.failed);
+            if let Some(reason) = self.retry_failed_items(failed, &mut tally, started).await {
+                stop_reason = reason;
+            }
+        }
+
         let end_time = chrono::Utc::now().to_rfc3339();
-        let status = self.determine_status(pages_crawled);
+        let status = self.determine_status(tally.pages_crawled);
 
-        log::info!("✅ Deep scrape completed: {} pages, {} links discovered",
-            pages_crawled, links_discovered);
+        log::info!("✅ Deep scrape completed: {} pages, {} links discovered (stopped: {})",
+            tally.pages_crawled, tally.links_discovered, stop_reason);
 
         DeepScrapeResult {
             session_id,
@@ -240,17 +549,355 @@ impl DeepScraper {
             config: self.config.clone(),
             results: self.results.lock().unwrap().clone(),
             crawl_tree: self.crawl_tree.lock().unwrap().clone(),
-            total_pages_crawled: pages_crawled,
-            total_links_discovered: links_discovered,
-            total_links_filtered: links_filtered,
+            total_pages_crawled: tally.pages_crawled,
+            total_links_discovered: tally.links_discovered,
+            total_links_filtered: tally.links_filtered,
             domains_visited: self.get_domains_visited(),
             errors: self.errors.lock().unwrap().clone(),
             status,
+            duplicates: self.duplicates.lock().unwrap().clone(),
+            elapsed_seconds: started.elapsed().as_secs_f64(),
+            budget_exhausted_reason: Some(stop_reason.to_string()),
+            content_hashes: self.page_hashes.lock().unwrap().clone(),
+            unchanged_urls: self.unchanged.lock().unwrap().clone(),
+            changed_urls: self.changed.lock().unwrap().clone(),
+            thin_urls: self.thin.lock().unwrap().clone(),
+            soft_404_urls: self.soft_404s.lock().unwrap().clone(),
+            summary: self.summarize(),
+            branch_yield: self.branch_yield(),
+            cycles: self.cycles.lock().unwrap().clone(),
+            validators: self.validators.lock().unwrap().clone(),
+        }
+    }
+
+    fn record_validators(&self, url: &str, validators: Option<PageValidators>) {
+        if let Some(validators) = validators {
+            self.validators.lock().unwrap().insert(url.to_string(), validators);
+        }
+    }
+
+    /// Keep the previous run's hash and validators for a page answered with `304 Not Modified`
+    fn carry_forward(&self, url: &str) {
+        if let Some(hash) = self.previous_hashes.get(url) {
+            self.page_hashes.lock().unwrap().insert(url.to_string(), hash.clone());
+        }
+        self.record_validators(url, self.previous_validators.get(url).cloned());
+    }
+
+    /// Pop up to `concurrency` unvisited URLs, but no more than `limit`, marking them visited
+    fn next_batch(&self, limit: usize) -> Vec<CrawlItem> {
+        let width = self.config.concurrency.max(1).min(limit.max(1));
+        let mut batch = Vec::new();
+
+        while batch.len() < width {
+            let Some(item) = self.queue.lock().unwrap().pop_front() else {
+                break;
+            };
+            if self.mark_visited(&item.url) {
+                log::info!("📄 Scraping [depth {}]: {}", item.depth, item.url);
+                batch.push(item);
+            }
+        }
+
+        batch
+    }
+
+    /// Record what fetching `item` produced: store, classify or skip the page and
+    /// queue its links, or log the failure. Returns the pause its rate limit headers ask for.
+    fn handle_outcome(
+        &self,
+        item: &CrawlItem,
+        outcome: crate::error::Result<(ScrapingResult, Vec<String>)>,
+        tally: &mut CrawlTally,
+    ) -> Option<Duration> {
+        let mut ratelimit_pause = None;
+
+        match outcome {
+            Ok((ref result, _)) if self.off_domain_redirect(&item.url, result).is_some() => {
+                tally.consecutive_errors = 0;
+                let target = result.final_url.clone().unwrap_or_default();
+                log::info!("↪️ Redirected off-domain, not stored: {} -> {}", item.url, target);
+                tally.links_filtered += 1;
+                self.update_crawl_tree(item, Some(format!("Redirected off-domain to {}", target)));
+            }
+            Ok((mut result, links)) => {
+                tally.consecutive_errors = 0;
+                if self.config.respect_ratelimit_headers {
+                    ratelimit_pause = ratelimit_pause_from(&result.headers);
+                }
+                let validators = PageValidators::from_headers(&result.headers);
+                result.headers.clear();
+                self.record_latency(&item.url, Duration::from_secs_f64(result.timings.fetch_ms / 1000.0));
+                let hash = page_hash(&result);
+                self.page_hashes.lock().unwrap().insert(item.url.clone(), hash.clone());
+
+                let duplicate_of = self.find_duplicate(&item.url, &hash)
+                    .or_else(|| self.canonical_duplicate(&item.url, &result));
+                if let Some(canonical) = duplicate_of {
+                    log::info!("♊ Duplicate of {}: {}", canonical, item.url);
+                    self.duplicates.lock().unwrap().insert(item.url.clone(), canonical);
+                } else {
+                    tally.links_discovered += links.len();
+
+                    // Filter and queue links
+                    if self.is_unproductive(&item.url) {
+                        log::info!("✂️ Pruning unproductive branch: {}", item.url);
+                    } else if item.depth < self.depth_ceiling() {
+                        let alternates = self.preferred_alternates(&result);
+                        tally.links_discovered += alternates.len();
+                        let mut candidates = links;
+                        candidates.extend(alternates);
+
+                        let offered = candidates.len();
+                        let filtered_links = self.filter_links(&item.url, candidates);
+                        tally.links_filtered += offered - filtered_links.len();
+
+                        self.record_cycles(item, &filtered_links);
+                        self.enqueue_links(&item.url, &filtered_links, item.depth + 1);
+                    }
+
+                    match self.previous_hashes.get(&item.url) {
+                        _ if self.config.skip_soft_404 && result.content.soft_404 => {
+                            log::info!("🚫 Soft 404, not stored: {}", item.url);
+                            self.soft_404s.lock().unwrap().push(item.url.clone());
+                        }
+                        _ if self.is_thin(&result) => {
+                            log::info!("🪶 Thin page, not stored: {}", item.url);
+                            self.thin.lock().unwrap().push(item.url.clone());
+                        }
+                        Some(previous) if *previous == hash => {
+                            log::info!("💤 Unchanged: {}", item.url);
+                            self.unchanged.lock().unwrap().push(item.url.clone());
+                            self.record_validators(&item.url, validators);
+                        }
+                        previous => {
+                            if previous.is_some() {
+                                self.changed.lock().unwrap().push(item.url.clone());
+                            }
+
+                            // Store result
+                            self.results.lock().unwrap().push(result);
+                            self.record_validators(&item.url, validators);
+                        }
+                    }
+                }
+
+                tally.pages_crawled += 1;
+
+                // Update crawl tree
+                self.update_crawl_tree(item, None);
+            }
+            Err(ref e) if e.status() == Some(reqwest::StatusCode::NOT_MODIFIED)
+                && self.previous_validators.contains_key(&item.url) => {
+                tally.consecutive_errors = 0;
+                log::info!("💤 Not modified: {}", item.url);
+                self.carry_forward(&item.url);
+                self.unchanged.lock().unwrap().push(item.url.clone());
+
+                if self.is_unproductive(&item.url) {
+                    log::info!("✂️ Pruning unproductive branch: {}", item.url);
+                } else if item.depth < self.depth_ceiling() {
+                    let children = self.previous_children.get(&item.url).cloned().unwrap_or_default();
+                    let filtered_links = self.filter_links(&item.url, children);
+                    self.record_cycles(item, &filtered_links);
+                    self.enqueue_links(&item.url, &filtered_links, item.depth + 1);
+                }
+
+                tally.pages_crawled += 1;
+                self.update_crawl_tree(item, None);
+            }
+            Err(e) => {
+                log::error!("❌ Failed to scrape {}: {}", item.url, e);
+                if let crate::error::ScraperError::Http { ref headers, .. } = e {
+                    if self.config.respect_ratelimit_headers {
+                        ratelimit_pause = ratelimit_pause_from(headers);
+                    }
+                }
+                let mut errors = self.errors.lock().unwrap();
+                errors.push(format!("{}: {}", item.url, e));
+
+                // Mark as error in crawl tree
+                self.update_crawl_tree(item, Some(e.to_string()));
+                tally.failed.push(item.clone());
+                tally.consecutive_errors += 1;
+            }
+        }
+
+        ratelimit_pause
+    }
+
+    /// Whether the previous crawl found nothing worth keeping below `url`
+    fn is_unproductive(&self, url: &str) -> bool {
+        self.config.prune_unproductive && self.previous_yield.get(url) == Some(&0)
+    }
+
+    /// Credit every stored or unchanged page to itself and each of its ancestors
+    fn branch_yield(&self) -> HashMap<String, usize> {
+        let tree = self.crawl_tree.lock().unwrap();
+        let parents: HashMap<&str, &str> = tree.iter()
+            .filter_map(|node| node.parent.as_deref().map(|parent| (node.url.as_str(), parent)))
+            .collect();
+        let mut yields: HashMap<String, usize> = tree.iter()
+            .map(|node| (node.url.clone(), 0))
+            .collect();
+
+        let results = self.results.lock().unwrap();
+        let unchanged = self.unchanged.lock().unwrap();
+        let valuable = results.iter().map(|r| r.url.as_str())
+            .chain(unchanged.iter().map(String::as_str));
+
+        for url in valuable {
+            let mut seen = HashSet::new();
+            let mut current = Some(url);
+            while let Some(node) = current {
+                if !seen.insert(node) {
+                    break;
+                }
+                *yields.entry(node.to_string()).or_insert(0) += 1;
+                current = parents.get(node).copied();
+            }
+        }
+
+        yields
+    }
+
+    fn summarize(&self) -> CrawlSummary {
+        let mut summary = CrawlSummary::default();
+
+        for node in self.crawl_tree.lock().unwrap().iter().filter(|node| node.scraped) {
+            if summary.pages_per_depth.len() <= node.depth {
+                summary.pages_per_depth.resize(node.depth + 1, 0);
+            }
+            summary.pages_per_depth[node.depth] += 1;
+        }
+
+        let results = self.results.lock().unwrap();
+        if !results.is_empty() {
+            let total: f64 = results.iter().map(|r| r.quality_score).sum();
+            summary.average_quality = Some(total / results.len() as f64);
+        }
+
+        for mode in self.page_modes.lock().unwrap().values() {
+            *summary.pages_by_mode.entry(mode.clone()).or_insert(0) += 1;
+        }
+
+        for error in self.errors.lock().unwrap().iter() {
+            *summary.errors_by_category.entry(error_category(error).to_string()).or_insert(0) += 1;
+        }
+
+        summary
+    }
+
+    /// Queue every page listed in the configured sitemaps at depth 0, subject to
+    /// the same filters as links found on pages
+    async fn seed_from_sitemaps(&self) {
+        // Guards against runaway or cyclic sitemap indexes
+        const MAX_SITEMAPS: usize = 50;
+
+        if self.config.sitemap_urls.is_empty() {
+            return;
+        }
+
+        // Fetched like pages are: same timeout, user agent, extra headers and in-flight cap
+        let scraper_config = ScrapingConfig {
+            rate_limit: self.config.rate_limit,
+            inflight_limiter: self.inflight.clone(),
+            extra_headers: self.config.extra_headers.clone(),
+            ..ScrapingConfig::default()
+        };
+        let scraper = match WebScraper::new(&scraper_config, false) {
+            Ok(scraper) => scraper,
+            Err(e) => {
+                log::error!("❌ Failed to fetch sitemaps: {}", e);
+                self.errors.lock().unwrap().push(format!("sitemaps: {}", e));
+                return;
+            }
+        };
+        let mut pending: VecDeque<String> = self.config.sitemap_urls.iter().cloned().collect();
+        let mut fetched = 0;
+        let mut seeded = 0;
+
+        while let Some(sitemap_url) = pending.pop_front() {
+            if fetched >= MAX_SITEMAPS {
+                log::warn!("Sitemap limit reached, skipping {}", sitemap_url);
+                break;
+            }
+            fetched += 1;
+
+            let xml = match scraper.fetch_page(&sitemap_url).await {
+                Ok(xml) => xml,
+                Err(e) => {
+                    log::error!("❌ Failed to fetch sitemap {}: {}", sitemap_url, e);
+                    self.errors.lock().unwrap().push(format!("{}: {}", sitemap_url, e));
+                    continue;
+                }
+            };
+
+            let locations = parse_sitemap_locations(&xml);
+            if xml.contains("<sitemapindex") {
+                pending.extend(locations);
+                continue;
+            }
+
+            let mut queue = self.queue.lock().unwrap();
+            for url in self.filter_links(&sitemap_url, locations) {
+                queue.push_back(CrawlItem {
+                    url,
+                    depth: 0,
+                    parent_url: None,
+                });
+                seeded += 1;
+            }
+        }
+
+        if seeded > 0 {
+            log::info!("🗺️ Seeded {} URLs from {} sitemap(s)", seeded, fetched);
+        }
+    }
+
+    /// Pause before the next request after fetching `url`
+    fn polite_delay(&self, url: &str) -> Duration {
+        let base = Duration::from_secs_f64(1.0 / self.config.rate_limit);
+        if !self.config.adaptive_delay {
+            return base;
+        }
+
+        let latencies = self.latencies.lock().unwrap();
+        let recent = match latencies.get(&latency_key(url)) {
+            Some(recent) if !recent.is_empty() => recent,
+            _ => return base,
+        };
+
+        let average = recent.iter().sum::<Duration>() / recent.len() as u32;
+        (base + average).min(MAX_ADAPTIVE_DELAY)
+    }
+
+    fn record_latency(&self, url: &str, latency: Duration) {
+        let mut latencies = self.latencies.lock().unwrap();
+        let recent = latencies.entry(latency_key(url)).or_default();
+        if recent.len() == LATENCY_WINDOW {
+            recent.pop_front();
         }
+        recent.push_back(latency);
     }
 
     /// Scrape a single page
-    async fn scrape_page(&self, item: &CrawlItem) -> Result<(ScrapingResult, Vec<String>), Box<dyn std::error::Error>> {
+    async fn scrape_page(&self, item: &CrawlItem) -> crate::error::Result<(ScrapingResult, Vec<String>)> {
+        let mut extra_headers = self.config.extra_headers.clone();
+        if self.config.send_referer {
+            if let Some(ref parent) = item.parent_url {
+                extra_headers.insert("Referer".to_string(), parent.clone());
+            }
+        }
+        if let Some(validators) = self.previous_validators.get(&item.url) {
+            if let Some(ref etag) = validators.etag {
+                extra_headers.insert("If-None-Match".to_string(), etag.clone());
+            }
+            if let Some(ref last_modified) = validators.last_modified {
+                extra_headers.insert("If-Modified-Since".to_string(), last_modified.clone());
+            }
+        }
+
         // Create scraper config
         let scraper_config = ScrapingConfig {
             urls: vec![item.url.clone()],
@@ -258,17 +905,34 @@ impl DeepScraper {
             max_pages: 1,
             rate_limit: self.config.rate_limit,
             custom_selectors: self.config.custom_selectors.clone(),
+            domain_rate_limiter: self.config.domain_rate_limiter.clone(),
+            fetch_cache: Some(self.fetch_cache.clone()),
+            inflight_limiter: self.inflight.clone(),
+            // Rate limit headers and cache validators are read, then cleared
+            capture_headers: true,
+            parse_json: self.config.follow_json_links,
+            extra_headers,
+            ..ScrapingConfig::default()
         };
 
         // Perform scrape
         let scraper = WebScraper::new(&scraper_config, false)?;
-        let session = scraper.scrape(scraper_config).await?;
+        let (result, html) = scraper.scrape_page_with_html(&item.url, 1).await?;
+
+        if let Some(ref json) = result.json {
+            let mut links = Vec::new();
+            json_links(json, &mut links);
+            return Ok((result, links));
+        }
 
-        if session.results.is_empty() {
-            return Err("No results returned".into());
+        if self.config.classify_pages {
+            let mode = StructureAnalyzer::new().analyze(&html, &item.url).recommendations.suggested_mode;
+            self.page_modes.lock().unwrap().insert(item.url.clone(), mode.as_str().to_string());
         }
 
-        let result = session.results[0].clone();
+        if let Some(next) = scraper.next_page_link(&result.content.links, &item.url) {
+            self.next_pages.lock().unwrap().insert(item.url.clone(), next);
+        }
 
         // Extract all links
         let links: Vec<String> = result.content.links.iter()
@@ -278,6 +942,124 @@ impl DeepScraper {
         Ok((result, links))
     }
 
+    fn is_thin(&self, result: &ScrapingResult) -> bool {
+        result.json.is_none() && result.content.char_count < self.config.min_content_length
+    }
+
+    /// Return the URL of an already-stored page with identical content, registering
+    /// this page's content hash when it is new
+    fn find_duplicate(&self, url: &str, hash: &str) -> Option<String> {
+        if !self.config.collapse_duplicates {
+            return None;
+        }
+
+        let mut hashes = self.content_hashes.lock().unwrap();
+        if let Some(canonical) = hashes.get(hash) {
+            return Some(canonical.clone());
+        }
+
+        hashes.insert(hash.to_string(), url.to_string());
+        None
+    }
+
+    /// URL of an already-stored page that `url`'s canonical chain leads to
+    fn canonical_duplicate(&self, url: &str, result: &ScrapingResult) -> Option<String> {
+        if !self.config.collapse_duplicates {
+            return None;
+        }
+
+        if let Some(ref declared) = result.content.canonical_url {
+            let declared = normalize_url_with_options(declared, self.config.fold_index_pages);
+            if declared != url {
+                self.canonicals.lock().unwrap().insert(url.to_string(), declared);
+            }
+        }
+
+        let canonical = self.resolve_canonical(url);
+        if canonical == url {
+            return None;
+        }
+        self.results.lock().unwrap().iter()
+            .any(|stored| stored.url == canonical)
+            .then_some(canonical)
+    }
+
+    /// Follow `rel=canonical` declarations from `url` to the end of the chain.
+    /// Pages naming each other (A -> B -> A) are ambiguous, so a loop resolves
+    /// to `url` itself.
+    fn resolve_canonical(&self, url: &str) -> String {
+        let canonicals = self.canonicals.lock().unwrap();
+        let mut seen = HashSet::from([url.to_string()]);
+        let mut current = url;
+
+        while let Some(next) = canonicals.get(current) {
+            if !seen.insert(next.clone()) {
+                log::warn!("🔁 Canonical loop through {} from {}, keeping the fetched URL", next, url);
+                return url.to_string();
+            }
+            current = next.as_str();
+        }
+
+        current.to_string()
+    }
+
+    /// hreflang alternates of a page in the configured preferred language
+    fn preferred_alternates(&self, result: &ScrapingResult) -> Vec<String> {
+        let preferred = match self.config.preferred_language {
+            Some(ref lang) => lang.to_lowercase(),
+            None => return Vec::new(),
+        };
+
+        result.content.alternates.iter()
+            .filter(|(lang, _)| {
+                let lang = lang.to_lowercase();
+                lang == preferred || lang.starts_with(&format!("{}-", preferred))
+            })
+            .map(|(_, url)| url.clone())
+            .collect()
+    }
+
+    /// Give URLs that failed during the crawl one more attempt
+    /// Retry `failed` once each, handling pages that now load exactly like the
+    /// main loop does. Returns "max_duration" when the crawl's time budget ran out first.
+    async fn retry_failed_items(
+        &self,
+        failed: Vec<CrawlItem>,
+        tally: &mut CrawlTally,
+        started: std::time::Instant,
+    ) -> Option<&'static str> {
+        // Back off well beyond the normal request spacing so transient failures can clear
+        let backoff = Duration::from_secs_f64(4.0 / self.config.rate_limit);
+
+        for item in failed {
+            if tally.pages_crawled >= self.config.max_pages {
+                break;
+            }
+            if self.config.max_duration_secs.is_some_and(|limit| (started.elapsed() + backoff).as_secs_f64() >= limit) {
+                log::info!("⏱️ Out of time, not retrying the remaining failures");
+                return Some("max_duration");
+            }
+
+            tokio::time::sleep(backoff).await;
+            log::info!("🔁 Retrying: {}", item.url);
+
+            match self.scrape_page(&item).await {
+                Err(e) => {
+                    log::warn!("❌ Retry failed for {}: {}", item.url, e);
+                }
+                outcome => {
+                    // Forget the failure; the page is then recorded afresh
+                    let prefix = format!("{}: ", item.url);
+                    self.errors.lock().unwrap().retain(|e| !e.starts_with(&prefix));
+                    self.crawl_tree.lock().unwrap().retain(|node| node.url != item.url);
+                    self.handle_outcome(&item, outcome, tally);
+                }
+            }
+        }
+
+        None
+    }
+
     /// Filter links based on config rules
     fn filter_links(&self, base_url: &str, links: Vec<String>) -> Vec<String> {
         let base_url_parsed = match Url::parse(base_url) {
@@ -289,12 +1071,12 @@ impl DeepScraper {
             .filter_map(|link| {
                 // Resolve relative URLs
                 let absolute_url = match base_url_parsed.join(&link) {
-                    Ok(u) => u.to_string(),
+                    Ok(u) => self.upgrade_insecure(u, &base_url_parsed).to_string(),
                     Err(_) => return None,
                 };
 
                 // Normalize URL
-                let normalized = normalize_url(&absolute_url);
+                let normalized = normalize_url_with_options(&absolute_url, self.config.fold_index_pages);
 
                 // Apply filters
                 if !self.should_crawl(&normalized, &base_url_parsed) {
@@ -306,16 +1088,30 @@ impl DeepScraper {
             .collect()
     }
 
-    /// Determine if a URL should be crawled
-    fn should_crawl(&self, url: &str, base_url: &Url) -> bool {
-        let parsed = match Url::parse(url) {
-            Ok(u) => u,
-            Err(_) => return false,
+    /// Switch `url` to https when `upgrade_insecure` is on and its host is known to serve https
+    fn upgrade_insecure(&self, mut url: Url, base_url: &Url) -> Url {
+        if !self.config.upgrade_insecure || url.scheme() != "http" {
+            return url;
+        }
+
+        let Some(host) = url.host_str() else {
+            return url;
         };
+        let serves_https = |known: &Url| known.scheme() == "https" && known.host_str() == Some(host);
+        let known_https = serves_https(base_url)
+            || self.config.start_urls.iter().filter_map(|start| Url::parse(start).ok()).any(|start| serves_https(&start));
+
+        if known_https {
+            let _ = url.set_scheme("https");
+        }
+        url
+    }
 
-        // Check domain restrictions
+    /// Whether `url` passes the `stay_in_domain` / `stay_in_subdomain` rules relative to `base_url`
+    fn in_domain_scope(&self, url: &Url, base_url: &Url) -> bool {
+        // Check domain restrictions (IP hosts are compared too)
         if self.config.stay_in_domain {
-            if let (Some(base_domain), Some(url_domain)) = (base_url.domain(), parsed.domain()) {
+            if let (Some(base_domain), Some(url_domain)) = (base_url.host_str(), url.host_str()) {
                 if base_domain != url_domain {
                     return false;
                 }
@@ -324,13 +1120,54 @@ impl DeepScraper {
 
         // Check subdomain restrictions
         if self.config.stay_in_subdomain {
-            if let (Some(base_host), Some(url_host)) = (base_url.host_str(), parsed.host_str()) {
+            if let (Some(base_host), Some(url_host)) = (base_url.host_str(), url.host_str()) {
                 if base_host != url_host {
                     return false;
                 }
             }
         }
 
+        true
+    }
+
+    /// Where `result` was redirected to, if that left the domain scope of the requested URL
+    fn off_domain_redirect<'a>(&self, url: &str, result: &'a ScrapingResult) -> Option<&'a str> {
+        let final_url = result.final_url.as_deref()?;
+        let (Ok(requested), Ok(landed)) = (Url::parse(url), Url::parse(final_url)) else {
+            return None;
+        };
+        (!self.in_domain_scope(&landed, &requested)).then_some(final_url)
+    }
+
+    /// Determine if a URL should be crawled
+    fn should_crawl(&self, url: &str, base_url: &Url) -> bool {
+        let parsed = match Url::parse(url) {
+            Ok(u) => u,
+            Err(_) => return false,
+        };
+
+        if !self.in_domain_scope(&parsed, base_url) {
+            return false;
+        }
+
+        // Check file extensions
+        if let Some(extension) = path_extension(&parsed) {
+            let listed = |list: &[String]| list.iter().any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(&extension));
+
+            if listed(&self.config.excluded_extensions) {
+                return false;
+            }
+            if self.config.allowed_extensions.as_deref().is_some_and(|allowed| !listed(allowed)) {
+                return false;
+            }
+        }
+
+        // Check path prefixes
+        if !self.config.path_prefixes.is_empty()
+            && !self.config.path_prefixes.iter().any(|prefix| parsed.path().starts_with(prefix.as_str())) {
+            return false;
+        }
+
         // Check exclude patterns
         for pattern in &self.config.exclude_patterns {
             if let Ok(re) = regex::Regex::new(pattern) {
@@ -359,10 +1196,20 @@ impl DeepScraper {
         true
     }
 
+    /// Claim `url` for fetching. Checking and inserting happen under one lock,
+    /// so when several workers race for the same URL exactly one gets `true`.
+    fn mark_visited(&self, url: &str) -> bool {
+        let key = normalize_url_with_options(url, self.config.fold_index_pages);
+        self.visited.lock().unwrap().insert(key)
+    }
+
     /// Add links to the crawl queue
     fn enqueue_links(&self, parent_url: &str, links: &[String], depth: usize) {
         let mut queue = self.queue.lock().unwrap();
         for link in links {
+            if depth > self.max_depth_for(link) {
+                continue;
+            }
             queue.push_back(CrawlItem {
                 url: link.clone(),
                 depth,
@@ -371,6 +1218,63 @@ impl DeepScraper {
         }
     }
 
+    /// Record links from `item` back to one of its ancestors in the crawl tree
+    fn record_cycles(&self, item: &CrawlItem, links: &[String]) {
+        let key = |url: &str| normalize_url_with_options(url, self.config.fold_index_pages);
+
+        // Ancestors of `item`, root first
+        let mut path = vec![item.url.clone()];
+        {
+            let tree = self.crawl_tree.lock().unwrap();
+            let mut parent = item.parent_url.clone();
+            while let Some(url) = parent {
+                if path.contains(&url) {
+                    break;
+                }
+                parent = tree.iter().find(|node| node.url == url).and_then(|node| node.parent.clone());
+                path.push(url);
+            }
+        }
+        path.reverse();
+        let keys: Vec<String> = path.iter().map(|url| key(url)).collect();
+
+        let mut cycles = self.cycles.lock().unwrap();
+        for link in links {
+            let link_key = key(link);
+            let Some(start) = keys.iter().position(|k| *k == link_key) else {
+                continue;
+            };
+            // A page linking to itself is not a cycle worth reporting
+            if start == path.len() - 1 {
+                continue;
+            }
+
+            let cycle = path[start..].to_vec();
+            if !cycles.iter().any(|known| is_rotation(known, &cycle)) {
+                log::info!("🔁 Link cycle: {} -> {}", cycle.join(" -> "), cycle[0]);
+                cycles.push(cycle);
+            }
+        }
+    }
+
+    /// Depth cap for the host of `url`, falling back to the global `max_depth`
+    fn max_depth_for(&self, url: &str) -> usize {
+        Url::parse(url)
+            .ok()
+            .and_then(|parsed| {
+                let host = parsed.host_str()?.to_string();
+                self.config.per_domain_max_depth.iter()
+                    .find(|(domain, _)| domain.eq_ignore_ascii_case(&host))
+                    .map(|(_, depth)| *depth)
+            })
+            .unwrap_or(self.config.max_depth)
+    }
+
+    /// Deepest depth any host may be crawled to
+    fn depth_ceiling(&self) -> usize {
+        self.config.per_domain_max_depth.values().copied().fold(self.config.max_depth, usize::max)
+    }
+
     /// Update crawl tree with node info
     fn update_crawl_tree(&self, item: &CrawlItem, error: Option<String>) {
         let mut tree = self.crawl_tree.lock().unwrap();
@@ -381,6 +1285,7 @@ impl DeepScraper {
             children: Vec::new(),
             scraped: error.is_none(),
             error,
+            next_page: self.next_pages.lock().unwrap().get(&item.url).cloned(),
         });
     }
 
@@ -413,3 +1318,977 @@ impl DeepScraper {
         }
     }
 }
+
+/// Hash of a page's extracted title and text, used to spot duplicate and unchanged pages
+fn page_hash(result: &ScrapingResult) -> String {
+    let mut text = result.content.title.clone().unwrap_or_default();
+    for block in &result.content.content {
+        text.push('\n');
+        text.push_str(block);
+    }
+    if let Some(ref json) = result.json {
+        text.push('\n');
+        text.push_str(&json.to_string());
+    }
+
+    content_hash(&text)
+}
+
+/// Absolute http(s) URLs and root-relative paths found anywhere in a JSON value
+fn json_links(value: &serde_json::Value, links: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            let url_like = s.starts_with("http://") || s.starts_with("https://")
+                || (s.starts_with('/') && !s.starts_with("//"));
+            if url_like && !s.contains(char::is_whitespace) {
+                links.push(s.to_string());
+            }
+        }
+        serde_json::Value::Array(values) => values.iter().for_each(|v| json_links(v, links)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| json_links(v, links)),
+        _ => {}
+    }
+}
+
+/// Whether `b` is the same cycle as `a`, entered at a different page
+fn is_rotation(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && (0..a.len()).any(|i| a[i..].iter().chain(&a[..i]).eq(b.iter()))
+}
+
+/// Lowercased extension of the URL's last path segment, if it has one
+fn path_extension(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    let (stem, extension) = segment.rsplit_once('.')?;
+
+    (!stem.is_empty() && !extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
+
+/// Longest wait honored for an `X-RateLimit-Reset`
+const MAX_RATELIMIT_PAUSE: Duration = Duration::from_secs(300);
+
+/// How long to wait before the next request, from `Retry-After` or `X-RateLimit-*`
+/// response headers (lowercased names). `Retry-After` wins when present. Otherwise,
+/// with nothing left, wait for the reset; when under a tenth of the limit remains,
+/// spread the remaining requests evenly until the reset.
+fn ratelimit_pause_from(headers: &HashMap<String, String>) -> Option<Duration> {
+    let number = |name: &str| headers.get(name).and_then(|v| v.trim().parse::<f64>().ok());
+
+    // Either seconds to wait or an HTTP date
+    if let Some(retry_after) = headers.get("retry-after").map(|v| v.trim()) {
+        let pause = match retry_after.parse::<f64>() {
+            Ok(seconds) => Some(seconds),
+            Err(_) => chrono::DateTime::parse_from_rfc2822(retry_after)
+                .ok()
+                .map(|date| (date.timestamp() - chrono::Utc::now().timestamp()) as f64),
+        };
+        if let Some(pause) = pause.filter(|pause| pause.is_finite()) {
+            return Some(Duration::from_secs_f64(pause.clamp(0.0, MAX_RATELIMIT_PAUSE.as_secs_f64())));
+        }
+    }
+
+    let remaining = number("x-ratelimit-remaining")?;
+
+    // Either seconds until the reset or, GitHub-style, a Unix timestamp
+    let reset = number("x-ratelimit-reset").map(|reset| {
+        if reset > 1_000_000_000.0 {
+            (reset - chrono::Utc::now().timestamp() as f64).max(0.0)
+        } else {
+            reset
+        }
+    });
+
+    let pause = if remaining <= 0.0 {
+        reset.unwrap_or(1.0)
+    } else {
+        let limit = number("x-ratelimit-limit")?;
+        if remaining >= limit * 0.1 {
+            return None;
+        }
+        reset? / (remaining + 1.0)
+    };
+
+    Some(Duration::from_secs_f64(pause).min(MAX_RATELIMIT_PAUSE))
+}
+
+/// Response times averaged by `adaptive_delay`
+const LATENCY_WINDOW: usize = 5;
+
+/// Upper bound of the adaptive delay, however slow the host gets
+const MAX_ADAPTIVE_DELAY: Duration = Duration::from_secs(30);
+
+/// Host and port of a URL, so sites sharing a host on different ports are tracked apart
+fn latency_key(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| Some(format!("{}:{}", u.host_str()?, u.port_or_known_default()?)))
+        .unwrap_or_default()
+}
+
+/// Coarse category of a crawl error message, for the summary
+fn error_category(error: &str) -> &'static str {
+    if let Some(cap) = ERROR_STATUS.captures(error) {
+        let class = cap.get(1).or_else(|| cap.get(2)).map(|m| m.as_str()).unwrap_or("");
+        return if class == "4" || class == "client" { "http_4xx" } else { "http_5xx" };
+    }
+
+    if error.contains("Failed to fetch page") || error.contains("Failed to read response body") {
+        "network"
+    } else {
+        "other"
+    }
+}
+
+// "HTTP error: 404 Not Found" from the scraper, "HTTP status client error (404 ...)" from reqwest
+static ERROR_STATUS: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"HTTP error: ([45])\d\d|status (client|server) error").unwrap());
+
+/// Most URLs a single templated seed may expand into
+const MAX_SEED_EXPANSION: usize = 100_000;
+
+static SEED_RANGE: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\{(\d+)\.\.(\d+)(?:\.\.(\d+))?\}").unwrap());
+
+/// Expand every `{start..end[..step]}` range in a seed URL; URLs without one pass through
+fn expand_seed(url: &str) -> Vec<String> {
+    let mut seeds = Vec::new();
+    if !expand_seed_into(url, &mut seeds) {
+        log::warn!("Seed {} expands to more than {} URLs, keeping the first {}", url, MAX_SEED_EXPANSION, MAX_SEED_EXPANSION);
+    }
+    seeds
+}
+
+/// Push the expansions of `url` onto `seeds`, stopping once it holds
+/// `MAX_SEED_EXPANSION` URLs. Returns false if that cut the expansion short.
+fn expand_seed_into(url: &str, seeds: &mut Vec<String>) -> bool {
+    if seeds.len() >= MAX_SEED_EXPANSION {
+        return false;
+    }
+
+    let parsed = SEED_RANGE.captures(url).and_then(|cap| {
+        let range = cap.get(0).unwrap();
+        let (start, end) = (cap[1].parse::<u64>().ok()?, cap[2].parse::<u64>().ok()?);
+        let step = cap.get(3).and_then(|s| s.as_str().parse::<u64>().ok()).unwrap_or(1).max(1);
+        let width = if cap[1].len() > 1 && cap[1].starts_with('0') { cap[1].len() } else { 0 };
+        Some((range.start(), range.end(), start, end, step, width))
+    });
+    let Some((range_start, range_end, start, end, step, width)) = parsed else {
+        seeds.push(url.to_string());
+        return true;
+    };
+
+    // Generated lazily: a huge range must not be materialized before the cap applies
+    let count = start.abs_diff(end) / step + 1;
+    let numbers = (0..count).map(|i| if start <= end { start + i * step } else { start - i * step });
+
+    for n in numbers {
+        let seed = format!("{}{:0width$}{}", &url[..range_start], n, &url[range_end..], width = width);
+        // Later ranges in the same URL draw on the same budget
+        if !expand_seed_into(&seed, seeds) {
+            return false;
+        }
+    }
+
+    true
+}
+
+static SITEMAP_LOC: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"(?s)<loc>\s*(.*?)\s*</loc>").unwrap());
+
+/// `<loc>` values of a sitemap or sitemap index, entity-decoded
+fn parse_sitemap_locations(xml: &str) -> Vec<String> {
+    SITEMAP_LOC.captures_iter(xml)
+        .map(|cap| {
+            cap[1]
+                .trim_start_matches("<![CDATA[")
+                .trim_end_matches("]]>")
+                .replace("&apos;", "'")
+                .replace("&quot;", "\"")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&amp;", "&")
+        })
+        .filter(|loc| !loc.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn test_config(start_urls: Vec<String>) -> DeepScrapeConfig {
+        DeepScrapeConfig {
+            start_urls,
+            max_depth: 0,
+            rate_limit: 100.0,
+            min_content_length: 0,
+            ..DeepScrapeConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_seed_range_expansion() {
+        let scraper = DeepScraper::new(test_config(vec!["https://site.example/item/{1..5}".to_string()]));
+        let queued: Vec<String> = scraper.queue.lock().unwrap().iter().map(|item| item.url.clone()).collect();
+
+        assert_eq!(queued, (1..=5).map(|n| format!("https://site.example/item/{}", n)).collect::<Vec<_>>());
+        assert_eq!(scraper.config.start_urls.len(), 5);
+
+        assert_eq!(
+            expand_seed("https://site.example/p/{008..12..2}?tab=1"),
+            vec!["https://site.example/p/008?tab=1", "https://site.example/p/010?tab=1", "https://site.example/p/012?tab=1"]
+        );
+        assert_eq!(expand_seed("https://site.example/plain"), vec!["https://site.example/plain"]);
+        assert_eq!(expand_seed("https://site.example/p/{3..1}"), vec![
+            "https://site.example/p/3", "https://site.example/p/2", "https://site.example/p/1"
+        ]);
+    }
+
+    #[test]
+    fn test_seed_expansion_is_capped_across_ranges() {
+        let huge = expand_seed("https://site.example/item/{1..99999999999}");
+        assert_eq!(huge.len(), MAX_SEED_EXPANSION);
+        assert_eq!(huge.last().unwrap(), &format!("https://site.example/item/{}", MAX_SEED_EXPANSION));
+
+        // 1000 x 1000 combinations share one budget
+        let nested = expand_seed("https://site.example/{1..1000}/page/{1..1000}");
+        assert_eq!(nested.len(), MAX_SEED_EXPANSION);
+        assert_eq!(nested[1000], "https://site.example/2/page/1");
+    }
+
+    #[tokio::test]
+    async fn test_summary_depth_histogram() {
+        // / -> /a, /b ; /a -> /a/1 ; /missing is a broken link from /b
+        let server = MockServer::start(|request| {
+            let links = match request.path.as_str() {
+                "/" => r#"<a href="/a">A</a><a href="/b">B</a>"#,
+                "/a" => r#"<a href="/a/1">A1</a>"#,
+                "/b" => r#"<a href="/missing">Gone</a>"#,
+                "/missing" => return MockResponse::status(404),
+                _ => "",
+            };
+            MockResponse::html(&format!("<html><body><h1>Page {}</h1>{}</body></html>", request.path, links))
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 2;
+
+        let unclassified = DeepScraper::new(config.clone()).scrape().await;
+        assert!(unclassified.summary.pages_by_mode.is_empty());
+
+        config.classify_pages = true;
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.summary.pages_per_depth, vec![1, 2, 1]);
+        assert_eq!(result.summary.pages_by_mode.values().sum::<usize>(), 4);
+        assert_eq!(result.summary.errors_by_category.get("http_4xx"), Some(&1));
+        assert!(result.summary.average_quality.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_path_prefixes_restrict_crawl_scope() {
+        let server = MockServer::start(|request| {
+            MockResponse::html(&format!(
+                r#"<html><body><h1>{}</h1><a href="/docs/api">API</a><a href="/blog/launch">Blog</a></body></html>"#,
+                request.path
+            ))
+        }).await;
+
+        let mut config = test_config(vec![server.url("/docs/intro")]);
+        config.max_depth = 1;
+        config.path_prefixes = vec!["/docs/".to_string()];
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(server.hits("/docs/api"), 1);
+        assert_eq!(server.hits("/blog/launch"), 0);
+        assert_eq!(result.results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_aborts_after_consecutive_errors() {
+        let server = MockServer::start(|_| MockResponse::status(403)).await;
+
+        let mut config = test_config(vec![server.url("/item/{1..10}")]);
+        config.max_consecutive_errors = Some(3);
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.errors.len(), 3);
+        assert_eq!(server.hits("/item/4"), 0);
+        assert_eq!(result.budget_exhausted_reason.as_deref(), Some("max_consecutive_errors"));
+        assert_eq!(result.status, CrawlStatus::Failed);
+    }
+
+    #[test]
+    fn test_extension_filters_ignore_case_and_query() {
+        let base = Url::parse("https://site.example/").unwrap();
+
+        let scraper = DeepScraper::new(test_config(Vec::new()));
+        assert!(!scraper.should_crawl("https://site.example/files/REPORT.PDF", &base));
+        assert!(!scraper.should_crawl("https://site.example/files/report.pdf?download=1", &base));
+        assert!(!scraper.should_crawl("https://site.example/img/Photo.JpG#zoom", &base));
+        assert!(scraper.should_crawl("https://site.example/docs/intro", &base));
+        assert!(scraper.should_crawl("https://site.example/v1.2/", &base));
+
+        let mut config = test_config(Vec::new());
+        config.allowed_extensions = Some(vec![".html".to_string()]);
+        let scraper = DeepScraper::new(config);
+        assert!(scraper.should_crawl("https://site.example/guide/Index.HTML?lang=en", &base));
+        assert!(scraper.should_crawl("https://site.example/guide/", &base));
+        assert!(!scraper.should_crawl("https://site.example/guide/page.php", &base));
+    }
+
+    #[tokio::test]
+    async fn test_retry_failed_recovers_transient_error() {
+        let attempts = AtomicUsize::new(0);
+        let server = MockServer::start(move |_| {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                MockResponse::status(503)
+            } else {
+                MockResponse::html("<html><body><h1>Recovered</h1><p>Content after a transient failure.</p></body></html>")
+            }
+        }).await;
+
+        let url = server.url("/flaky");
+        let mut config = test_config(vec![url.clone()]);
+        config.retry_failed = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].url, url);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.status, CrawlStatus::Completed);
+        assert!(result.crawl_tree.iter().all(|node| node.scraped));
+    }
+
+    #[tokio::test]
+    async fn test_retried_pages_are_classified_like_any_other() {
+        let attempts = AtomicUsize::new(0);
+        let server = MockServer::start(move |_| {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                MockResponse::status(503)
+            } else {
+                MockResponse::html(
+                    "<html><head><title>Page not found</title></head><body><h1>Sorry</h1><p>That page doesn't exist.</p></body></html>"
+                )
+            }
+        }).await;
+
+        let url = server.url("/gone");
+        let mut config = test_config(vec![url.clone()]);
+        config.retry_failed = true;
+        config.skip_soft_404 = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert!(result.results.is_empty());
+        assert_eq!(result.soft_404_urls, vec![url]);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.crawl_tree.len(), 1);
+        assert!(result.crawl_tree[0].scraped);
+    }
+
+    #[tokio::test]
+    async fn test_retries_stay_within_max_duration() {
+        let server = MockServer::start(|_| MockResponse::status(503)).await;
+
+        let mut config = test_config(vec![server.url("/down")]);
+        config.retry_failed = true;
+        // The retry backoff (4s at one request a second) doesn't fit in the budget
+        config.rate_limit = 1.0;
+        config.max_duration_secs = Some(2.0);
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(server.hits("/down"), 1);
+        assert_eq!(result.budget_exhausted_reason.as_deref(), Some("max_duration"));
+        assert!(result.elapsed_seconds < 2.0, "elapsed {}", result.elapsed_seconds);
+        assert_eq!(result.errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_collapse_duplicates_records_alias() {
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Same Page</h1><p>Identical body served under many URLs.</p></body></html>")
+        }).await;
+
+        let first = server.url("/a");
+        let second = server.url("/b?ref=nav");
+        let mut config = test_config(vec![first.clone(), second.clone()]);
+        config.collapse_duplicates = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].url, first);
+        assert_eq!(result.duplicates.get(&second), Some(&first));
+    }
+
+    #[tokio::test]
+    async fn test_canonical_loop_falls_back_to_fetched_url() {
+        let server = MockServer::start(|request| {
+            let other = if request.path == "/a" { "/b" } else { "/a" };
+            MockResponse::html(&format!(
+                r#"<html><head><link rel="canonical" href="{}"></head><body><h1>Page {}</h1></body></html>"#,
+                other, request.path
+            ))
+        }).await;
+
+        let mut config = test_config(vec![server.url("/a"), server.url("/b")]);
+        config.collapse_duplicates = true;
+        let mut scraper = DeepScraper::new(config);
+        let result = tokio::time::timeout(Duration::from_secs(10), scraper.scrape()).await
+            .expect("canonical resolution looped");
+
+        // Neither page wins the loop: both are kept under their own URL
+        assert_eq!(result.results.len(), 2);
+        assert!(result.duplicates.is_empty());
+        assert_eq!(scraper.resolve_canonical(&server.url("/a")), server.url("/a"));
+        assert_eq!(scraper.resolve_canonical(&server.url("/b")), server.url("/b"));
+    }
+
+    #[tokio::test]
+    async fn test_reports_max_pages_budget() {
+        let server = MockServer::start(|request| {
+            MockResponse::html(&format!("<html><body><h1>{}</h1></body></html>", request.path))
+        }).await;
+
+        let mut config = test_config(vec![server.url("/one"), server.url("/two")]);
+        config.max_pages = 1;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.total_pages_crawled, 1);
+        assert_eq!(result.budget_exhausted_reason.as_deref(), Some("max_pages"));
+        assert!(result.elapsed_seconds >= 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_fold_index_pages_dedups_directory_and_index() {
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Home</h1></body></html>")
+        }).await;
+
+        let mut config = test_config(vec![server.url("/"), server.url("/index.html")]);
+        config.fold_index_pages = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(server.hits("/index.html"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_depth_zero_scrapes_only_sitemap_seeds() {
+        let server = MockServer::start(|request| {
+            if request.path == "/sitemap.xml" {
+                let urls: String = (1..=5)
+                    .map(|i| format!("<url><loc>http://{}/page/{}</loc></url>", request.header("Host").unwrap(), i))
+                    .collect();
+                return MockResponse::status(200)
+                    .with_header("Content-Type", "application/xml")
+                    .with_body(format!(r#"<?xml version="1.0"?><urlset>{}</urlset>"#, urls).as_bytes());
+            }
+            MockResponse::html(&format!(
+                r#"<html><body><h1>{}</h1><a href="/undiscovered">More</a></body></html>"#,
+                request.path
+            ))
+        }).await;
+
+        let mut config = test_config(Vec::new());
+        config.sitemap_urls = vec![server.url("/sitemap.xml")];
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.results.len(), 5);
+        for i in 1..=5 {
+            assert_eq!(server.hits(&format!("/page/{}", i)), 1);
+        }
+        assert_eq!(server.hits("/undiscovered"), 0);
+        assert!(result.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sitemap_is_fetched_like_pages_and_seeds_are_filtered() {
+        let server = MockServer::start(|request| {
+            if request.path == "/sitemap.xml" {
+                let host = request.header("Host").unwrap();
+                let body = format!(
+                    r#"<?xml version="1.0"?><urlset><url><loc>http://{host}/kept</loc></url><url><loc>http://{host}/private/page</loc></url><url><loc>http://{host}/report.pdf</loc></url><url><loc>https://elsewhere.example/page</loc></url></urlset>"#
+                );
+                return MockResponse::status(200)
+                    .with_header("Content-Type", "application/xml")
+                    .with_body(body.as_bytes());
+            }
+            MockResponse::html(&format!("<html><body><h1>{}</h1></body></html>", request.path))
+        }).await;
+
+        let mut config = test_config(Vec::new());
+        config.sitemap_urls = vec![server.url("/sitemap.xml")];
+        config.exclude_patterns = vec!["/private/".to_string()];
+        config.extra_headers.insert("X-Crawl".to_string(), "docs".to_string());
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        let sitemap = server.requests().into_iter().find(|request| request.path == "/sitemap.xml").unwrap();
+        assert_eq!(sitemap.header("X-Crawl"), Some("docs"));
+        assert!(sitemap.header("User-Agent").is_some_and(|agent| !agent.is_empty()));
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].url, server.url("/kept"));
+        assert_eq!(server.hits("/private/page"), 0);
+        assert_eq!(server.hits("/report.pdf"), 0);
+        assert!(result.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_crawl_reprocesses_only_changed_pages() {
+        let revision = Arc::new(AtomicUsize::new(1));
+        let current = revision.clone();
+        let server = MockServer::start(move |request| {
+            let body = if request.path == "/b" {
+                format!("Page b content, revision {}.", current.load(Ordering::SeqCst))
+            } else {
+                format!("Page {} content that never changes.", request.path)
+            };
+            MockResponse::html(&format!("<html><body><h1>{}</h1><p>{}</p></body></html>", request.path, body))
+        }).await;
+
+        let urls = vec![server.url("/a"), server.url("/b"), server.url("/c")];
+        let first = DeepScraper::new(test_config(urls.clone())).scrape().await;
+        assert_eq!(first.results.len(), 3);
+        assert_eq!(first.content_hashes.len(), 3);
+
+        revision.store(2, Ordering::SeqCst);
+        let second = DeepScraper::new(test_config(urls.clone()))
+            .incremental_against(&first)
+            .scrape()
+            .await;
+
+        assert_eq!(second.changed_urls, vec![urls[1].clone()]);
+        assert_eq!(second.unchanged_urls, vec![urls[0].clone(), urls[2].clone()]);
+        assert_eq!(second.results.len(), 1);
+        assert_eq!(second.results[0].url, urls[1]);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_crawl_sends_validators_and_skips_not_modified() {
+        let server = MockServer::start(|request| {
+            match (request.path.as_str(), request.header("If-None-Match")) {
+                ("/", Some("\"v1\"")) => MockResponse::status(304),
+                ("/", _) => MockResponse::html(r#"<html><body><h1>Home</h1><p>Home page.</p><a href="/child">Child</a></body></html>"#)
+                    .with_header("ETag", "\"v1\""),
+                _ => MockResponse::html("<html><body><h1>Child</h1><p>Child page.</p></body></html>")
+                    .with_header("Last-Modified", "Wed, 14 Oct 2026 08:00:00 GMT"),
+            }
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 1;
+        let first = DeepScraper::new(config.clone()).scrape().await;
+        assert_eq!(first.validators[&server.url("/")].etag.as_deref(), Some("\"v1\""));
+        assert!(first.results.iter().all(|page| page.headers.is_empty()));
+
+        let second = DeepScraper::new(config)
+            .incremental_against(&first)
+            .scrape()
+            .await;
+
+        let child = server.requests().into_iter().rfind(|request| request.path == "/child").unwrap();
+        assert_eq!(child.header("If-Modified-Since"), Some("Wed, 14 Oct 2026 08:00:00 GMT"));
+        assert!(second.errors.is_empty());
+        assert_eq!(second.unchanged_urls, vec![server.url("/"), server.url("/child")]);
+        assert_eq!(second.content_hashes, first.content_hashes);
+        assert_eq!(second.validators, first.validators);
+        assert!(second.results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prune_unproductive_skips_thin_branches_on_recrawl() {
+        let server = MockServer::start(|request| {
+            let links = match request.path.as_str() {
+                "/" => r#"<a href="/good">Good</a><a href="/thin">Thin</a>"#,
+                "/good" => r#"<a href="/good/1">More</a>"#,
+                "/thin" => r#"<a href="/thin/1">More</a>"#,
+                _ => "",
+            };
+            let text = if request.path.starts_with("/thin") {
+                "Short.".to_string()
+            } else {
+                "A full paragraph of real article text. ".repeat(10)
+            };
+            MockResponse::html(&format!("<html><body><article><p>{}</p></article>{}</body></html>", text, links))
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 2;
+        config.min_content_length = 100;
+
+        let first = DeepScraper::new(config.clone()).scrape().await;
+        assert_eq!(first.branch_yield[&server.url("/thin")], 0);
+        assert_eq!(first.branch_yield[&server.url("/good")], 2);
+        assert_eq!(first.branch_yield[&server.url("/")], 3);
+
+        config.prune_unproductive = true;
+        let second = DeepScraper::new(config)
+            .incremental_against(&first)
+            .scrape()
+            .await;
+
+        assert_eq!(server.hits("/thin"), 2);
+        assert_eq!(server.hits("/thin/1"), 1);
+        assert_eq!(server.hits("/good/1"), 2);
+        assert_eq!(second.unchanged_urls.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_link_cycles_are_reported_once() {
+        let server = MockServer::start(|request| {
+            let next = match request.path.as_str() {
+                "/a" => "/b",
+                "/b" => "/c",
+                _ => "/a",
+            };
+            MockResponse::html(&format!(
+                r#"<html><body><h1>{}</h1><p>Page in a loop.</p><a href="{}">Next</a><a href="{}">Self</a></body></html>"#,
+                request.path, next, request.path
+            ))
+        }).await;
+
+        let mut config = test_config(vec![server.url("/a")]);
+        config.max_depth = 5;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.cycles, vec![vec![server.url("/a"), server.url("/b"), server.url("/c")]]);
+        assert!(is_rotation(&result.cycles[0], &[server.url("/c"), server.url("/a"), server.url("/b")]));
+    }
+
+    #[tokio::test]
+    async fn test_follow_json_links_crawls_urls_inside_json() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/api/items.json" => MockResponse::status(200)
+                .with_header("Content-Type", "application/json; charset=utf-8")
+                .with_body(br#"{
+                    "items": [{"id": 1, "url": "/products/1"}, {"id": 2, "links": {"self": "/products/2"}}],
+                    "source": "https://elsewhere.example/feed",
+                    "title": "Not a link / really"
+                }"#),
+            path => MockResponse::html(&format!("<html><body><h1>{}</h1><p>Product page.</p></body></html>", path)),
+        }).await;
+
+        let mut config = test_config(vec![server.url("/api/items.json")]);
+        config.max_depth = 1;
+        config.follow_json_links = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(server.hits("/products/1"), 1);
+        assert_eq!(server.hits("/products/2"), 1);
+        assert_eq!(result.total_pages_crawled, 3);
+
+        let json = result.results.iter()
+            .find(|r| r.url == server.url("/api/items.json"))
+            .and_then(|r| r.json.as_ref())
+            .unwrap();
+        assert_eq!(json["items"][0]["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_soft_404_pages_are_not_stored() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::html(r#"<html><body><h1>Home</h1><p>Welcome.</p><a href="/old-product">Old</a></body></html>"#),
+            _ => MockResponse::html(
+                "<html><head><title>Page not found</title></head><body><h1>Sorry</h1><p>That page doesn't exist.</p></body></html>"
+            ),
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 1;
+        config.skip_soft_404 = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.soft_404_urls, vec![server.url("/old-product")]);
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].url, server.url("/"));
+        assert!(!result.results[0].content.soft_404);
+    }
+
+    #[tokio::test]
+    async fn test_thin_pages_are_crawled_but_not_stored() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::html(r#"<html><body><p>Hi</p><a href="/full">More</a></body></html>"#),
+            _ => MockResponse::html(&format!(
+                "<html><body><article><p>{}</p></article></body></html>",
+                "A full paragraph of real article text. ".repeat(10)
+            )),
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 1;
+        config.min_content_length = 100;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.thin_urls, vec![server.url("/")]);
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].url, server.url("/full"));
+        assert_eq!(result.total_pages_crawled, 2);
+    }
+
+    #[tokio::test]
+    async fn test_preferred_language_alternates_are_followed_and_counted() {
+        // The start page has no anchors at all, only an hreflang alternate
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::html(
+                r#"<html><head><link rel="alternate" hreflang="de-AT" href="/de"></head><body><h1>Home</h1></body></html>"#,
+            ),
+            _ => MockResponse::html(r#"<html><body><h1>Start</h1><a href="/">Home</a><a href="/kontakt">Kontakt</a></body></html>"#),
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 1;
+        config.preferred_language = Some("de".to_string());
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(server.hits("/de"), 1);
+        assert_eq!(server.hits("/kontakt"), 0);
+        assert_eq!(result.total_pages_crawled, 2);
+        assert_eq!(result.total_links_discovered, 3);
+        assert_eq!(result.total_links_filtered, 0);
+    }
+
+    #[tokio::test]
+    async fn test_per_domain_max_depth_caps_each_host() {
+        // "/a1" links to "/a2", and so on
+        fn chain(prefix: &str, path: &str) -> MockResponse {
+            let next = path.trim_start_matches(&format!("/{}", prefix)).parse::<usize>().unwrap_or(0) + 1;
+            MockResponse::html(&format!(r#"<html><body><h1>Page</h1><a href="/{}{}">Next</a></body></html>"#, prefix, next))
+        }
+
+        let partner = MockServer::start(|request| chain("b", &request.path)).await;
+        // Same mock server under a second host name
+        let partner_link = partner.url("/b1").replace("127.0.0.1", "localhost");
+        let main = MockServer::start(move |request| match request.path.as_str() {
+            "/" => MockResponse::html(&format!(
+                r#"<html><body><h1>Home</h1><a href="/a1">Docs</a><a href="{}">Partner</a></body></html>"#,
+                partner_link
+            )),
+            path => chain("a", path),
+        }).await;
+
+        let mut config = test_config(vec![main.url("/")]);
+        config.max_depth = 5;
+        config.stay_in_domain = false;
+        config.per_domain_max_depth.insert("127.0.0.1".to_string(), 2);
+        config.per_domain_max_depth.insert("localhost".to_string(), 1);
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        let main_paths: Vec<String> = main.requests().iter().map(|r| r.path.clone()).collect();
+        let partner_paths: Vec<String> = partner.requests().iter().map(|r| r.path.clone()).collect();
+        assert_eq!(main_paths, vec!["/", "/a1", "/a2"]);
+        assert_eq!(partner_paths, vec!["/b1"]);
+        assert_eq!(result.total_pages_crawled, 4);
+    }
+
+    #[test]
+    fn test_upgrade_insecure_links_on_https_hosts() {
+        let mut config = test_config(vec!["https://site.example/".to_string()]);
+        config.stay_in_domain = false;
+        config.upgrade_insecure = true;
+        let scraper = DeepScraper::new(config);
+
+        let links = scraper.filter_links("https://site.example/docs", vec![
+            "http://site.example/guide".to_string(),
+            "http://plain.example/page".to_string(),
+        ]);
+
+        assert_eq!(links, vec!["https://site.example/guide", "http://plain.example/page"]);
+    }
+
+    #[tokio::test]
+    async fn test_off_domain_redirect_is_not_stored() {
+        let elsewhere = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Elsewhere</h1><p>Another site's page.</p></body></html>")
+        }).await;
+        // Same machine, different host name
+        let target = elsewhere.url("/landing").replace("127.0.0.1", "localhost");
+        let server = MockServer::start(move |_| MockResponse::status(301).with_header("Location", &target)).await;
+
+        let result = DeepScraper::new(test_config(vec![server.url("/moved")])).scrape().await;
+
+        assert_eq!(elsewhere.hits("/landing"), 1);
+        assert!(result.results.is_empty());
+        assert_eq!(result.total_links_filtered, 1);
+        assert!(result.crawl_tree[0].error.as_deref().is_some_and(|e| e.starts_with("Redirected off-domain")));
+    }
+
+    #[tokio::test]
+    async fn test_crawl_node_records_next_page_link() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/list" => MockResponse::html(r#"<html><body><h1>Listing</h1><a href="/list?page=2">Next »</a></body></html>"#),
+            _ => MockResponse::html("<html><body><h1>Item</h1></body></html>"),
+        }).await;
+
+        let result = DeepScraper::new(test_config(vec![server.url("/list")])).scrape().await;
+
+        // max_depth 0: the next page is recorded even though it wasn't followed
+        assert_eq!(result.crawl_tree.len(), 1);
+        assert_eq!(result.crawl_tree[0].next_page, Some(server.url("/list?page=2")));
+        assert_eq!(server.hits("/list"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_child_fetch_sends_parent_as_referer() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::html(r#"<html><body><h1>Home</h1><a href="/child">Child</a></body></html>"#),
+            _ => MockResponse::html("<html><body><h1>Child</h1></body></html>"),
+        }).await;
+
+        let mut config = test_config(vec![server.url("/")]);
+        config.max_depth = 1;
+        config.send_referer = true;
+        config.extra_headers.insert("X-Crawl".to_string(), "docs".to_string());
+
+        DeepScraper::new(config).scrape().await;
+
+        let requests = server.requests();
+        let root = requests.iter().find(|r| r.path == "/").unwrap();
+        let child = requests.iter().find(|r| r.path == "/child").unwrap();
+        assert_eq!(root.header("Referer"), None);
+        assert_eq!(child.header("Referer"), Some(server.url("/").as_str()));
+        assert_eq!(child.header("X-Crawl"), Some("docs"));
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_delay_grows_with_response_time() {
+        let fast = MockServer::start(|_| MockResponse::html("<html><body><h1>Fast</h1></body></html>")).await;
+        let slow = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Slow</h1></body></html>")
+                .with_delay(Duration::from_millis(300))
+        }).await;
+
+        let mut config = test_config(vec![fast.url("/"), slow.url("/")]);
+        config.adaptive_delay = true;
+        let mut scraper = DeepScraper::new(config);
+        scraper.scrape().await;
+
+        let base = Duration::from_secs_f64(1.0 / 100.0);
+        let fast_delay = scraper.polite_delay(&fast.url("/next"));
+        let slow_delay = scraper.polite_delay(&slow.url("/next"));
+
+        assert!(slow_delay >= base + Duration::from_millis(300));
+        assert!(slow_delay > fast_delay);
+        assert!(fast_delay < base + Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_global_max_inflight_caps_concurrent_fetches() {
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Slow</h1></body></html>").with_delay(Duration::from_millis(150))
+        }).await;
+
+        let urls = (0..6).map(|i| server.url(&format!("/page/{}", i))).collect();
+        let mut config = test_config(urls);
+        config.concurrency = 6;
+        config.global_max_inflight = 2;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.total_pages_crawled, 6);
+        assert!(result.errors.is_empty());
+        assert_eq!(server.requests().len(), 6);
+        server.assert_max_in_flight(2, Duration::from_millis(150));
+        // Three rounds of two, not six fetches one after another
+        assert!(result.elapsed_seconds < 0.8, "elapsed {}", result.elapsed_seconds);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_visited_claim_is_atomic() {
+        let scraper = Arc::new(DeepScraper::new(test_config(Vec::new())));
+
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let scraper = scraper.clone();
+                tokio::spawn(async move {
+                    (0..500)
+                        .filter(|i| scraper.mark_visited(&format!("https://site.example/page/{}", i)))
+                        .count()
+                })
+            })
+            .collect();
+
+        let mut claimed = 0;
+        for task in tasks {
+            claimed += task.await.unwrap();
+        }
+        assert_eq!(claimed, 500);
+
+        // Overlapping seeds are fetched once
+        let server = MockServer::start(|_| MockResponse::html("<html><body><h1>Shared</h1></body></html>")).await;
+        let seeds = vec![server.url("/shared"), server.url("/shared/"), server.url("/shared#top")];
+        DeepScraper::new(test_config(seeds)).scrape().await;
+        assert_eq!(server.hits("/shared"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ratelimit_headers_pause_when_exhausted() {
+        let served = Arc::new(AtomicUsize::new(0));
+        let counter = served.clone();
+        let server = MockServer::start(move |_| {
+            let remaining = 1usize.saturating_sub(counter.fetch_add(1, Ordering::SeqCst));
+            MockResponse::html("<html><body><h1>Limited</h1></body></html>")
+                .with_header("X-RateLimit-Limit", "2")
+                .with_header("X-RateLimit-Remaining", &remaining.to_string())
+                .with_header("X-RateLimit-Reset", "1")
+        }).await;
+
+        let urls = vec![server.url("/a"), server.url("/b"), server.url("/c")];
+        let mut config = test_config(urls.clone());
+        config.respect_ratelimit_headers = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.total_pages_crawled, 3);
+        // Remaining hits 0 on /b, so /c waits for the 1s reset
+        assert!(result.elapsed_seconds >= 0.9, "elapsed {}", result.elapsed_seconds);
+        assert!(result.results.iter().all(|page| page.headers.is_empty()));
+
+        let mut headers = HashMap::new();
+        headers.insert("x-ratelimit-remaining".to_string(), "50".to_string());
+        headers.insert("x-ratelimit-limit".to_string(), "60".to_string());
+        assert_eq!(ratelimit_pause_from(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_on_rate_limited_errors_pauses() {
+        let server = MockServer::start(|request| {
+            if request.path == "/a" {
+                return MockResponse::status(429).with_header("Retry-After", "1");
+            }
+            MockResponse::html("<html><body><h1>Fine</h1></body></html>")
+        }).await;
+
+        let mut config = test_config(vec![server.url("/a"), server.url("/b")]);
+        config.respect_ratelimit_headers = true;
+
+        let result = DeepScraper::new(config).scrape().await;
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.total_pages_crawled, 1);
+        assert!(result.elapsed_seconds >= 0.9, "elapsed {}", result.elapsed_seconds);
+
+        let mut headers = HashMap::new();
+        headers.insert("retry-after".to_string(), "Wed, 21 Oct 2015 07:28:00 GMT".to_string());
+        assert_eq!(ratelimit_pause_from(&headers), Some(Duration::ZERO));
+        headers.insert("retry-after".to_string(), "1e300".to_string());
+        assert_eq!(ratelimit_pause_from(&headers), Some(MAX_RATELIMIT_PAUSE));
+    }
+}
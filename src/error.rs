@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Everything the library can fail with
+#[derive(Debug, Error)]
+pub enum ScraperError {
+    /// The request could not be sent, or its body not read
+    #[error("Failed to fetch page {url}: {source}")]
+    Fetch {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The server answered with a non-success status
+    #[error("HTTP error: {status}")]
+    Http {
+        url: String,
+        status: reqwest::StatusCode,
+        /// Response headers under lowercased names, such as `retry-after` on a 429
+        headers: HashMap<String, String>,
+    },
+
+    /// A CSS selector that does not parse
+    #[error("Invalid selector '{selector}': {message}")]
+    Selector { selector: String, message: String },
+
+    /// Input that could not be parsed, such as a malformed URL
+    #[error("Failed to parse {0}")]
+    Parse(String),
+
+    /// A configuration value that can't be used
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// Failures of user-supplied hooks such as render backends and request interceptors
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl ScraperError {
+    /// Status code of an `Http` error
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            ScraperError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ScraperError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_variants() {
+        // Nothing listens on port 9 of localhost
+        let source = reqwest::get("http://127.0.0.1:9/").await.unwrap_err();
+        let fetch = ScraperError::Fetch { url: "http://127.0.0.1:9/".to_string(), source };
+        assert!(matches!(fetch, ScraperError::Fetch { ref url, .. } if url == "http://127.0.0.1:9/"));
+        assert!(fetch.to_string().starts_with("Failed to fetch page"));
+
+        let http = ScraperError::Http { url: "https://example.com/".to_string(), status: reqwest::StatusCode::NOT_FOUND, headers: HashMap::new() };
+        assert_eq!(http.status(), Some(reqwest::StatusCode::NOT_FOUND));
+        assert_eq!(http.to_string(), "HTTP error: 404 Not Found");
+
+        let selector = ScraperError::Selector { selector: "div[".to_string(), message: "unexpected end".to_string() };
+        assert!(matches!(selector, ScraperError::Selector { .. }));
+        assert_eq!(selector.to_string(), "Invalid selector 'div[': unexpected end");
+
+        let parse = ScraperError::Parse("URL 'nope'".to_string());
+        assert!(matches!(parse, ScraperError::Parse(_)));
+
+        let config = ScraperError::Config("rate_limit must be positive".to_string());
+        assert!(matches!(config, ScraperError::Config(_)));
+        assert_eq!(config.status(), None);
+
+        let database: ScraperError = rusqlite::Connection::open_in_memory().unwrap()
+            .execute("SELECT * FROM missing", [])
+            .unwrap_err()
+            .into();
+        assert!(matches!(database, ScraperError::Database(_)));
+
+        let io: ScraperError = std::fs::read("/definitely/not/here").unwrap_err().into();
+        assert!(matches!(io, ScraperError::Io(_)));
+
+        let json: ScraperError = serde_json::from_str::<serde_json::Value>("{").unwrap_err().into();
+        assert!(matches!(json, ScraperError::Json(_)));
+
+        let other: ScraperError = anyhow::anyhow!("renderer crashed").into();
+        assert!(matches!(other, ScraperError::Other(_)));
+        assert_eq!(other.to_string(), "renderer crashed");
+    }
+}
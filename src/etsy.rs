@@ -1,12 +1,13 @@
 use anyhow::{Context, Result};
 use chrono::Local;
+use futures::stream::{self, StreamExt};
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::sync::Arc;
 use url::Url;
 
-use crate::rate_limit::RateLimiter;
-use crate::user_agents::get_random_user_agent;
+use crate::utils::{get_random_user_agent, DomainRateLimiter};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EtsyProduct {
@@ -17,6 +18,17 @@ pub struct EtsyProduct {
     pub product_url: String,
     pub image_url: Option<String>,
     pub reviews: Vec<Review>,
+    #[serde(default)]
+    pub variations: Vec<Variation>,
+    #[serde(default)]
+    pub sku: Option<String>,
+}
+
+/// A purchasable option group on a listing, e.g. "Size" with its choices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variation {
+    pub name: String,
+    pub options: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,9 +58,13 @@ pub struct ScrapingSummary {
 
 pub struct EtsyScraper {
     client: reqwest::Client,
-    rate_limiter: RateLimiter,
+    /// Reserves a slot per request, so concurrent review fetches are spaced too
+    rate_limiter: Arc<DomainRateLimiter>,
     verbose: bool,
     fetch_reviews: bool,
+    /// How many products' reviews are fetched at once
+    review_concurrency: usize,
+    reviews_api_base: String,
 }
 
 impl EtsyScraper {
@@ -64,12 +80,26 @@ impl EtsyScraper {
 
         Ok(Self {
             client,
-            rate_limiter: RateLimiter::default(),
+            rate_limiter: Arc::new(DomainRateLimiter::default()),
             verbose,
             fetch_reviews,
+            review_concurrency: 5,
+            reviews_api_base: crate::etsy_reviews::REVIEWS_API_BASE.to_string(),
         })
     }
 
+    /// Share a per-host limiter with other scrapers instead of pacing on our own
+    pub fn with_rate_limiter(mut self, limiter: Arc<DomainRateLimiter>) -> Self {
+        self.rate_limiter = limiter;
+        self
+    }
+
+    /// Fetch reviews for up to `limit` products concurrently (still paced by the rate limiter)
+    pub fn with_review_concurrency(mut self, limit: usize) -> Self {
+        self.review_concurrency = limit.max(1);
+        self
+    }
+
     pub async fn scrape_category(
         &self,
         category_url: &str,
@@ -110,7 +140,7 @@ impl EtsyScraper {
             }
 
             // Rate limiting
-            self.rate_limiter.wait().await;
+            self.rate_limiter.wait(&current_url).await;
 
             // Fetch page
             match self.fetch_page(&current_url).await {
@@ -192,7 +222,7 @@ impl EtsyScraper {
 
         // Etsy product selectors (these may need adjustment based on actual HTML structure)
         // These are common patterns - adjust based on actual Etsy HTML
-        let product_selectors = vec![
+        let product_selectors = [
             "div.wt-grid__item-xs-6",  // Common Etsy grid item
             "div[data-appears-component-name*='listing']",
             "li.wt-list-unstyled",
@@ -223,9 +253,56 @@ impl EtsyScraper {
             }
         }
 
+        if self.fetch_reviews {
+            products = self.attach_reviews(products).await;
+        }
+
         Ok(products)
     }
 
+    /// Fetch every product's reviews with bounded concurrency, keeping product order
+    async fn attach_reviews(&self, products: Vec<EtsyProduct>) -> Vec<EtsyProduct> {
+        let mut fetched: Vec<(usize, EtsyProduct)> = stream::iter(products.into_iter().enumerate())
+            .map(|(index, mut product)| async move {
+                product.reviews = self.fetch_product_reviews(&product.product_url).await;
+                (index, product)
+            })
+            .buffer_unordered(self.review_concurrency)
+            .collect()
+            .await;
+
+        fetched.sort_by_key(|(index, _)| *index);
+        fetched.into_iter().map(|(_, product)| product).collect()
+    }
+
+    async fn fetch_product_reviews(&self, product_url: &str) -> Vec<Review> {
+        // Extract listing ID from product URL
+        let Some(listing_id) = crate::etsy_reviews::extract_listing_id(product_url) else {
+            if self.verbose {
+                println!("        ⚠ Could not extract listing ID from URL: {}", product_url);
+            }
+            return Vec::new();
+        };
+
+        match crate::etsy_reviews::fetch_reviews_from(
+            &self.client,
+            &self.reviews_api_base,
+            &listing_id,
+            &self.rate_limiter,
+            self.verbose,
+        )
+        .await
+        {
+            Ok(reviews) => reviews,
+            Err(e) => {
+                if self.verbose {
+                    println!("        ⚠ Failed to fetch reviews: {}", e);
+                }
+                Vec::new()
+            }
+        }
+    }
+
     async fn extract_product_info(&self, html: &str, base_url: &str) -> Result<EtsyProduct> {
         let document = Html::parse_document(html);
 
@@ -250,35 +327,9 @@ impl EtsyScraper {
         // Extract image URL
         let image_url = self.extract_attr(&document, &["img"], "src", base_url);
 
-        // Fetch reviews if enabled
-        let reviews = if self.fetch_reviews {
-            // Extract listing ID from product URL
-            if let Some(listing_id) = crate::etsy_reviews::extract_listing_id(&product_url) {
-                match crate::etsy_reviews::fetch_reviews(
-                    &self.client,
-                    &listing_id,
-                    &self.rate_limiter,
-                    self.verbose,
-                )
-                .await
-                {
-                    Ok(reviews) => reviews,
-                    Err(e) => {
-                        if self.verbose {
-                            println!("        ⚠ Failed to fetch reviews: {}", e);
-                        }
-                        Vec::new()
-                    }
-                }
-            } else {
-                if self.verbose {
-                    println!("        ⚠ Could not extract listing ID from URL: {}", product_url);
-                }
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        };
+        // Extract variations (size/color dropdowns) and SKU
+        let variations = self.extract_variations(&document);
+        let sku = self.extract_sku(&document);
 
         Ok(EtsyProduct {
             name: name.trim().to_string(),
@@ -287,10 +338,73 @@ impl EtsyScraper {
             review_count,
             product_url,
             image_url,
-            reviews,
+            // Filled in by `attach_reviews` once the whole page is parsed
+            reviews: Vec::new(),
+            variations,
+            sku,
         })
     }
 
+    fn extract_variations(&self, document: &Html) -> Vec<Variation> {
+        let mut variations = Vec::new();
+
+        let (Ok(select_selector), Ok(option_selector)) =
+            (Selector::parse("select"), Selector::parse("option"))
+        else {
+            return variations;
+        };
+
+        for select in document.select(&select_selector) {
+            // Prefer the visible <label for="...">, then accessibility/name attributes
+            let label = select.value().id()
+                .and_then(|id| Selector::parse(&format!("label[for='{}']", id)).ok())
+                .and_then(|selector| document.select(&selector).next())
+                .map(|label| label.text().collect::<String>().trim().to_string())
+                .filter(|label| !label.is_empty());
+
+            let name = label
+                .or_else(|| select.value().attr("aria-label").map(|s| s.trim().to_string()))
+                .or_else(|| select.value().attr("name").map(|s| s.trim().to_string()))
+                .unwrap_or_else(|| "Option".to_string());
+
+            let options: Vec<String> = select.select(&option_selector)
+                // Placeholder entries ("Select a size") carry an empty value
+                .filter(|option| option.value().attr("value").is_none_or(|v| !v.is_empty()))
+                .map(|option| option.text().collect::<String>().trim().to_string())
+                .filter(|text| !text.is_empty())
+                .collect();
+
+            if !options.is_empty() {
+                variations.push(Variation { name, options });
+            }
+        }
+
+        variations
+    }
+
+    fn extract_sku(&self, document: &Html) -> Option<String> {
+        for (selector_str, attr) in [
+            ("[itemprop='sku']", Some("content")),
+            ("[itemprop='sku']", None),
+            ("[data-sku]", Some("data-sku")),
+            ("meta[property='product:retailer_item_id']", Some("content")),
+        ] {
+            if let Ok(selector) = Selector::parse(selector_str)
+                && let Some(element) = document.select(&selector).next() {
+                    let value = match attr {
+                        Some(attr) => element.value().attr(attr).map(|s| s.to_string()),
+                        None => Some(element.text().collect::<String>()),
+                    };
+
+                    if let Some(value) = value
+                        && !value.trim().is_empty() {
+                            return Some(value.trim().to_string());
+                        }
+                }
+        }
+        None
+    }
+
     fn extract_text(&self, document: &Html, selectors: &[&str]) -> Option<String> {
         for selector_str in selectors {
             if let Ok(selector) = Selector::parse(selector_str)
@@ -323,7 +437,7 @@ impl EtsyScraper {
     fn extract_next_page_url(&self, html: &str, current_url: &str) -> Result<Option<String>> {
         let document = Html::parse_document(html);
 
-        let next_selectors = vec![
+        let next_selectors = [
             "a.wt-action-group__item-container[aria-label*='Next']",
             "a[rel='next']",
             "a.pagination-next",
@@ -357,3 +471,81 @@ impl EtsyScraper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_extract_variations_and_sku() {
+        let html = r#"
+            <div>
+                <h3>Linen Shirt</h3>
+                <span class="currency-value">45.00</span>
+                <label for="variation-selector-0">Size</label>
+                <select id="variation-selector-0">
+                    <option value="">Select a size</option>
+                    <option value="1">Small</option>
+                    <option value="2">Medium</option>
+                    <option value="3">Large</option>
+                </select>
+                <span data-sku="LS-001"></span>
+            </div>
+        "#;
+
+        let scraper = EtsyScraper::new(false).unwrap();
+        let product = scraper.extract_product_info(html, "https://www.etsy.com/listing/123").await.unwrap();
+
+        assert_eq!(product.variations, vec![Variation {
+            name: "Size".to_string(),
+            options: vec!["Small".to_string(), "Medium".to_string(), "Large".to_string()],
+        }]);
+        assert_eq!(product.sku.as_deref(), Some("LS-001"));
+    }
+
+    #[tokio::test]
+    async fn test_reviews_fetched_concurrently_stay_with_their_product() {
+        use crate::test_support::{MockResponse, MockServer};
+
+        let server = MockServer::start(|request| {
+            let listing_id = request.path
+                .split("listing_id=").nth(1)
+                .and_then(|rest| rest.split('&').next())
+                .unwrap_or("")
+                .to_string();
+            let body = serde_json::json!({
+                "reviews": [{ "review": format!("Review for {}", listing_id), "rating": 5 }]
+            });
+            MockResponse::status(200)
+                .with_header("Content-Type", "application/json")
+                .with_body(body.to_string().as_bytes())
+        }).await;
+
+        let cards: String = (1..=4)
+            .map(|id| format!(
+                r#"<article><h3>Item {0}</h3><a href="https://www.etsy.com/listing/{0}/item">View</a></article>"#,
+                id
+            ))
+            .collect();
+        let html = format!("<html><body>{}</body></html>", cards);
+
+        let mut scraper = EtsyScraper::with_options(false, true).unwrap()
+            .with_review_concurrency(3)
+            .with_rate_limiter(Arc::new(DomainRateLimiter::new(10.0)));
+        scraper.reviews_api_base = server.url("/feedback");
+
+        let start = std::time::Instant::now();
+        let products = scraper.extract_products(&html, "https://www.etsy.com/c/shirts").await.unwrap();
+
+        // Concurrent fetches still take turns on the host's 100ms slots
+        assert!(start.elapsed() >= std::time::Duration::from_millis(300));
+        assert_eq!(server.requests().len(), 4);
+        assert_eq!(products.len(), 4);
+        for (i, product) in products.iter().enumerate() {
+            let id = i + 1;
+            assert_eq!(product.name, format!("Item {}", id));
+            assert_eq!(product.reviews.len(), 1);
+            assert_eq!(product.reviews[0].text, format!("Review for {}", id));
+        }
+    }
+}
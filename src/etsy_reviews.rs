@@ -4,8 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tokio::time::sleep;
 
-use crate::rate_limit::RateLimiter;
-use crate::user_agents::get_random_user_agent;
+use crate::utils::{get_random_user_agent, DomainRateLimiter};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EtsyReviewResponse {
@@ -27,6 +26,9 @@ pub struct ApiReview {
     pub created_at: Option<i64>,
 }
 
+/// Etsy's AJAX endpoint for listing feedback
+pub const REVIEWS_API_BASE: &str = "https://www.etsy.com/api/v3/ajax/bespoke/member/feedback";
+
 /// Extract listing ID from Etsy product URL
 /// Example: https://www.etsy.com/listing/1234567890/product-name -> Some("1234567890")
 pub fn extract_listing_id(url: &str) -> Option<String> {
@@ -43,17 +45,25 @@ pub fn extract_listing_id(url: &str) -> Option<String> {
 pub async fn fetch_reviews(
     client: &reqwest::Client,
     listing_id: &str,
-    rate_limiter: &RateLimiter,
+    rate_limiter: &DomainRateLimiter,
     verbose: bool,
 ) -> Result<Vec<crate::etsy::Review>> {
-    // Apply rate limiting
-    rate_limiter.wait().await;
+    fetch_reviews_from(client, REVIEWS_API_BASE, listing_id, rate_limiter, verbose).await
+}
 
+/// Like `fetch_reviews`, against a different API base URL
+pub async fn fetch_reviews_from(
+    client: &reqwest::Client,
+    api_base: &str,
+    listing_id: &str,
+    rate_limiter: &DomainRateLimiter,
+    verbose: bool,
+) -> Result<Vec<crate::etsy::Review>> {
     // Construct the API URL
-    let api_url = format!(
-        "https://www.etsy.com/api/v3/ajax/bespoke/member/feedback?listing_id={}&limit=20",
-        listing_id
-    );
+    let api_url = format!("{}?listing_id={}&limit=20", api_base, listing_id);
+
+    // Apply rate limiting
+    rate_limiter.wait(&api_url).await;
 
     if verbose {
         println!("      Fetching reviews from API for listing {}", listing_id);
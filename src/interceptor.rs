@@ -0,0 +1,16 @@
+use anyhow::Result;
+
+/// Inspects and rewrites each outgoing page request right before it is sent:
+/// add headers, rewrite the URL, attach query params. Use it to sign requests
+/// or inject per-domain cache-busters. Returning an error aborts the fetch.
+///
+/// Not applied when a `RenderBackend` does the fetching.
+pub trait RequestInterceptor: Send + Sync {
+    fn intercept(&self, request: &mut reqwest::Request) -> Result<()>;
+}
+
+impl std::fmt::Debug for dyn RequestInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RequestInterceptor")
+    }
+}
@@ -0,0 +1,87 @@
+use scraper::Html;
+use serde_json::Value;
+
+use crate::utils::cached_selector;
+
+/// All schema.org JSON-LD objects on the page, with top-level arrays and
+/// `@graph` containers flattened out
+pub fn items(document: &Html) -> Vec<Value> {
+    let selector = match cached_selector("script[type='application/ld+json']") {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+
+    let mut items = Vec::new();
+    for script in document.select(&selector) {
+        let raw: String = script.text().collect();
+        if let Ok(value) = serde_json::from_str::<Value>(raw.trim()) {
+            flatten(value, &mut items);
+        }
+    }
+
+    items
+}
+
+fn flatten(value: Value, items: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for v in values {
+                flatten(v, items);
+            }
+        }
+        Value::Object(mut map) => {
+            if let Some(graph) = map.remove("@graph") {
+                flatten(graph, items);
+            }
+            if map.contains_key("@type") {
+                items.push(Value::Object(map));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether an item's `@type` (a string or an array of strings) includes `type_name`
+pub fn has_type(item: &Value, type_name: &str) -> bool {
+    match item.get("@type") {
+        Some(Value::String(t)) => t == type_name,
+        Some(Value::Array(types)) => types.iter().any(|t| t.as_str() == Some(type_name)),
+        _ => false,
+    }
+}
+
+/// Properties may hold a single value or an array of values; iterate either uniformly
+pub fn values(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(v) => vec![v],
+    }
+}
+
+/// Read a property that is either plain text or an object carrying `name`/`text`
+pub fn text(value: Option<&Value>) -> Option<String> {
+    let value = values(value).into_iter().next()?;
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Object(map) => map.get("name")
+            .or_else(|| map.get("text"))
+            .and_then(|v| v.as_str())?
+            .to_string(),
+        _ => return None,
+    };
+
+    let text = text.trim().to_string();
+    if text.is_empty() { None } else { Some(text) }
+}
+
+/// Read a numeric property that sites publish as either a number or a string
+pub fn number(value: Option<&Value>) -> Option<f64> {
+    let value = values(value).into_iter().next()?;
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().replace(',', "").parse().ok(),
+        _ => None,
+    }
+}
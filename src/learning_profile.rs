@@ -1,10 +1,16 @@
-use anyhow::{Context, Result};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
 use rusqlite::{params, Connection, OptionalExtension};
+use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::RwLock;
 use uuid::Uuid;
 
-use crate::structure_analyzer::StructureAnalysis;
+use crate::auto_selectors::AutoSelectors;
+use crate::error::{Result, ScraperError};
+use crate::structure_analyzer::{ExtractionMode, StructureAnalysis};
+use crate::utils::normalize_domain;
 
 /// A learned profile for a domain or pattern
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,13 +21,68 @@ pub struct SiteProfile {
     pub main_content_selector: Option<String>,
     pub title_selector: Option<String>,
     pub comments_selector: Option<String>,
-    pub extraction_mode: String,
+    pub extraction_mode: ExtractionMode,
     pub confidence: f64,
     pub use_count: i32,
     pub success_rate: f64,
     pub created_at: String,
     pub last_used: String,
     pub notes: Option<String>,
+    /// Publisher (or author) the site names in its JSON-LD
+    #[serde(default)]
+    pub publisher: Option<String>,
+}
+
+impl SiteProfile {
+    /// The profile's selectors as an `AutoSelectors` ready for `ScrapingConfig::custom_selectors`.
+    /// Learned selectors replace the defaults for their field; the comments selector is
+    /// extracted as content after the main one. Everything else keeps the defaults.
+    pub fn to_auto_selectors(&self) -> AutoSelectors {
+        let mut selectors = AutoSelectors::default();
+
+        if let Some(ref title) = self.title_selector {
+            selectors.title = vec![title.clone()];
+        }
+
+        let content: Vec<String> = self.main_content_selector.iter()
+            .chain(self.comments_selector.iter())
+            .cloned()
+            .collect();
+        if !content.is_empty() {
+            selectors.content = content;
+        }
+
+        selectors
+    }
+}
+
+// Modes are stored as their snake_case name
+impl ToSql for ExtractionMode {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for ExtractionMode {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+/// Characters the main content selector must yield for a profile to still count as working
+const MIN_VERIFIED_CONTENT_CHARS: usize = 200;
+
+/// Outcome of checking a stored profile's selectors against a live page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileVerification {
+    pub profile_id: String,
+    /// The main content selector still yields substantial text
+    pub passed: bool,
+    /// Characters of text under the main content selector
+    pub content_length: usize,
+    pub title_matched: bool,
+    /// Success rate after recording this check
+    pub success_rate: f64,
 }
 
 /// Profile database manager
@@ -32,8 +93,7 @@ pub struct ProfileDatabase {
 impl ProfileDatabase {
     /// Create a new database connection and initialize schema
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let conn = Connection::open(db_path)
-            .context("Failed to open database connection")?;
+        let conn = Connection::open(db_path)?;
 
         let db = Self { conn };
         db.initialize_schema()?;
@@ -42,8 +102,7 @@ impl ProfileDatabase {
 
     /// Create an in-memory database (for testing)
     pub fn new_in_memory() -> Result<Self> {
-        let conn = Connection::open_in_memory()
-            .context("Failed to create in-memory database")?;
+        let conn = Connection::open_in_memory()?;
 
         let db = Self { conn };
         db.initialize_schema()?;
@@ -66,11 +125,22 @@ impl ProfileDatabase {
                 success_rate REAL DEFAULT 1.0,
                 created_at TEXT NOT NULL,
                 last_used TEXT NOT NULL,
-                notes TEXT
+                notes TEXT,
+                publisher TEXT
             )",
             [],
         )?;
 
+        // Databases created before the publisher column was added
+        let has_publisher: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('profiles') WHERE name = 'publisher'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_publisher {
+            self.conn.execute("ALTER TABLE profiles ADD COLUMN publisher TEXT", [])?;
+        }
+
         // Create indexes
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_domain ON profiles(domain)",
@@ -82,12 +152,65 @@ impl ProfileDatabase {
             [],
         )?;
 
+        // Older versions stored the Debug form ("ListPage"); move to snake_case
+        // so mode lookups match
+        for mode in ExtractionMode::ALL {
+            self.conn.execute(
+                "UPDATE profiles SET extraction_mode = ?1 WHERE extraction_mode = ?2",
+                params![mode, format!("{:?}", mode)],
+            )?;
+        }
+
         Ok(())
     }
+}
+
+/// Storage behind the profile API. Backends provide the basic queries; saving
+/// from an analysis, usage tracking and verification are built on top of them.
+pub trait ProfileStore: Send {
+    /// Save or update a profile
+    fn insert_profile(&self, profile: &SiteProfile) -> Result<()>;
+
+    /// Get profile by ID
+    fn get_by_id(&self, id: &str) -> Result<Option<SiteProfile>>;
+
+    /// Get profile by domain (most recent and confident)
+    fn get_by_domain(&self, domain: &str) -> Result<Option<SiteProfile>>;
+
+    /// Get all profiles, ordered by confidence
+    fn get_all(&self) -> Result<Vec<SiteProfile>>;
+
+    /// Get profiles for a specific extraction mode
+    fn get_by_mode(&self, mode: ExtractionMode) -> Result<Vec<SiteProfile>>;
+
+    /// Delete profile by ID
+    fn delete(&self, id: &str) -> Result<()>;
+
+    /// Clear all profiles
+    fn clear_all(&self) -> Result<()>;
+
+    /// Get statistics
+    fn get_stats(&self) -> Result<ProfileStats> {
+        let profiles = self.get_all()?;
+        let average = |value: fn(&SiteProfile) -> f64| {
+            if profiles.is_empty() {
+                0.0
+            } else {
+                profiles.iter().map(value).sum::<f64>() / profiles.len() as f64
+            }
+        };
+
+        Ok(ProfileStats {
+            total_profiles: profiles.len() as i32,
+            total_uses: profiles.iter().map(|p| p.use_count).sum(),
+            avg_confidence: average(|p| p.confidence),
+            avg_success_rate: average(|p| p.success_rate),
+        })
+    }
 
     /// Save a new profile from structure analysis
-    pub fn save_from_analysis(&self, analysis: &StructureAnalysis) -> Result<SiteProfile> {
-        let domain = Self::extract_domain(&analysis.url)?;
+    fn save_from_analysis(&self, analysis: &StructureAnalysis) -> Result<SiteProfile> {
+        let domain = extract_domain(&analysis.url)?;
 
         let profile = SiteProfile {
             id: Uuid::new_v4().to_string(),
@@ -96,13 +219,14 @@ impl ProfileDatabase {
             main_content_selector: analysis.recommendations.best_main_content.clone(),
             title_selector: analysis.recommendations.best_title.clone(),
             comments_selector: analysis.recommendations.best_comments.clone(),
-            extraction_mode: format!("{:?}", analysis.recommendations.suggested_mode),
-            confidence: self.calculate_confidence_from_analysis(analysis),
+            extraction_mode: analysis.recommendations.suggested_mode,
+            confidence: confidence_from_analysis(analysis),
             use_count: 0,
             success_rate: 1.0,
             created_at: chrono::Local::now().to_rfc3339(),
             last_used: chrono::Local::now().to_rfc3339(),
             notes: None,
+            publisher: analysis.publisher.clone(),
         };
 
         self.insert_profile(&profile)?;
@@ -111,14 +235,69 @@ impl ProfileDatabase {
         Ok(profile)
     }
 
-    /// Save or update a profile
-    pub fn insert_profile(&self, profile: &SiteProfile) -> Result<()> {
+    /// Update profile usage statistics
+    fn update_usage(&self, profile_id: &str, success: bool) -> Result<()> {
+        let profile = self.get_by_id(profile_id)?;
+
+        if let Some(mut p) = profile {
+            p.use_count += 1;
+
+            // Update success rate with exponential moving average
+            let alpha = 0.3; // Weight for new observation
+            let new_success = if success { 1.0 } else { 0.0 };
+            p.success_rate = alpha * new_success + (1.0 - alpha) * p.success_rate;
+
+            p.last_used = chrono::Local::now().to_rfc3339();
+
+            self.insert_profile(&p)?;
+            log::info!("Updated usage for profile: {} (success: {})", profile_id, success);
+        }
+
+        Ok(())
+    }
+
+    /// Check that profile `id`'s selectors still extract content from `html` (a
+    /// fresh copy of one of its pages) and fold the outcome into its success rate.
+    /// `None` when there is no such profile.
+    fn verify_profile(&self, id: &str, html: &str) -> Result<Option<ProfileVerification>> {
+        let Some(profile) = self.get_by_id(id)? else {
+            return Ok(None);
+        };
+
+        let document = Html::parse_document(html);
+        let selected_text = |selector: &Option<String>| -> Option<String> {
+            let selector = Selector::parse(selector.as_deref()?).ok()?;
+            let element = document.select(&selector).next()?;
+            Some(element.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+        };
+
+        let content_length = selected_text(&profile.main_content_selector)
+            .map(|text| text.chars().count())
+            .unwrap_or(0);
+        let title_matched = selected_text(&profile.title_selector).is_some_and(|title| !title.is_empty());
+        let passed = content_length >= MIN_VERIFIED_CONTENT_CHARS;
+
+        self.update_usage(id, passed)?;
+        let success_rate = self.get_by_id(id)?.map(|p| p.success_rate).unwrap_or(profile.success_rate);
+
+        Ok(Some(ProfileVerification {
+            profile_id: id.to_string(),
+            passed,
+            content_length,
+            title_matched,
+            success_rate,
+        }))
+    }
+}
+
+impl ProfileStore for ProfileDatabase {
+    fn insert_profile(&self, profile: &SiteProfile) -> Result<()> {
         self.conn.execute(
             "INSERT OR REPLACE INTO profiles
             (id, domain, pattern, main_content_selector, title_selector,
              comments_selector, extraction_mode, confidence, use_count,
-             success_rate, created_at, last_used, notes)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+             success_rate, created_at, last_used, notes, publisher)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
             params![
                 profile.id,
                 profile.domain,
@@ -133,25 +312,23 @@ impl ProfileDatabase {
                 profile.created_at,
                 profile.last_used,
                 profile.notes,
+                profile.publisher,
             ],
         )?;
 
         Ok(())
     }
 
-    /// Get profile by domain (most recent and confident)
-    pub fn get_by_domain(&self, domain: &str) -> Result<Option<SiteProfile>> {
+    fn get_by_id(&self, id: &str) -> Result<Option<SiteProfile>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, domain, pattern, main_content_selector, title_selector,
                     comments_selector, extraction_mode, confidence, use_count,
-                    success_rate, created_at, last_used, notes
+                    success_rate, created_at, last_used, notes, publisher
              FROM profiles
-             WHERE domain = ?1
-             ORDER BY confidence DESC, last_used DESC
-             LIMIT 1"
+             WHERE id = ?1"
         )?;
 
-        let profile = stmt.query_row([domain], |row| {
+        let profile = stmt.query_row([id], |row| {
             Ok(SiteProfile {
                 id: row.get(0)?,
                 domain: row.get(1)?,
@@ -166,23 +343,26 @@ impl ProfileDatabase {
                 created_at: row.get(10)?,
                 last_used: row.get(11)?,
                 notes: row.get(12)?,
+                publisher: row.get(13)?,
             })
         }).optional()?;
 
         Ok(profile)
     }
 
-    /// Get all profiles, ordered by confidence
-    pub fn get_all(&self) -> Result<Vec<SiteProfile>> {
+    fn get_by_domain(&self, domain: &str) -> Result<Option<SiteProfile>> {
+        let domain = normalize_domain(domain);
         let mut stmt = self.conn.prepare(
             "SELECT id, domain, pattern, main_content_selector, title_selector,
                     comments_selector, extraction_mode, confidence, use_count,
-                    success_rate, created_at, last_used, notes
+                    success_rate, created_at, last_used, notes, publisher
              FROM profiles
-             ORDER BY confidence DESC, last_used DESC"
+             WHERE domain = ?1
+             ORDER BY confidence DESC, last_used DESC
+             LIMIT 1"
         )?;
 
-        let profiles = stmt.query_map([], |row| {
+        let profile = stmt.query_row([&domain], |row| {
             Ok(SiteProfile {
                 id: row.get(0)?,
                 domain: row.get(1)?,
@@ -197,24 +377,23 @@ impl ProfileDatabase {
                 created_at: row.get(10)?,
                 last_used: row.get(11)?,
                 notes: row.get(12)?,
+                publisher: row.get(13)?,
             })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        }).optional()?;
 
-        Ok(profiles)
+        Ok(profile)
     }
 
-    /// Get profiles for a specific extraction mode
-    pub fn get_by_mode(&self, mode: &str) -> Result<Vec<SiteProfile>> {
+    fn get_all(&self) -> Result<Vec<SiteProfile>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, domain, pattern, main_content_selector, title_selector,
                     comments_selector, extraction_mode, confidence, use_count,
-                    success_rate, created_at, last_used, notes
+                    success_rate, created_at, last_used, notes, publisher
              FROM profiles
-             WHERE extraction_mode = ?1
-             ORDER BY confidence DESC"
+             ORDER BY confidence DESC, last_used DESC"
         )?;
 
-        let profiles = stmt.query_map([mode], |row| {
+        let profiles = stmt.query_map([], |row| {
             Ok(SiteProfile {
                 id: row.get(0)?,
                 domain: row.get(1)?,
@@ -229,44 +408,24 @@ impl ProfileDatabase {
                 created_at: row.get(10)?,
                 last_used: row.get(11)?,
                 notes: row.get(12)?,
+                publisher: row.get(13)?,
             })
-        })?.collect::<Result<Vec<_>, _>>()?;
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
 
         Ok(profiles)
     }
 
-    /// Update profile usage statistics
-    pub fn update_usage(&self, profile_id: &str, success: bool) -> Result<()> {
-        let profile = self.get_by_id(profile_id)?;
-
-        if let Some(mut p) = profile {
-            p.use_count += 1;
-
-            // Update success rate with exponential moving average
-            let alpha = 0.3; // Weight for new observation
-            let new_success = if success { 1.0 } else { 0.0 };
-            p.success_rate = alpha * new_success + (1.0 - alpha) * p.success_rate;
-
-            p.last_used = chrono::Local::now().to_rfc3339();
-
-            self.insert_profile(&p)?;
-            log::info!("Updated usage for profile: {} (success: {})", profile_id, success);
-        }
-
-        Ok(())
-    }
-
-    /// Get profile by ID
-    pub fn get_by_id(&self, id: &str) -> Result<Option<SiteProfile>> {
+    fn get_by_mode(&self, mode: ExtractionMode) -> Result<Vec<SiteProfile>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, domain, pattern, main_content_selector, title_selector,
                     comments_selector, extraction_mode, confidence, use_count,
-                    success_rate, created_at, last_used, notes
+                    success_rate, created_at, last_used, notes, publisher
              FROM profiles
-             WHERE id = ?1"
+             WHERE extraction_mode = ?1
+             ORDER BY confidence DESC"
         )?;
 
-        let profile = stmt.query_row([id], |row| {
+        let profiles = stmt.query_map([mode], |row| {
             Ok(SiteProfile {
                 id: row.get(0)?,
                 domain: row.get(1)?,
@@ -281,28 +440,26 @@ impl ProfileDatabase {
                 created_at: row.get(10)?,
                 last_used: row.get(11)?,
                 notes: row.get(12)?,
+                publisher: row.get(13)?,
             })
-        }).optional()?;
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
 
-        Ok(profile)
+        Ok(profiles)
     }
 
-    /// Delete profile by ID
-    pub fn delete(&self, id: &str) -> Result<()> {
+    fn delete(&self, id: &str) -> Result<()> {
         self.conn.execute("DELETE FROM profiles WHERE id = ?1", [id])?;
         log::info!("Deleted profile: {}", id);
         Ok(())
     }
 
-    /// Clear all profiles
-    pub fn clear_all(&self) -> Result<()> {
+    fn clear_all(&self) -> Result<()> {
         self.conn.execute("DELETE FROM profiles", [])?;
         log::info!("Cleared all profiles");
         Ok(())
     }
 
-    /// Get statistics
-    pub fn get_stats(&self) -> Result<ProfileStats> {
+    fn get_stats(&self) -> Result<ProfileStats> {
         let total_profiles: i32 = self.conn.query_row(
             "SELECT COUNT(*) FROM profiles",
             [],
@@ -334,46 +491,105 @@ impl ProfileDatabase {
             avg_success_rate,
         })
     }
+}
 
-    /// Extract domain from URL
-    fn extract_domain(url: &str) -> Result<String> {
-        let parsed = url::Url::parse(url)
-            .context("Failed to parse URL")?;
+/// Profiles kept in a map, for tests and embedding where no database is wanted
+#[derive(Debug, Default)]
+pub struct MemoryProfileStore {
+    profiles: RwLock<HashMap<String, SiteProfile>>,
+}
 
-        parsed.host_str()
-            .map(|h| h.to_string())
-            .context("No host in URL")
+impl MemoryProfileStore {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Calculate confidence from analysis
-    fn calculate_confidence_from_analysis(&self, analysis: &StructureAnalysis) -> f64 {
-        if analysis.sections.is_empty() {
-            return 0.0;
-        }
+    /// Matching profiles, best first: by confidence, then most recently used
+    fn sorted(&self, filter: impl Fn(&SiteProfile) -> bool) -> Vec<SiteProfile> {
+        let mut profiles: Vec<SiteProfile> = self.profiles.read().unwrap()
+            .values()
+            .filter(|p| filter(p))
+            .cloned()
+            .collect();
+        profiles.sort_by(|a, b| {
+            b.confidence.total_cmp(&a.confidence).then_with(|| b.last_used.cmp(&a.last_used))
+        });
+        profiles
+    }
+}
 
-        // Get top section score
-        let top_score = analysis.sections.first()
-            .map(|s| s.score)
-            .unwrap_or(0.0);
+impl ProfileStore for MemoryProfileStore {
+    fn insert_profile(&self, profile: &SiteProfile) -> Result<()> {
+        self.profiles.write().unwrap().insert(profile.id.clone(), profile.clone());
+        Ok(())
+    }
 
-        // Factor in recommendations
-        let has_main_content = analysis.recommendations.best_main_content.is_some();
-        let has_title = analysis.recommendations.best_title.is_some();
+    fn get_by_id(&self, id: &str) -> Result<Option<SiteProfile>> {
+        Ok(self.profiles.read().unwrap().get(id).cloned())
+    }
 
-        let mut confidence = top_score * 0.7;
+    fn get_by_domain(&self, domain: &str) -> Result<Option<SiteProfile>> {
+        let domain = normalize_domain(domain);
+        Ok(self.sorted(|p| p.domain == domain).into_iter().next())
+    }
 
-        if has_main_content {
-            confidence += 0.2;
-        }
+    fn get_all(&self) -> Result<Vec<SiteProfile>> {
+        Ok(self.sorted(|_| true))
+    }
 
-        if has_title {
-            confidence += 0.1;
-        }
+    fn get_by_mode(&self, mode: ExtractionMode) -> Result<Vec<SiteProfile>> {
+        Ok(self.sorted(|p| p.extraction_mode == mode))
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.profiles.write().unwrap().remove(id);
+        Ok(())
+    }
 
-        confidence.min(1.0)
+    fn clear_all(&self) -> Result<()> {
+        self.profiles.write().unwrap().clear();
+        Ok(())
     }
 }
 
+/// Extract domain from URL
+fn extract_domain(url: &str) -> Result<String> {
+    let parsed = url::Url::parse(url)
+        .map_err(|e| ScraperError::Parse(format!("URL '{}': {}", url, e)))?;
+
+    parsed.host_str()
+        .map(normalize_domain)
+        .ok_or_else(|| ScraperError::Parse(format!("URL '{}': no host", url)))
+}
+
+/// Calculate confidence from analysis
+fn confidence_from_analysis(analysis: &StructureAnalysis) -> f64 {
+    if analysis.sections.is_empty() {
+        return 0.0;
+    }
+
+    // Get top section score
+    let top_score = analysis.sections.first()
+        .map(|s| s.score)
+        .unwrap_or(0.0);
+
+    // Factor in recommendations
+    let has_main_content = analysis.recommendations.best_main_content.is_some();
+    let has_title = analysis.recommendations.best_title.is_some();
+
+    let mut confidence = top_score * 0.7;
+
+    if has_main_content {
+        confidence += 0.2;
+    }
+
+    if has_title {
+        confidence += 0.1;
+    }
+
+    confidence.min(1.0)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProfileStats {
     pub total_profiles: i32,
@@ -404,13 +620,14 @@ mod tests {
             main_content_selector: Some("article".to_string()),
             title_selector: Some("h1".to_string()),
             comments_selector: None,
-            extraction_mode: "Article".to_string(),
+            extraction_mode: ExtractionMode::Article,
             confidence: 0.9,
             use_count: 0,
             success_rate: 1.0,
             created_at: chrono::Local::now().to_rfc3339(),
             last_used: chrono::Local::now().to_rfc3339(),
             notes: None,
+            publisher: None,
         };
 
         db.insert_profile(&profile).unwrap();
@@ -422,4 +639,232 @@ mod tests {
         let stats = db.get_stats().unwrap();
         assert_eq!(stats.total_profiles, 1);
     }
+
+    #[test]
+    fn test_profile_to_auto_selectors() {
+        let profile = SiteProfile {
+            id: Uuid::new_v4().to_string(),
+            domain: "example.com".to_string(),
+            pattern: None,
+            main_content_selector: Some("div.post-body".to_string()),
+            title_selector: Some("h1.post-title".to_string()),
+            comments_selector: None,
+            extraction_mode: ExtractionMode::Article,
+            confidence: 0.9,
+            use_count: 0,
+            success_rate: 1.0,
+            created_at: chrono::Local::now().to_rfc3339(),
+            last_used: chrono::Local::now().to_rfc3339(),
+            notes: None,
+            publisher: None,
+        };
+
+        let selectors = profile.to_auto_selectors();
+
+        assert_eq!(selectors.title, vec!["h1.post-title"]);
+        assert_eq!(selectors.content, vec!["div.post-body"]);
+        assert_eq!(selectors.links, AutoSelectors::default().links);
+    }
+
+    #[test]
+    fn test_verify_profile_detects_stale_selector() {
+        let db = ProfileDatabase::new_in_memory().unwrap();
+        let profile = SiteProfile {
+            id: Uuid::new_v4().to_string(),
+            domain: "news.example.com".to_string(),
+            pattern: None,
+            main_content_selector: Some("div.story-body".to_string()),
+            title_selector: Some("h1".to_string()),
+            comments_selector: None,
+            extraction_mode: ExtractionMode::Article,
+            confidence: 0.9,
+            use_count: 0,
+            success_rate: 1.0,
+            created_at: chrono::Local::now().to_rfc3339(),
+            last_used: chrono::Local::now().to_rfc3339(),
+            notes: None,
+            publisher: None,
+        };
+        db.insert_profile(&profile).unwrap();
+
+        let body = "Paragraph of the story. ".repeat(20);
+        let before = format!("<html><body><h1>Story</h1><div class=\"story-body\">{}</div></body></html>", body);
+        let passed = db.verify_profile(&profile.id, &before).unwrap().unwrap();
+        assert!(passed.passed);
+        assert_eq!(passed.success_rate, 1.0);
+
+        // After a redesign the content moved to <article>
+        let after = format!("<html><body><h1>Story</h1><article>{}</article></body></html>", body);
+        let failed = db.verify_profile(&profile.id, &after).unwrap().unwrap();
+        assert!(!failed.passed);
+        assert!(failed.title_matched);
+        assert_eq!(failed.content_length, 0);
+        assert!(failed.success_rate < 1.0);
+        assert_eq!(db.get_by_id(&profile.id).unwrap().unwrap().success_rate, failed.success_rate);
+
+        assert!(db.verify_profile("missing", &after).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_extraction_mode_round_trips_through_db() {
+        let db = ProfileDatabase::new_in_memory().unwrap();
+
+        for mode in ExtractionMode::ALL {
+            let profile = SiteProfile {
+                id: Uuid::new_v4().to_string(),
+                domain: format!("{}.example.com", mode),
+                pattern: None,
+                main_content_selector: None,
+                title_selector: None,
+                comments_selector: None,
+                extraction_mode: mode,
+                confidence: 0.5,
+                use_count: 0,
+                success_rate: 1.0,
+                created_at: chrono::Local::now().to_rfc3339(),
+                last_used: chrono::Local::now().to_rfc3339(),
+                notes: None,
+                publisher: None,
+            };
+            db.insert_profile(&profile).unwrap();
+
+            let stored = db.get_by_id(&profile.id).unwrap().unwrap();
+            assert_eq!(stored.extraction_mode, mode);
+
+            let by_mode = db.get_by_mode(mode).unwrap();
+            assert_eq!(by_mode.len(), 1);
+            assert_eq!(by_mode[0].id, profile.id);
+        }
+
+        // Legacy Debug-formatted values still parse
+        assert_eq!("ListPage".parse::<ExtractionMode>(), Ok(ExtractionMode::ListPage));
+        assert!("blog".parse::<ExtractionMode>().is_err());
+    }
+
+    #[test]
+    fn test_idn_domain_lookup() {
+        let db = ProfileDatabase::new_in_memory().unwrap();
+        let analysis = crate::structure_analyzer::StructureAnalyzer::new()
+            .analyze("<html><body><article><p>Stadtplan</p></article></body></html>", "https://münchen.de/stadtplan");
+
+        let saved = db.save_from_analysis(&analysis).unwrap();
+        assert_eq!(saved.domain, "xn--mnchen-3ya.de");
+
+        assert!(db.get_by_domain("münchen.de").unwrap().is_some());
+        assert!(db.get_by_domain("xn--mnchen-3ya.de").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_save_from_analysis_records_publisher() {
+        let db = ProfileDatabase::new_in_memory().unwrap();
+        let html = r#"<html><head><script type="application/ld+json">
+            {"@context": "https://schema.org", "@type": "NewsArticle", "headline": "Tides",
+             "author": {"@type": "Person", "name": "A. Reporter"},
+             "publisher": {"@type": "Organization", "name": "Coastal Times"}}
+        </script></head><body><article><p>High tide at noon.</p></article></body></html>"#;
+        let analysis = crate::structure_analyzer::StructureAnalyzer::new()
+            .analyze(html, "https://coastal.example/tides");
+        assert_eq!(analysis.publisher.as_deref(), Some("Coastal Times"));
+
+        let saved = db.save_from_analysis(&analysis).unwrap();
+        let stored = db.get_by_id(&saved.id).unwrap().unwrap();
+        assert_eq!(stored.publisher.as_deref(), Some("Coastal Times"));
+    }
+
+    #[test]
+    fn test_publisher_column_added_to_existing_database() {
+        let path = std::env::temp_dir().join(format!("profiles-{}.db", Uuid::new_v4()));
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute(
+                "CREATE TABLE profiles (
+                    id TEXT PRIMARY KEY, domain TEXT NOT NULL, pattern TEXT,
+                    main_content_selector TEXT, title_selector TEXT, comments_selector TEXT,
+                    extraction_mode TEXT NOT NULL, confidence REAL NOT NULL,
+                    use_count INTEGER DEFAULT 0, success_rate REAL DEFAULT 1.0,
+                    created_at TEXT NOT NULL, last_used TEXT NOT NULL, notes TEXT
+                )",
+                [],
+            ).unwrap();
+            conn.execute(
+                "INSERT INTO profiles (id, domain, extraction_mode, confidence, created_at, last_used)
+                 VALUES ('old', 'example.com', 'article', 0.8, '', '')",
+                [],
+            ).unwrap();
+        }
+
+        let db = ProfileDatabase::new(&path).unwrap();
+        let old = db.get_by_id("old").unwrap().unwrap();
+        assert_eq!(old.domain, "example.com");
+        assert!(old.publisher.is_none());
+
+        // Reopening doesn't try to add the column twice
+        drop(db);
+        assert!(ProfileDatabase::new(&path).is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// The same checks against every backend
+    fn run_store_suite(store: &dyn ProfileStore) {
+        let profile = |domain: &str, mode: ExtractionMode, confidence: f64| SiteProfile {
+            id: Uuid::new_v4().to_string(),
+            domain: domain.to_string(),
+            pattern: None,
+            main_content_selector: Some("article".to_string()),
+            title_selector: Some("h1".to_string()),
+            comments_selector: None,
+            extraction_mode: mode,
+            confidence,
+            use_count: 0,
+            success_rate: 1.0,
+            created_at: chrono::Local::now().to_rfc3339(),
+            last_used: chrono::Local::now().to_rfc3339(),
+            notes: None,
+            publisher: None,
+        };
+
+        assert_eq!(store.get_stats().unwrap().total_profiles, 0);
+
+        let weak = profile("example.com", ExtractionMode::Article, 0.4);
+        let strong = profile("example.com", ExtractionMode::Article, 0.9);
+        let shop = profile("shop.example.com", ExtractionMode::Product, 0.7);
+        for p in [&weak, &strong, &shop] {
+            store.insert_profile(p).unwrap();
+        }
+
+        assert_eq!(store.get_by_id(&shop.id).unwrap().unwrap().domain, "shop.example.com");
+        assert_eq!(store.get_by_domain("EXAMPLE.com").unwrap().unwrap().id, strong.id);
+        let all: Vec<String> = store.get_all().unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(all, vec![strong.id.clone(), shop.id.clone(), weak.id.clone()]);
+        assert_eq!(store.get_by_mode(ExtractionMode::Product).unwrap().len(), 1);
+
+        store.update_usage(&weak.id, false).unwrap();
+        let updated = store.get_by_id(&weak.id).unwrap().unwrap();
+        assert_eq!(updated.use_count, 1);
+        assert!(updated.success_rate < 1.0);
+
+        let stats = store.get_stats().unwrap();
+        assert_eq!((stats.total_profiles, stats.total_uses), (3, 1));
+        assert!((stats.avg_confidence - (0.4 + 0.9 + 0.7) / 3.0).abs() < 1e-9);
+
+        let html = format!("<html><body><h1>T</h1><article>{}</article></body></html>", "Body text. ".repeat(30));
+        assert!(store.verify_profile(&strong.id, &html).unwrap().unwrap().passed);
+
+        store.delete(&strong.id).unwrap();
+        assert!(store.get_by_id(&strong.id).unwrap().is_none());
+        assert_eq!(store.get_by_domain("example.com").unwrap().unwrap().id, weak.id);
+
+        store.clear_all().unwrap();
+        assert!(store.get_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_store_suite() {
+        run_store_suite(&ProfileDatabase::new_in_memory().unwrap());
+    }
+
+    #[test]
+    fn test_memory_store_suite() {
+        run_store_suite(&MemoryProfileStore::new());
+    }
 }
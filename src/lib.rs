@@ -5,18 +5,33 @@
 
 pub mod auto_selectors;
 pub mod deep_scraper;
+pub mod error;
+pub mod etsy;
+pub mod etsy_reviews;
+pub mod interceptor;
+pub mod json_ld;
 pub mod learning_profile;
+pub mod processors;
+pub mod render;
 pub mod scraper;
 pub mod structure_analyzer;
 pub mod utils;
 
+#[cfg(test)]
+mod test_support;
+
 // Re-export main types for convenience
-pub use auto_selectors::{AutoSelectors, DetectedContent, ImageData, LinkData, SelectorDetector};
-pub use deep_scraper::{DeepScraper, DeepScrapeConfig, DeepScrapeResult, CrawlStatus, CrawlNode};
-pub use learning_profile::{ProfileDatabase, SiteProfile, ProfileStats};
-pub use scraper::{ScrapingConfig, ScrapingResult, ScrapingSession, WebScraper};
+pub use auto_selectors::{AccessState, AutoSelectors, DetectedContent, ImageData, LinkData, PriceData, RatingData, RecipeData, SelectorDetector, TitlePolicy};
+pub use deep_scraper::{DeepScraper, DeepScrapeConfig, DeepScrapeResult, CrawlStatus, CrawlNode, CrawlSummary, PageValidators};
+pub use error::{Result, ScraperError};
+pub use etsy::{EtsyProduct, EtsyScraper, EtsyScrapingResult, Variation};
+pub use interceptor::RequestInterceptor;
+pub use learning_profile::{MemoryProfileStore, ProfileDatabase, ProfileStats, ProfileStore, ProfileVerification, SiteProfile};
+pub use processors::{ContentProcessor, NormalizeWhitespace, StripControlChars};
+pub use render::{HttpRenderBackend, RenderBackend};
+pub use scraper::{ContentDiff, ExtractionTimings, ScrapingConfig, ScrapingResult, ScrapingSession, WebScraper};
 pub use structure_analyzer::{
     StructureAnalysis, StructureAnalyzer, Section, SectionType,
-    Recommendations, ExtractionMode, ConfidenceLevel
+    Recommendations, ExtractionMode, ConfidenceLevel, ScoringWeights, SeoAudit
 };
-pub use utils::{get_random_user_agent, RateLimiter, USER_AGENTS};
+pub use utils::{get_random_user_agent, DomainRateLimiter, FetchCache, RateLimiter, WhitespacePolicy, USER_AGENTS};
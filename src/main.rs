@@ -1,18 +1,29 @@
 use actix_cors::Cors;
 use actix_files as fs;
 use actix_web::{middleware, web, App, HttpServer};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
 
 mod api;
 mod auto_selectors;
 mod deep_scraper;
+mod error;
+mod interceptor;
+mod json_ld;
 mod learning_profile;
+mod processors;
+mod render;
 mod scraper;
 mod structure_analyzer;
 mod utils;
 
+#[cfg(test)]
+mod test_support;
+
 use api::AppState;
-use learning_profile::ProfileDatabase;
+use learning_profile::{ProfileDatabase, ProfileStore};
+use utils::DomainRateLimiter;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -28,9 +39,35 @@ async fn main() -> std::io::Result<()> {
 
     log::info!("📊 Profile database initialized at: {}", db_path);
 
+    // One politeness budget per host, shared by every scrape in the process
+    let host_rate_limit = std::env::var("HOST_RATE_LIMIT")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(2.0);
+    let host_rate_jitter = std::env::var("HOST_RATE_JITTER")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    // Caps fetches in flight across batch endpoints like /api/scrape/validate
+    let batch_concurrency = std::env::var("BATCH_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(api::DEFAULT_BATCH_CONCURRENCY);
+
+    let scrape_timeout_secs = std::env::var("SCRAPE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|secs| *secs > 0.0 && std::time::Duration::try_from_secs_f64(*secs).is_ok());
+
     let state = web::Data::new(AppState {
         sessions: Arc::new(Mutex::new(Vec::new())),
-        profiles: Arc::new(Mutex::new(profile_db)),
+        deep_stats: Arc::new(Mutex::new(BTreeMap::new())),
+        profiles: Arc::new(Mutex::new(Box::new(profile_db) as Box<dyn ProfileStore>)),
+        rate_limiter: Arc::new(DomainRateLimiter::new(host_rate_limit).with_domain_jitter(host_rate_jitter)),
+        batch_limiter: Arc::new(Semaphore::new(batch_concurrency)),
+        scrape_timeout_secs,
     });
 
     let host = std::env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
@@ -58,16 +95,26 @@ async fn main() -> std::io::Result<()> {
             // API routes
             .route("/api/health", web::get().to(api::health_check))
             .route("/api/scrape", web::post().to(api::scrape_handler))
+            .route("/api/scrape/validate", web::post().to(api::validate_handler))
             .route("/api/deep-scrape", web::post().to(api::deep_scrape_handler))
             .route("/api/analyze", web::post().to(api::analyze_handler))
+            .route("/api/analyze/rescore", web::post().to(api::rescore_handler))
+            .route("/api/diff", web::post().to(api::diff_handler))
+            .route("/api/scrape-analyze", web::post().to(api::scrape_analyze_handler))
+            .route("/api/audit", web::post().to(api::audit_handler))
+            .route("/api/selectors/test", web::post().to(api::selector_test_handler))
+            .route("/api/selectors/infer", web::post().to(api::selector_infer_handler))
             .route("/api/sessions", web::get().to(api::get_sessions))
             .route("/api/sessions", web::delete().to(api::clear_sessions))
             .route("/api/sessions/{id}", web::get().to(api::get_session))
+            .route("/api/stats/domains", web::get().to(api::get_domain_stats))
             // Profile management routes
             .route("/api/profiles", web::get().to(api::get_profiles))
             .route("/api/profiles/stats", web::get().to(api::get_profile_stats))
             .route("/api/profiles", web::delete().to(api::clear_profiles))
             .route("/api/profiles/{id}", web::get().to(api::get_profile))
+            .route("/api/profiles/{id}/selectors", web::get().to(api::get_profile_selectors))
+            .route("/api/profiles/{id}/verify", web::post().to(api::verify_profile_handler))
             .route("/api/profiles/{id}", web::delete().to(api::delete_profile))
             .route("/api/profiles/domain/{domain}", web::get().to(api::get_profile_by_domain))
             // Serve static files
@@ -0,0 +1,68 @@
+use crate::utils::{normalize_whitespace, WhitespacePolicy};
+
+/// A cleanup step applied to every extracted content block before it lands in
+/// `DetectedContent`. Blocks that come back empty are dropped.
+pub trait ContentProcessor: Send + Sync {
+    fn process(&self, text: &str) -> String;
+}
+
+impl std::fmt::Debug for dyn ContentProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ContentProcessor")
+    }
+}
+
+/// Re-apply a whitespace policy, e.g. after a processor that removed words
+pub struct NormalizeWhitespace(pub WhitespacePolicy);
+
+impl ContentProcessor for NormalizeWhitespace {
+    fn process(&self, text: &str) -> String {
+        normalize_whitespace(text, self.0)
+    }
+}
+
+/// Remove control characters, keeping newlines and tabs
+pub struct StripControlChars;
+
+impl ContentProcessor for StripControlChars {
+    fn process(&self, text: &str) -> String {
+        text.chars()
+            .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_selectors::SelectorDetector;
+    use std::sync::Arc;
+
+    struct Uppercase;
+
+    impl ContentProcessor for Uppercase {
+        fn process(&self, text: &str) -> String {
+            text.to_uppercase()
+        }
+    }
+
+    #[test]
+    fn test_processors_apply_to_content_blocks() {
+        let html = "<html><body><article><p>Quiet words\u{7} in lower case.</p></article></body></html>";
+
+        let detector = SelectorDetector::new().with_processors(vec![
+            Arc::new(StripControlChars),
+            Arc::new(Uppercase),
+        ]);
+        let content = detector.detect(html, "https://example.com");
+
+        assert!(!content.content.is_empty());
+        assert!(content.content.iter().all(|block| block == "QUIET WORDS IN LOWER CASE."));
+    }
+
+    #[test]
+    fn test_normalize_whitespace_processor() {
+        let processor = NormalizeWhitespace(WhitespacePolicy::Collapse);
+        assert_eq!(processor.process("  a \n\n b  "), "a b");
+    }
+}
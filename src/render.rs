@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+
+use crate::utils::get_random_user_agent;
+
+/// Produces the HTML for a URL.
+///
+/// Implement this outside the crate with a headless browser (e.g. chromiumoxide)
+/// to scrape JavaScript-rendered pages without pulling browser deps in here.
+pub trait RenderBackend: Send + Sync {
+    fn render<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<String>>;
+}
+
+impl std::fmt::Debug for dyn RenderBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RenderBackend")
+    }
+}
+
+/// Default backend: a plain HTTP GET with no JavaScript execution
+pub struct HttpRenderBackend {
+    client: reqwest::Client,
+}
+
+impl HttpRenderBackend {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Default for HttpRenderBackend {
+    fn default() -> Self {
+        Self::new(reqwest::Client::new())
+    }
+}
+
+impl RenderBackend for HttpRenderBackend {
+    fn render<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let response = self.client
+                .get(url)
+                .header("User-Agent", get_random_user_agent())
+                .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+                .send()
+                .await
+                .context("Failed to fetch page")?;
+
+            if !response.status().is_success() {
+                anyhow::bail!("HTTP error: {}", response.status());
+            }
+
+            response.text().await.context("Failed to read response body")
+        })
+    }
+}
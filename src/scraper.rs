@@ -1,11 +1,26 @@
-use anyhow::{Context, Result};
 use chrono::Local;
+use encoding_rs::Encoding;
+use futures::stream::{self, StreamExt};
+use reqwest::header::{HeaderName, HeaderValue};
+use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use url::Url;
 
-use crate::auto_selectors::{AutoSelectors, DetectedContent, SelectorDetector};
-use crate::utils::{get_random_user_agent, RateLimiter};
+use crate::error::{Result, ScraperError};
+use crate::auto_selectors::{
+    default_max_block_chars, default_max_content_blocks, AutoSelectors, DetectedContent, LinkData,
+    SelectorDetector, TitlePolicy, DEFAULT_MAX_BLOCK_CHARS, DEFAULT_MAX_CONTENT_BLOCKS,
+};
+use crate::interceptor::RequestInterceptor;
+use crate::processors::ContentProcessor;
+use crate::render::RenderBackend;
+use crate::utils::{
+    get_random_user_agent, normalize_url, normalize_url_query, normalize_whitespace, DomainRateLimiter, FetchCache,
+    FetchedPage, RateLimiter, WhitespacePolicy,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrapingConfig {
@@ -16,8 +31,90 @@ pub struct ScrapingConfig {
     pub max_pages: usize,
     #[serde(default)]
     pub rate_limit: f64,
+    /// Give up after this many seconds, abandoning the page in progress and
+    /// returning what was scraped so far with `ScrapingSession::timed_out` set
+    #[serde(default)]
+    pub max_duration_secs: Option<f64>,
+    /// Pages scraped at once when pagination is off; 0 or 1 scrapes one at a time.
+    /// Results and errors are still reported in `urls` order.
+    #[serde(default)]
+    pub concurrency: usize,
+    /// Allow this many back-to-back requests before throttling to `rate_limit`
+    #[serde(default)]
+    pub burst: Option<u32>,
     #[serde(default)]
     pub custom_selectors: Option<AutoSelectors>,
+    /// Fetch the AMP version of a page instead when one is advertised
+    #[serde(default)]
+    pub prefer_amp: bool,
+    /// Start pagination from a cursor saved by a previous session instead of `urls`
+    #[serde(default)]
+    pub resume_cursor: Option<String>,
+    /// Cleanup applied to extracted text blocks
+    #[serde(default)]
+    pub whitespace_policy: WhitespacePolicy,
+    /// How the title is chosen when several title selectors match
+    #[serde(default)]
+    pub title_policy: TitlePolicy,
+    /// Stop paginating once a page matches this selector (e.g. `.empty-results`)
+    #[serde(default)]
+    pub stop_selector: Option<String>,
+    /// Selector matching one listing item (e.g. `.product-card`), counted into
+    /// `ScrapingResult::item_count`. Without it only pages with ItemList JSON-LD
+    /// have their items counted.
+    #[serde(default)]
+    pub item_selector: Option<String>,
+    /// Extract `<noscript>` fallback content when the visible page is nearly empty
+    #[serde(default)]
+    pub noscript_fallback: bool,
+    /// Most content blocks extracted per page
+    #[serde(default = "default_max_content_blocks")]
+    pub max_content_blocks: usize,
+    /// Longest content block kept, in characters; longer ones are cut
+    #[serde(default = "default_max_block_chars")]
+    pub max_block_chars: usize,
+    /// Keep the raw response headers on each result (for debugging anti-bot behavior)
+    #[serde(default)]
+    pub capture_headers: bool,
+    /// Keep the HTML each result was extracted from in `ScrapingResult::raw_html`
+    #[serde(default)]
+    pub store_raw_html: bool,
+    /// Keep `application/json` responses as parsed JSON in `ScrapingResult::json`
+    /// instead of running HTML extraction on them
+    #[serde(default)]
+    pub parse_json: bool,
+    /// Named CSS selectors (e.g. `price` -> `.price`) extracted into `ScrapingResult::fields`
+    #[serde(default)]
+    pub field_selectors: Option<HashMap<String, String>>,
+    /// Query parameters, besides `utm_*` and click ids, that don't change which
+    /// page is served; ignored when checking whether a page was already visited
+    #[serde(default)]
+    pub ignored_query_params: Vec<String>,
+    /// Headers sent with every request, replacing the defaults of the same name
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    /// Decode every body with this encoding (e.g. "Shift_JIS"), ignoring the
+    /// charset the site declares. For sites that declare the wrong one.
+    #[serde(default)]
+    pub force_encoding: Option<String>,
+    /// Cleanups applied, in order, to every extracted content block
+    #[serde(skip)]
+    pub processors: Vec<Arc<dyn ContentProcessor>>,
+    /// Replaces the plain HTTP fetch, e.g. with a headless browser for SPAs
+    #[serde(skip)]
+    pub render_backend: Option<Arc<dyn RenderBackend>>,
+    /// Process-wide per-host limiter, honoured on top of this scraper's own `rate_limit`
+    #[serde(skip)]
+    pub domain_rate_limiter: Option<Arc<DomainRateLimiter>>,
+    /// Serve repeat fetches of a URL from recently fetched bodies
+    #[serde(skip)]
+    pub fetch_cache: Option<Arc<FetchCache>>,
+    /// Permits shared with other scrapers; one is held for the duration of every fetch
+    #[serde(skip)]
+    pub inflight_limiter: Option<Arc<Semaphore>>,
+    /// Hook that can rewrite every outgoing request before it is sent
+    #[serde(skip)]
+    pub request_interceptor: Option<Arc<dyn RequestInterceptor>>,
 }
 
 impl Default for ScrapingConfig {
@@ -27,7 +124,32 @@ impl Default for ScrapingConfig {
             enable_pagination: false,
             max_pages: 0,
             rate_limit: 2.0,
+            max_duration_secs: None,
+            concurrency: 0,
+            burst: None,
             custom_selectors: None,
+            prefer_amp: false,
+            resume_cursor: None,
+            whitespace_policy: WhitespacePolicy::default(),
+            title_policy: TitlePolicy::default(),
+            stop_selector: None,
+            item_selector: None,
+            noscript_fallback: false,
+            max_content_blocks: DEFAULT_MAX_CONTENT_BLOCKS,
+            max_block_chars: DEFAULT_MAX_BLOCK_CHARS,
+            capture_headers: false,
+            store_raw_html: false,
+            parse_json: false,
+            field_selectors: None,
+            ignored_query_params: Vec::new(),
+            extra_headers: HashMap::new(),
+            force_encoding: None,
+            processors: Vec::new(),
+            render_backend: None,
+            domain_rate_limiter: None,
+            fetch_cache: None,
+            inflight_limiter: None,
+            request_interceptor: None,
         }
     }
 }
@@ -39,6 +161,101 @@ pub struct ScrapingResult {
     pub status: String,
     pub content: DetectedContent,
     pub page_number: usize,
+    /// 0–1 signal of how much useful content was extracted
+    #[serde(default)]
+    pub quality_score: f64,
+    /// Response headers, only filled when `capture_headers` is enabled
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Where the request ended up, when redirects led away from `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    /// Matches of each `field_selectors` entry, by field name
+    #[serde(default)]
+    pub fields: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub timings: ExtractionTimings,
+    /// Listing items on this page, see `ScrapingConfig::item_selector`; None
+    /// when the page offers no way to count them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_count: Option<usize>,
+    /// HTML the content was extracted from, only filled when `store_raw_html` is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_html: Option<String>,
+    /// Parsed body of a JSON response, only filled when `parse_json` is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<serde_json::Value>,
+}
+
+/// Where the time went for one page, in milliseconds. Rate-limit waits and the
+/// AMP refetch are not included.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtractionTimings {
+    pub fetch_ms: f64,
+    pub parse_ms: f64,
+    pub detect_ms: f64,
+}
+
+/// What changed in a page's extracted content between two scrapes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentDiff {
+    /// `(previous, current)` titles, when they differ
+    pub title_changed: Option<(Option<String>, Option<String>)>,
+    /// Content blocks only in the current scrape, in page order
+    pub added: Vec<String>,
+    /// Content blocks only in the previous scrape, in page order
+    pub removed: Vec<String>,
+}
+
+impl ContentDiff {
+    pub fn is_empty(&self) -> bool {
+        self.title_changed.is_none() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl ScrapingResult {
+    /// Block-level diff of this scrape's content against an earlier scrape of the same
+    /// page. Blocks kept in the same order (their longest common subsequence) count
+    /// as unchanged; an edited block shows up as removed and added.
+    pub fn content_diff(&self, previous: &ScrapingResult) -> ContentDiff {
+        let old = &previous.content.content;
+        let new = &self.content.content;
+
+        // lcs[i][j]: longest common subsequence of old[i..] and new[j..]
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut diff = ContentDiff::default();
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() && j < new.len() {
+            if old[i] == new[j] {
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                diff.removed.push(old[i].clone());
+                i += 1;
+            } else {
+                diff.added.push(new[j].clone());
+                j += 1;
+            }
+        }
+        diff.removed.extend(old[i..].iter().cloned());
+        diff.added.extend(new[j..].iter().cloned());
+
+        if previous.content.title != self.content.title {
+            diff.title_changed = Some((previous.content.title.clone(), self.content.title.clone()));
+        }
+
+        diff
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +267,24 @@ pub struct ScrapingSession {
     pub total_links_found: usize,
     pub total_images_found: usize,
     pub errors: Vec<String>,
+    /// Next unvisited page of the last paginated listing; pass it back as
+    /// `resume_cursor` to continue. None when pagination ran to the end.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Listing sizes announced (see `DetectedContent::total_items`), summed over
+    /// the pagination chains that announce one and have countable items
+    #[serde(default)]
+    pub total_items_expected: Option<usize>,
+    /// Sum of `item_count` over those chains
+    #[serde(default)]
+    pub items_collected: usize,
+    /// Items announced but not collected, summed over chains that came up short;
+    /// a sign pagination stopped early
+    #[serde(default)]
+    pub items_shortfall: Option<usize>,
+    /// `max_duration_secs` ran out before every page was scraped
+    #[serde(default)]
+    pub timed_out: bool,
 }
 
 pub struct WebScraper {
@@ -57,6 +292,22 @@ pub struct WebScraper {
     rate_limiter: RateLimiter,
     detector: SelectorDetector,
     verbose: bool,
+    max_pages: usize,
+    prefer_amp: bool,
+    capture_headers: bool,
+    store_raw_html: bool,
+    parse_json: bool,
+    field_selectors: Vec<(String, Selector)>,
+    ignored_query_params: Vec<String>,
+    extra_headers: Vec<(HeaderName, HeaderValue)>,
+    force_encoding: Option<&'static Encoding>,
+    stop_selector: Option<Selector>,
+    item_selector: Option<Selector>,
+    render_backend: Option<Arc<dyn RenderBackend>>,
+    domain_rate_limiter: Option<Arc<DomainRateLimiter>>,
+    fetch_cache: Option<Arc<FetchCache>>,
+    inflight_limiter: Option<Arc<Semaphore>>,
+    request_interceptor: Option<Arc<dyn RequestInterceptor>>,
 }
 
 impl WebScraper {
@@ -64,43 +315,216 @@ impl WebScraper {
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
             .cookie_store(true)
-            .build()?;
+            .build()
+            .map_err(|e| ScraperError::Config(format!("Failed to create HTTP client: {}", e)))?;
 
         let detector = if let Some(ref custom) = config.custom_selectors {
             SelectorDetector::with_custom_selectors(custom.clone())
         } else {
             SelectorDetector::new()
+        }
+        .with_whitespace_policy(config.whitespace_policy)
+        .with_title_policy(config.title_policy)
+        .with_noscript_fallback(config.noscript_fallback)
+        .with_content_limits(config.max_content_blocks, config.max_block_chars)
+        .with_processors(config.processors.clone());
+
+        let stop_selector = match config.stop_selector {
+            Some(ref selector) => Some(
+                Selector::parse(selector)
+                    .map_err(|e| ScraperError::Selector {
+                        selector: selector.clone(),
+                        message: format!("stop selector: {:?}", e),
+                    })?,
+            ),
+            None => None,
+        };
+
+        let item_selector = match config.item_selector {
+            Some(ref selector) => Some(
+                Selector::parse(selector)
+                    .map_err(|e| ScraperError::Selector {
+                        selector: selector.clone(),
+                        message: format!("item selector: {:?}", e),
+                    })?,
+            ),
+            None => None,
+        };
+
+        let mut field_selectors = Vec::new();
+        for (name, selector) in config.field_selectors.iter().flatten() {
+            let parsed = Selector::parse(selector)
+                .map_err(|e| ScraperError::Selector {
+                    selector: selector.clone(),
+                    message: format!("field '{}': {:?}", name, e),
+                })?;
+            field_selectors.push((name.clone(), parsed));
+        }
+
+        let mut extra_headers = Vec::new();
+        for (name, value) in &config.extra_headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| ScraperError::Config(format!("Invalid header name '{}': {}", name, e)))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|e| ScraperError::Config(format!("Invalid value for header '{}': {}", name, e)))?;
+            extra_headers.push((header_name, header_value));
+        }
+
+        let force_encoding = match config.force_encoding {
+            Some(ref label) => Some(
+                Encoding::for_label(label.trim().as_bytes())
+                    .ok_or_else(|| ScraperError::Config(format!("Unknown encoding '{}'", label)))?,
+            ),
+            None => None,
         };
 
         Ok(Self {
             client,
-            rate_limiter: RateLimiter::new(config.rate_limit),
+            rate_limiter: match config.burst {
+                Some(capacity) => RateLimiter::token_bucket(capacity, config.rate_limit * 60.0),
+                None => RateLimiter::new(config.rate_limit),
+            },
             detector,
             verbose,
+            max_pages: config.max_pages,
+            prefer_amp: config.prefer_amp,
+            capture_headers: config.capture_headers,
+            store_raw_html: config.store_raw_html,
+            parse_json: config.parse_json,
+            field_selectors,
+            ignored_query_params: config.ignored_query_params.clone(),
+            extra_headers,
+            force_encoding,
+            stop_selector,
+            item_selector,
+            render_backend: config.render_backend.clone(),
+            domain_rate_limiter: config.domain_rate_limiter.clone(),
+            fetch_cache: config.fetch_cache.clone(),
+            inflight_limiter: config.inflight_limiter.clone(),
+            request_interceptor: config.request_interceptor.clone(),
         })
     }
 
+    /// Fetch `url` and return the text of every element matching `selector`,
+    /// without building a config. Handy for one-off scripts.
+    pub async fn quick_extract(url: &str, selector: &str) -> Result<Vec<String>> {
+        let parsed = Selector::parse(selector)
+            .map_err(|e| ScraperError::Selector { selector: selector.to_string(), message: format!("{:?}", e) })?;
+
+        let scraper = Self::new(&ScrapingConfig::default(), false)?;
+        let html = scraper.fetch_page(url).await?;
+        let document = Html::parse_document(&html);
+
+        Ok(document.select(&parsed)
+            .map(|element| {
+                let text: String = element.text().collect();
+                normalize_whitespace(&text, WhitespacePolicy::Collapse)
+            })
+            .collect())
+    }
+
+    /// Fetch `url` and return only the links whose absolute href matches the
+    /// `pattern` regex (e.g. `\.pdf$`), skipping content extraction.
+    pub async fn harvest_links(url: &str, pattern: &str) -> Result<Vec<LinkData>> {
+        let pattern = regex::Regex::new(pattern)
+            .map_err(|e| ScraperError::Config(format!("Invalid link pattern '{}': {}", pattern, e)))?;
+
+        let scraper = Self::new(&ScrapingConfig::default(), false)?;
+        let html = scraper.fetch_page(url).await?;
+
+        Ok(scraper.detector.detect_links_only(&html, url)
+            .into_iter()
+            .filter(|link| pattern.is_match(&link.href))
+            .collect())
+    }
+
     pub async fn scrape(&self, config: ScrapingConfig) -> Result<ScrapingSession> {
         let start_time = Local::now().to_rfc3339();
         let mut all_results = Vec::new();
         let mut errors = Vec::new();
+        let mut cursor = None;
+        let mut timed_out = false;
+        // (expected, collected) items of each pagination chain that can tell
+        let mut item_tallies = Vec::new();
+        let deadline = match config.max_duration_secs {
+            Some(secs) => {
+                let budget = Duration::try_from_secs_f64(secs)
+                    .map_err(|_| ScraperError::Config(format!("Invalid max_duration_secs: {}", secs)))?;
+                // A budget past the clock's range is as good as none
+                tokio::time::Instant::now().checked_add(budget)
+            }
+            None => None,
+        };
+
+        let start_urls = match config.resume_cursor {
+            Some(ref resume) => vec![resume.clone()],
+            None => config.urls.clone(),
+        };
+
+        if !config.enable_pagination && config.concurrency > 1 {
+            // Completion order varies between runs; restore input order afterwards
+            let mut outcomes: Vec<(usize, Result<ScrapingResult>)> = stream::iter(start_urls.iter().enumerate())
+                .map(|(index, url)| async move { (index, before_deadline(deadline, self.scrape_single_page(url, 1)).await) })
+                .buffer_unordered(config.concurrency)
+                .collect()
+                .await;
+            outcomes.sort_by_key(|(index, _)| *index);
 
-        for url in &config.urls {
-            if config.enable_pagination {
-                match self.scrape_with_pagination(url, config.max_pages).await {
-                    Ok(mut results) => all_results.append(&mut results),
-                    Err(e) => errors.push(format!("Failed to scrape {}: {}", url, e)),
+            for (index, outcome) in outcomes {
+                match outcome {
+                    Some(Ok(result)) => {
+                        item_tallies.extend(chain_items(std::slice::from_ref(&result)));
+                        all_results.push(result);
+                    }
+                    Some(Err(e)) => errors.push(format!("Failed to scrape {}: {}", start_urls[index], e)),
+                    None => timed_out = true,
                 }
-            } else {
-                match self.scrape_single_page(url, 1).await {
-                    Ok(result) => all_results.push(result),
-                    Err(e) => errors.push(format!("Failed to scrape {}: {}", url, e)),
+            }
+        } else {
+            for url in &start_urls {
+                if config.enable_pagination {
+                    match self.scrape_with_pagination(url, config.max_pages, deadline).await {
+                        Ok((mut results, next, expired)) => {
+                            item_tallies.extend(chain_items(&results));
+                            all_results.append(&mut results);
+                            cursor = next;
+                            if expired {
+                                timed_out = true;
+                                break;
+                            }
+                        }
+                        Err(e) => errors.push(format!("Failed to scrape {}: {}", url, e)),
+                    }
+                } else {
+                    match before_deadline(deadline, self.scrape_single_page(url, 1)).await {
+                        Some(Ok(result)) => {
+                            item_tallies.extend(chain_items(std::slice::from_ref(&result)));
+                            all_results.push(result);
+                        }
+                        Some(Err(e)) => errors.push(format!("Failed to scrape {}: {}", url, e)),
+                        None => {
+                            timed_out = true;
+                            break;
+                        }
+                    }
                 }
             }
         }
 
+        if timed_out {
+            log::warn!("Scrape ran out of time after {} page(s)", all_results.len());
+        }
+
         let total_links_found = all_results.iter().map(|r| r.content.links.len()).sum();
         let total_images_found = all_results.iter().map(|r| r.content.images.len()).sum();
+        let total_items_expected = (!item_tallies.is_empty())
+            .then(|| item_tallies.iter().map(|(expected, _)| expected).sum());
+        let items_collected = item_tallies.iter().map(|(_, collected)| collected).sum();
+        let missing: usize = item_tallies.iter().map(|(expected, collected)| expected.saturating_sub(*collected)).sum();
+        let items_shortfall = (missing > 0).then_some(missing);
+        if let (Some(expected), Some(missing)) = (total_items_expected, items_shortfall) {
+            log::warn!("Collected {} of {} expected items ({} missing)", items_collected, expected, missing);
+        }
 
         Ok(ScrapingSession {
             start_time,
@@ -110,42 +534,135 @@ impl WebScraper {
             total_images_found,
             results: all_results,
             errors,
+            cursor,
+            total_items_expected,
+            items_collected,
+            items_shortfall,
+            timed_out,
         })
     }
 
+    /// Wait out this scraper's own rate limit and, when set, the shared per-host budget
+    async fn throttle(&self, url: &str) {
+        match self.domain_rate_limiter {
+            Some(ref limiter) => {
+                tokio::join!(limiter.wait(url), self.rate_limiter.wait());
+            }
+            None => self.rate_limiter.wait().await,
+        }
+    }
+
     async fn scrape_single_page(&self, url: &str, page_number: usize) -> Result<ScrapingResult> {
+        self.scrape_page_with_html(url, page_number).await.map(|(result, _)| result)
+    }
+
+    /// Scrape a page, also returning the HTML originally fetched for it
+    pub(crate) async fn scrape_page_with_html(&self, url: &str, page_number: usize) -> Result<(ScrapingResult, String)> {
         if self.verbose {
             log::info!("Scraping: {}", url);
         }
 
-        self.rate_limiter.wait().await;
+        self.throttle(url).await;
+
+        let started = Instant::now();
+        let FetchedPage { body: html, headers, final_url, content_type } = self.fetch_response(url).await?;
+        let fetch_ms = elapsed_ms(started);
+
+        let json = if self.parse_json && content_type.as_deref().is_some_and(is_json_content_type) {
+            serde_json::from_str::<serde_json::Value>(&html).ok()
+        } else {
+            None
+        };
+
+        // The parsed document isn't Send, so it must be gone before the next await
+        let (mut content, timings, item_count) = if json.is_some() {
+            (DetectedContent::default(), ExtractionTimings { fetch_ms, ..ExtractionTimings::default() }, None)
+        } else {
+            let started = Instant::now();
+            let document = Html::parse_document(&html);
+            let parse_ms = elapsed_ms(started);
+
+            let started = Instant::now();
+            let content = self.detector.detect_document(&document, url);
+            let timings = ExtractionTimings { fetch_ms, parse_ms, detect_ms: elapsed_ms(started) };
+            let item_count = match self.item_selector {
+                Some(ref selector) => Some(document.select(selector).count()),
+                None => content.listed_items,
+            };
+            (content, timings, item_count)
+        };
+
+        let mut amp_html = None;
+        if let Some(amp_url) = self.amp_target(&content, url) {
+            if self.verbose {
+                log::info!("Using AMP version: {}", amp_url);
+            }
+
+            self.throttle(&amp_url).await;
+            match self.fetch_page(&amp_url).await {
+                Ok(body) => {
+                    content = DetectedContent {
+                        amp_url: content.amp_url,
+                        mobile_url: content.mobile_url,
+                        ..self.detector.detect(&body, &amp_url)
+                    };
+                    amp_html = Some(body);
+                }
+                Err(e) => log::warn!("Failed to fetch AMP version {}: {}", amp_url, e),
+            }
+        }
 
-        let html = self.fetch_page(url).await?;
-        let content = self.detector.detect(&html, url);
+        // Fields come from the same copy the content was detected in
+        let fields = self.extract_fields(amp_html.as_deref().unwrap_or(&html));
 
-        Ok(ScrapingResult {
+        let result = ScrapingResult {
             url: url.to_string(),
             timestamp: Local::now().to_rfc3339(),
             status: "success".to_string(),
+            quality_score: quality_score(&content),
             content,
             page_number,
-        })
+            headers,
+            final_url: (normalize_url(&final_url) != normalize_url(url)).then_some(final_url),
+            fields,
+            timings,
+            item_count,
+            raw_html: self.store_raw_html.then(|| amp_html.unwrap_or_else(|| html.clone())),
+            json,
+        };
+
+        Ok((result, html))
     }
 
-    async fn scrape_with_pagination(&self, start_url: &str, max_pages: usize) -> Result<Vec<ScrapingResult>> {
+    /// Follow next-page links from `start_url`, returning the results and the
+    /// cursor (next unvisited page) if the page budget ran out first, and whether
+    /// the deadline cut the walk short
+    async fn scrape_with_pagination(
+        &self,
+        start_url: &str,
+        max_pages: usize,
+        deadline: Option<tokio::time::Instant>,
+    ) -> Result<(Vec<ScrapingResult>, Option<String>, bool)> {
         let mut results = Vec::new();
         let mut visited_urls = HashSet::new();
         let mut current_url = start_url.to_string();
         let mut page_count = 0;
+        let mut cursor = None;
 
         let effective_max_pages = if max_pages > 0 { max_pages } else { usize::MAX };
 
         loop {
-            if visited_urls.contains(&current_url) || page_count >= effective_max_pages {
+            let visit_key = self.visit_key(&current_url);
+            if visited_urls.contains(&visit_key) {
                 break;
             }
 
-            visited_urls.insert(current_url.clone());
+            if page_count >= effective_max_pages {
+                cursor = Some(current_url);
+                break;
+            }
+
+            visited_urls.insert(visit_key);
             page_count += 1;
 
             if self.verbose {
@@ -155,10 +672,25 @@ impl WebScraper {
                 );
             }
 
-            match self.scrape_single_page(&current_url, page_count).await {
-                Ok(result) => {
-                    // Try to find next page link
-                    let next_url = self.find_next_page(&result.content, &current_url);
+            let Some(outcome) = before_deadline(deadline, self.scrape_page_with_html(&current_url, page_count)).await else {
+                // Resume from the abandoned page next time
+                return Ok((results, Some(current_url), true));
+            };
+
+            match outcome {
+                Ok((result, html)) => {
+                    if self.is_stop_page(&html) {
+                        if self.verbose {
+                            log::info!("Stop marker found on {}", current_url);
+                        }
+                        results.push(result);
+                        break;
+                    }
+
+                    // Try to find next page link, then a "load more" button, then a page-number form
+                    let next_url = self.find_next_page(&result.content.links, &current_url, &visited_urls)
+                        .or_else(|| self.find_load_more(&html, &current_url, &visited_urls))
+                        .or_else(|| self.find_page_form(&html, &current_url, &visited_urls));
                     results.push(result);
 
                     if let Some(next) = next_url {
@@ -169,44 +701,311 @@ impl WebScraper {
                 }
                 Err(e) => {
                     log::error!("Failed to scrape {}: {}", current_url, e);
+                    // Resume from the failed page next time
+                    cursor = Some(current_url);
                     break;
                 }
             }
         }
 
-        Ok(results)
+        Ok((results, cursor, false))
     }
 
-    async fn fetch_page(&self, url: &str) -> Result<String> {
+    /// Walk pagination from `start_url` and return every page URL in order, up to
+    /// `max_pages`, without extracting content. Useful to distribute the pages
+    /// before scraping them.
+    pub async fn discover_pages(&self, start_url: &str) -> Result<Vec<String>> {
+        let mut pages: Vec<String> = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current_url = start_url.to_string();
+        let max_pages = if self.max_pages > 0 { self.max_pages } else { usize::MAX };
+
+        while pages.len() < max_pages && visited.insert(self.visit_key(&current_url)) {
+            self.throttle(&current_url).await;
+
+            let html = self.fetch_page(&current_url).await?;
+            pages.push(current_url.clone());
+
+            if self.is_stop_page(&html) {
+                break;
+            }
+
+            let links = self.detector.detect_links_only(&html, &current_url);
+            match self.find_next_page(&links, &current_url, &visited)
+                .or_else(|| self.find_load_more(&html, &current_url, &visited))
+                .or_else(|| self.find_page_form(&html, &current_url, &visited)) {
+                Some(next) => current_url = next,
+                None => break,
+            }
+        }
+
+        Ok(pages)
+    }
+
+    /// Text of every match of each named field selector; elements without text
+    /// (e.g. `meta`, `input`) fall back to their `content`/`value` attribute
+    fn extract_fields(&self, html: &str) -> HashMap<String, Vec<String>> {
+        if self.field_selectors.is_empty() {
+            return HashMap::new();
+        }
+
+        let document = Html::parse_document(html);
+        self.field_selectors.iter()
+            .map(|(name, selector)| {
+                let values = document.select(selector)
+                    .filter_map(|element| {
+                        let text: String = element.text().collect();
+                        let text = normalize_whitespace(&text, WhitespacePolicy::Collapse);
+                        if !text.is_empty() {
+                            return Some(text);
+                        }
+                        element.value().attr("content")
+                            .or_else(|| element.value().attr("value"))
+                            .map(|value| value.trim().to_string())
+                    })
+                    .collect();
+                (name.clone(), values)
+            })
+            .collect()
+    }
+
+    /// Whether the page carries the configured "no more results" marker
+    fn is_stop_page(&self, html: &str) -> bool {
+        match self.stop_selector {
+            Some(ref selector) => Html::parse_document(html).select(selector).next().is_some(),
+            None => false,
+        }
+    }
+
+    /// Pick the AMP alternate of a page when `prefer_amp` is enabled
+    fn amp_target(&self, content: &DetectedContent, url: &str) -> Option<String> {
+        if !self.prefer_amp {
+            return None;
+        }
+
+        content.amp_url.clone().filter(|amp_url| amp_url != url)
+    }
+
+    pub(crate) async fn fetch_page(&self, url: &str) -> Result<String> {
+        self.fetch_response(url).await.map(|page| page.body)
+    }
+
+    /// Fetch a page with its final URL, and its response headers too when `capture_headers` is set
+    async fn fetch_response(&self, url: &str) -> Result<FetchedPage> {
+        if let Some(cached) = self.fetch_cache.as_ref().and_then(|cache| cache.get(url)) {
+            return Ok(cached);
+        }
+
+        let page = self.fetch_uncached(url).await?;
+        if let Some(ref cache) = self.fetch_cache {
+            cache.insert(url, page.clone());
+        }
+        Ok(page)
+    }
+
+    /// Fetch from the network (or render backend), bypassing the cache
+    async fn fetch_uncached(&self, url: &str) -> Result<FetchedPage> {
+        let _permit = match self.inflight_limiter {
+            Some(ref limiter) => limiter.acquire().await.ok(),
+            None => None,
+        };
+
+        if let Some(ref backend) = self.render_backend {
+            return Ok(FetchedPage {
+                body: backend.render(url).await?,
+                headers: HashMap::new(),
+                final_url: url.to_string(),
+                content_type: None,
+            });
+        }
+
         let user_agent = get_random_user_agent();
 
-        let response = self.client
+        let mut request = self.client
             .get(url)
             .header("User-Agent", user_agent)
             .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
             .header("Accept-Language", "en-US,en;q=0.5")
-            .send()
+            .build()
+            .map_err(|source| ScraperError::Fetch { url: url.to_string(), source })?;
+
+        for (name, value) in &self.extra_headers {
+            request.headers_mut().insert(name.clone(), value.clone());
+        }
+
+        if let Some(ref interceptor) = self.request_interceptor {
+            interceptor.intercept(&mut request)?;
+        }
+
+        let response = self.client
+            .execute(request)
             .await
-            .context("Failed to fetch page")?;
+            .map_err(|source| ScraperError::Fetch { url: url.to_string(), source })?;
 
         if !response.status().is_success() {
-            anyhow::bail!("HTTP error: {}", response.status());
+            return Err(ScraperError::Http {
+                url: url.to_string(),
+                status: response.status(),
+                headers: header_map(response.headers()),
+            });
         }
 
-        response.text().await.context("Failed to read response body")
+        let headers = if self.capture_headers {
+            header_map(response.headers())
+        } else {
+            HashMap::new()
+        };
+
+        let final_url = response.url().to_string();
+        let content_type = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let body = match self.force_encoding {
+            Some(encoding) => {
+                let bytes = response.bytes()
+                    .await
+                    .map_err(|source| ScraperError::Fetch { url: url.to_string(), source })?;
+                encoding.decode_without_bom_handling(&bytes).0.into_owned()
+            }
+            None => response.text()
+                .await
+                .map_err(|source| ScraperError::Fetch { url: url.to_string(), source })?,
+        };
+        Ok(FetchedPage { body, headers, final_url, content_type })
     }
 
-    fn find_next_page(&self, content: &DetectedContent, current_url: &str) -> Option<String> {
+    /// Key a page URL is recorded under in the pagination visited set
+    fn visit_key(&self, url: &str) -> String {
+        normalize_url_query(url, &self.ignored_query_params)
+    }
+
+    /// URL behind a "load more" button, often a JSON or partial-HTML endpoint
+    fn find_load_more(&self, html: &str, current_url: &str, visited: &HashSet<String>) -> Option<String> {
+        let document = Html::parse_document(html);
+        let base = Url::parse(current_url).ok()?;
+        let current_key = self.visit_key(current_url);
+
+        for (selector_str, attr) in LOAD_MORE_TARGETS {
+            let Ok(selector) = Selector::parse(selector_str) else {
+                continue;
+            };
+
+            for element in document.select(&selector) {
+                let Some(target) = element.value().attr(attr).map(str::trim).filter(|t| !t.is_empty()) else {
+                    continue;
+                };
+                let Ok(next) = base.join(target) else {
+                    continue;
+                };
+
+                let key = self.visit_key(next.as_str());
+                if key != current_key && !visited.contains(&key) && next.host() == base.host() {
+                    return Some(next.to_string());
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Next page of a listing paginated only through a `<form>` with a page-number
+    /// input: the GET submission with the page incremented. POST forms are skipped
+    /// since pages are only ever fetched with GET.
+    fn find_page_form(&self, html: &str, current_url: &str, visited: &HashSet<String>) -> Option<String> {
+        let document = Html::parse_document(html);
+        let base = Url::parse(current_url).ok()?;
+        let current_key = self.visit_key(current_url);
+        let (Ok(forms), Ok(inputs)) = (Selector::parse("form"), Selector::parse("input[name]")) else {
+            return None;
+        };
+
+        for form in document.select(&forms) {
+            let method = form.value().attr("method").unwrap_or("get");
+            if !method.trim().eq_ignore_ascii_case("get") {
+                continue;
+            }
+
+            let fields: Vec<_> = form.select(&inputs).collect();
+            let by_name = fields.iter().find(|input| {
+                let name = input.value().attr("name").unwrap_or_default().to_ascii_lowercase();
+                PAGE_INPUT_NAMES.contains(&name.as_str())
+            });
+            // Any number box could be a quantity or a price filter; only trust one
+            // when the form itself talks about pages
+            let Some(page_input) = by_name.or_else(|| {
+                fields.iter()
+                    .find(|input| input.value().attr("type").is_some_and(|t| t.eq_ignore_ascii_case("number")))
+                    .filter(|_| form_mentions_page(form))
+            }) else {
+                continue;
+            };
+            let page_name = page_input.value().attr("name").unwrap_or_default();
+
+            // The page being shown: the input's value, else the URL's, else the first
+            let current_page = page_input.value().attr("value")
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .or_else(|| base.query_pairs().find(|(k, _)| k == page_name).and_then(|(_, v)| v.parse().ok()))
+                .unwrap_or(1);
+            let next_page = current_page + 1;
+            let last_page = page_input.value().attr("max").and_then(|v| v.trim().parse::<usize>().ok());
+            if last_page.is_some_and(|last| next_page > last) {
+                continue;
+            }
+
+            let action = form.value().attr("action").map(str::trim).filter(|a| !a.is_empty()).unwrap_or(current_url);
+            let Ok(mut next) = base.join(action) else {
+                continue;
+            };
+
+            // A GET submission replaces the action's query with the form's fields
+            next.set_query(None);
+            {
+                let mut query = next.query_pairs_mut();
+                for input in &fields {
+                    let name = input.value().attr("name").unwrap_or_default();
+                    let kind = input.value().attr("type").unwrap_or("text").to_ascii_lowercase();
+                    if name == page_name {
+                        query.append_pair(name, &next_page.to_string());
+                    } else if !matches!(kind.as_str(), "submit" | "button" | "image" | "reset" | "file")
+                        && (!matches!(kind.as_str(), "checkbox" | "radio") || input.value().attr("checked").is_some()) {
+                        query.append_pair(name, input.value().attr("value").unwrap_or_default());
+                    }
+                }
+            }
+
+            let key = self.visit_key(next.as_str());
+            if key != current_key && !visited.contains(&key) && next.host() == base.host() {
+                return Some(next.to_string());
+            }
+        }
+
+        None
+    }
+
+    /// Link to the next page of a listing, if `links` (from `current_url`) has one
+    pub fn next_page_link(&self, links: &[LinkData], current_url: &str) -> Option<String> {
+        self.find_next_page(links, current_url, &HashSet::new())
+    }
+
+    fn find_next_page(&self, links: &[LinkData], current_url: &str, visited: &HashSet<String>) -> Option<String> {
         // Look for common pagination patterns
         let pagination_keywords = ["next", "next page", "→", "»", "›"];
+        let current_key = self.visit_key(current_url);
+
+        for link in links {
+            // Same page, or one already scraped, behind a different query string
+            let key = self.visit_key(&link.href);
+            if key == current_key || visited.contains(&key) {
+                continue;
+            }
 
-        for link in &content.links {
             let text_lower = link.text.to_lowercase();
 
             // Check if link text contains pagination keywords
             if pagination_keywords.iter().any(|&kw| text_lower.contains(kw)) {
-                // Ensure it's not an external link and not the current page
-                if !link.is_external && link.href != current_url {
+                // Ensure it's not an external link and that it goes somewhere
+                if !link.is_external && advances_page(current_url, &link.href) {
                     return Some(link.href.clone());
                 }
             }
@@ -214,7 +1013,8 @@ impl WebScraper {
             // Check for rel="next" pattern in href
             if link.href.contains("page=") || link.href.contains("p=") {
                 if let (Ok(current), Ok(next)) = (Url::parse(current_url), Url::parse(&link.href)) {
-                    if current.host() == next.host() && current.path() == next.path() {
+                    if current.host() == next.host() && current.path() == next.path()
+                        && advances_page(current_url, &link.href) {
                         return Some(link.href.clone());
                     }
                 }
@@ -225,11 +1025,137 @@ impl WebScraper {
     }
 }
 
-use std::time::Duration;
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Response headers under their lowercased names, repeated headers joined with ", "
+fn header_map(headers: &reqwest::header::HeaderMap) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = HashMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+        map.entry(name.to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    map
+}
+
+/// "Load more" buttons and the attributes holding the URL of the next chunk
+const LOAD_MORE_TARGETS: &[(&str, &str)] = &[
+    ("[data-next-page]", "data-next-page"),
+    (".load-more[data-url]", "data-url"),
+    (".load-more[data-next]", "data-next"),
+];
+
+/// Query parameters that select a page of a listing
+const PAGE_PARAMS: &[&str] = &["page", "p", "pg", "paged", "offset", "start", "cursor", "after"];
+
+/// Names of form inputs holding a page number
+const PAGE_INPUT_NAMES: &[&str] = &["page", "p", "pg", "paged", "pagenum", "page_no", "pageno"];
+
+/// Whether a form's labels or buttons are about pages ("Go to page", "Page:"),
+/// not counting "per page" size pickers
+fn form_mentions_page(form: ElementRef) -> bool {
+    let Ok(selector) = Selector::parse("label, button, input[type='submit' i], input[type='button' i]") else {
+        return false;
+    };
+
+    form.select(&selector).any(|element| {
+        let text = match element.value().name() {
+            "input" => element.value().attr("value").unwrap_or_default().to_string(),
+            _ => element.text().collect(),
+        };
+        let text = text.to_lowercase().replace("per page", "");
+        text.split(|c: char| !c.is_alphanumeric()).any(|word| word == "page")
+    })
+}
+
+/// Whether following `next_url` from `current_url` moves anywhere: another path,
+/// or another value for a pagination parameter. Anchors like `#next` and links
+/// that only toggle sorting or filters don't count.
+fn advances_page(current_url: &str, next_url: &str) -> bool {
+    let (Ok(current), Ok(next)) = (Url::parse(current_url), Url::parse(next_url)) else {
+        return true;
+    };
+
+    if current.host() != next.host() || current.path() != next.path() {
+        return true;
+    }
+
+    let page_value = |url: &Url, param: &str| {
+        url.query_pairs().find(|(key, _)| key == param).map(|(_, value)| value.into_owned())
+    };
+    PAGE_PARAMS.iter().any(|param| page_value(&current, param) != page_value(&next, param))
+}
+
+/// Items announced and collected over one pagination chain (or a lone page),
+/// when a page announced the listing size and the chain's items could be counted
+fn chain_items(results: &[ScrapingResult]) -> Option<(usize, usize)> {
+    let expected = results.iter().filter_map(|r| r.content.total_items).max()?;
+    let counts: Vec<usize> = results.iter().filter_map(|r| r.item_count).collect();
+    if counts.is_empty() {
+        return None;
+    }
+    Some((expected, counts.iter().sum()))
+}
+
+/// Run `task` to completion, or give up at `deadline`
+async fn before_deadline<T>(deadline: Option<tokio::time::Instant>, task: impl std::future::Future<Output = T>) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, task).await.ok(),
+        None => Some(task.await),
+    }
+}
+
+/// `application/json` or a `+json` type such as `application/ld+json`
+fn is_json_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    mime == "application/json" || mime.ends_with("+json")
+}
+
+/// Score extracted content from 0 to 1; low scores flag pages that likely need
+/// a custom selector or JavaScript rendering
+pub fn quality_score(content: &DetectedContent) -> f64 {
+    let word_count: usize = content.content.iter()
+        .map(|block| block.split_whitespace().count())
+        .sum();
+    let link_words: usize = content.links.iter()
+        .map(|link| link.text.split_whitespace().count())
+        .sum();
+
+    let mut score = 0.0;
+
+    // Favor substantial text
+    score += (word_count.min(500) as f64 / 500.0) * 0.4;
+
+    // A detected title is a good sign the page parsed sensibly
+    if content.title.is_some() {
+        score += 0.2;
+    }
+
+    // Favor content text over link text
+    if word_count > 0 {
+        score += (word_count as f64 / (word_count + link_words) as f64) * 0.2;
+    }
+
+    // A long block means main content was found, not just scattered snippets
+    if content.content.iter().any(|block| block.split_whitespace().count() >= 50) {
+        score += 0.2;
+    }
+
+    score.clamp(0.0, 1.0)
+}
+
+use std::time::{Duration, Instant};
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{MockResponse, MockServer};
 
     #[test]
     fn test_scraping_config_default() {
@@ -238,6 +1164,111 @@ mod tests {
         assert!(!config.enable_pagination);
     }
 
+    #[test]
+    fn test_content_diff_reports_added_blocks_and_title_change() {
+        let result = |title: &str, blocks: &[&str]| ScrapingResult {
+            url: "https://news.example/story".to_string(),
+            timestamp: String::new(),
+            status: "success".to_string(),
+            content: DetectedContent {
+                title: Some(title.to_string()),
+                content: blocks.iter().map(|b| b.to_string()).collect(),
+                ..DetectedContent::default()
+            },
+            page_number: 1,
+            quality_score: 0.0,
+            headers: HashMap::new(),
+            final_url: None,
+            fields: HashMap::new(),
+            timings: ExtractionTimings::default(),
+            item_count: None,
+            raw_html: None,
+            json: None,
+        };
+
+        let before = result("Storm warning", &["First paragraph.", "Second paragraph."]);
+        let after = result("Storm warning (updated)", &["First paragraph.", "Update: roads closed.", "Second paragraph."]);
+
+        let diff = after.content_diff(&before);
+        assert_eq!(diff.added, vec!["Update: roads closed.".to_string()]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            diff.title_changed,
+            Some((Some("Storm warning".to_string()), Some("Storm warning (updated)".to_string())))
+        );
+
+        assert!(after.content_diff(&after).is_empty());
+        let reverted = before.content_diff(&after);
+        assert_eq!(reverted.removed, vec!["Update: roads closed.".to_string()]);
+    }
+
+    #[test]
+    fn test_next_page_skips_same_page_anchors() {
+        let scraper = WebScraper::new(&ScrapingConfig::default(), false).unwrap();
+        let link = |text: &str, href: &str| LinkData {
+            text: text.to_string(),
+            href: href.to_string(),
+            is_external: false,
+        };
+        let current = "https://shop.example/list?page=1";
+
+        let links = vec![
+            link("Next", "https://shop.example/list?page=1#next"),
+            link("Next »", "https://shop.example/list?page=1&sort=new"),
+            link("2", "https://shop.example/list?page=2"),
+        ];
+        assert_eq!(
+            scraper.find_next_page(&links, current, &HashSet::new()).as_deref(),
+            Some("https://shop.example/list?page=2")
+        );
+
+        let only_anchor = vec![link("Next", "https://shop.example/list?page=1#next")];
+        assert!(scraper.find_next_page(&only_anchor, current, &HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_page_form_synthesizes_next_page() {
+        let scraper = WebScraper::new(&ScrapingConfig::default(), false).unwrap();
+        let form = |method: &str, page: usize| format!(
+            r#"<html><body><form action="/list?ignored=1" method="{}">
+                <input type="hidden" name="q" value="shoes">
+                <input type="number" name="page" value="{}" min="1" max="10">
+                <button type="submit">Go</button>
+            </form></body></html>"#,
+            method, page
+        );
+        let current = "https://shop.example/list?q=shoes&page=2";
+
+        assert_eq!(
+            scraper.find_page_form(&form("get", 2), current, &HashSet::new()).as_deref(),
+            Some("https://shop.example/list?q=shoes&page=3")
+        );
+        // Last page, and POST forms
+        assert!(scraper.find_page_form(&form("get", 10), current, &HashSet::new()).is_none());
+        assert!(scraper.find_page_form(&form("post", 2), current, &HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_page_form_ignores_unlabelled_number_inputs() {
+        let scraper = WebScraper::new(&ScrapingConfig::default(), false).unwrap();
+        let current = "https://shop.example/list?min_price=1";
+
+        let filter = r#"<html><body><form action="/list" method="get">
+            <label for="min">Min price</label><input type="number" id="min" name="min_price" value="1">
+            <label>Quantity <input type="number" name="qty" value="1"></label>
+            <button type="submit">Filter</button>
+        </form></body></html>"#;
+        assert!(scraper.find_page_form(filter, current, &HashSet::new()).is_none());
+
+        let jump = r#"<html><body><form action="/list" method="get">
+            <label for="n">Go to page</label><input type="number" id="n" name="n" value="4">
+        </form></body></html>"#;
+        assert_eq!(
+            scraper.find_page_form(jump, current, &HashSet::new()).as_deref(),
+            Some("https://shop.example/list?n=5")
+        );
+    }
+
     #[test]
     fn test_scraping_config_from_json() {
         let json = r#"{
@@ -251,4 +1282,643 @@ mod tests {
         assert!(config.enable_pagination);
         assert_eq!(config.max_pages, 5);
     }
+
+    #[test]
+    fn test_prefer_amp_targets_amp_url() {
+        let html = r#"<html><head><link rel="amphtml" href="https://example.com/post/amp"></head></html>"#;
+        let detector = SelectorDetector::new();
+        let content = detector.detect(html, "https://example.com/post");
+
+        let config = ScrapingConfig { prefer_amp: true, ..ScrapingConfig::default() };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        assert_eq!(
+            scraper.amp_target(&content, "https://example.com/post").as_deref(),
+            Some("https://example.com/post/amp")
+        );
+
+        let scraper = WebScraper::new(&ScrapingConfig::default(), false).unwrap();
+        assert!(scraper.amp_target(&content, "https://example.com/post").is_none());
+    }
+
+    #[test]
+    fn test_quality_score_rich_vs_nav_only() {
+        let paragraph = "This paragraph carries real article content with plenty of words. ".repeat(10);
+        let rich_html = format!(
+            "<html><body><h1>Deep Dive</h1><article><p>{0}</p><p>{0}</p></article></body></html>",
+            paragraph
+        );
+        let nav_html = r#"
+            <html><body>
+                <nav><a href="/">Home</a><a href="/about">About</a><a href="/contact">Contact</a></nav>
+            </body></html>
+        "#;
+
+        let detector = SelectorDetector::new();
+        let rich = quality_score(&detector.detect(&rich_html, "https://example.com"));
+        let nav_only = quality_score(&detector.detect(nav_html, "https://example.com"));
+
+        assert!(rich > 0.8, "rich page scored {}", rich);
+        assert!(nav_only < 0.2, "nav-only page scored {}", nav_only);
+    }
+
+    #[tokio::test]
+    async fn test_resume_pagination_from_cursor() {
+        let server = MockServer::start(|request| {
+            let page: usize = request.path.rsplit('=').next().and_then(|p| p.parse().ok()).unwrap_or(1);
+            let next = if page < 3 {
+                format!(r#"<a href="/list?page={}">Next</a>"#, page + 1)
+            } else {
+                String::new()
+            };
+            MockResponse::html(&format!("<html><body><h1>Page {}</h1>{}</body></html>", page, next))
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/list?page=1")],
+            enable_pagination: true,
+            max_pages: 2,
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+
+        let first = scraper.scrape(config.clone()).await.unwrap();
+        assert_eq!(first.results.len(), 2);
+        assert_eq!(first.cursor, Some(server.url("/list?page=3")));
+
+        let resumed = scraper.scrape(ScrapingConfig {
+            resume_cursor: first.cursor.clone(),
+            ..config
+        }).await.unwrap();
+        assert_eq!(resumed.results.len(), 1);
+        assert_eq!(resumed.results[0].url, server.url("/list?page=3"));
+        assert_eq!(resumed.results[0].content.title.as_deref(), Some("Page 3"));
+        assert!(resumed.cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_quick_extract() {
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h2>First</h2><p>Body</p><h2> Second\n heading </h2></body></html>")
+        }).await;
+
+        let headings = WebScraper::quick_extract(&server.url("/"), "h2").await.unwrap();
+        assert_eq!(headings, vec!["First".to_string(), "Second heading".to_string()]);
+
+        let error = WebScraper::quick_extract(&server.url("/"), "h2[").await.unwrap_err();
+        assert!(error.to_string().contains("Invalid selector"));
+    }
+
+    #[tokio::test]
+    async fn test_harvest_links_keeps_only_matching_hrefs() {
+        let server = MockServer::start(|_| {
+            MockResponse::html(r#"<html><body>
+                <a href="/docs/report.pdf">Annual report</a>
+                <a href="/about">About us</a>
+                <a href="https://cdn.example/brochure.PDF?v=2">Brochure</a>
+                <a href="/files/manual.pdf">Manual</a>
+            </body></html>"#)
+        }).await;
+
+        let links = WebScraper::harvest_links(&server.url("/"), r"(?i)\.pdf(\?|$)").await.unwrap();
+        let hrefs: Vec<&str> = links.iter().map(|link| link.href.as_str()).collect();
+        assert_eq!(hrefs, vec![
+            server.url("/docs/report.pdf").as_str(),
+            "https://cdn.example/brochure.PDF?v=2",
+            server.url("/files/manual.pdf").as_str(),
+        ]);
+
+        let error = WebScraper::harvest_links(&server.url("/"), "(").await.unwrap_err();
+        assert!(error.to_string().contains("Invalid link pattern"));
+    }
+
+    #[tokio::test]
+    async fn test_force_encoding_overrides_declared_charset() {
+        let (body, _, _) = encoding_rs::SHIFT_JIS.encode("<html><body><h1>日本語のページ</h1></body></html>");
+        let body = body.into_owned();
+        let server = MockServer::start(move |_| {
+            MockResponse::status(200)
+                .with_header("Content-Type", "text/html; charset=iso-8859-1")
+                .with_body(&body)
+        }).await;
+
+        let declared = WebScraper::new(&ScrapingConfig::default(), false).unwrap();
+        assert!(!declared.fetch_page(&server.url("/")).await.unwrap().contains("日本語"));
+
+        let config = ScrapingConfig {
+            force_encoding: Some("Shift_JIS".to_string()),
+            ..ScrapingConfig::default()
+        };
+        let forced = WebScraper::new(&config, false).unwrap();
+        assert!(forced.fetch_page(&server.url("/")).await.unwrap().contains("<h1>日本語のページ</h1>"));
+
+        let unknown = ScrapingConfig {
+            force_encoding: Some("klingon".to_string()),
+            ..ScrapingConfig::default()
+        };
+        assert!(WebScraper::new(&unknown, false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_field_selectors_fill_named_fields() {
+        let server = MockServer::start(|_| {
+            MockResponse::html(r#"
+                <html><head><meta itemprop="sku" content="SKU-42"></head><body>
+                    <h1>Kettle</h1>
+                    <span class="price">$19.99</span>
+                    <ul><li class="tag">kitchen</li><li class="tag">steel</li></ul>
+                </body></html>
+            "#)
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/kettle")],
+            rate_limit: 100.0,
+            field_selectors: Some(HashMap::from([
+                ("price".to_string(), ".price".to_string()),
+                ("tags".to_string(), ".tag".to_string()),
+                ("sku".to_string(), "meta[itemprop='sku']".to_string()),
+            ])),
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        let fields = &session.results[0].fields;
+        assert_eq!(fields["price"], vec!["$19.99".to_string()]);
+        assert_eq!(fields["tags"], vec!["kitchen".to_string(), "steel".to_string()]);
+        assert_eq!(fields["sku"], vec!["SKU-42".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_discover_pages_lists_all_pages_in_order() {
+        let server = MockServer::start(|request| {
+            let page: usize = request.path.rsplit('=').next().and_then(|p| p.parse().ok()).unwrap_or(1);
+            let next = if page < 3 {
+                format!(r#"<a href="/list?page={}">Next</a>"#, page + 1)
+            } else {
+                String::new()
+            };
+            MockResponse::html(&format!("<html><body><h1>Page {}</h1>{}</body></html>", page, next))
+        }).await;
+
+        let config = ScrapingConfig {
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let pages = scraper.discover_pages(&server.url("/list?page=1")).await.unwrap();
+
+        assert_eq!(pages, vec![
+            server.url("/list?page=1"),
+            server.url("/list?page=2"),
+            server.url("/list?page=3"),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_selector_ends_pagination() {
+        let server = MockServer::start(|request| {
+            let page: usize = request.path.rsplit('=').next().and_then(|p| p.parse().ok()).unwrap_or(1);
+            let marker = if page == 3 { r#"<div class="empty-results">No more results</div>"# } else { "" };
+            // The next link persists even on the last real page
+            MockResponse::html(&format!(
+                r#"<html><body><h1>Page {}</h1>{}<a href="/list?page={}">Next</a></body></html>"#,
+                page, marker, page + 1
+            ))
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/list?page=1")],
+            enable_pagination: true,
+            max_pages: 10,
+            rate_limit: 100.0,
+            stop_selector: Some(".empty-results".to_string()),
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert_eq!(session.results.len(), 3);
+        assert_eq!(session.results[2].url, server.url("/list?page=3"));
+        assert_eq!(server.hits("/list?page=4"), 0);
+        assert!(session.cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pagination_loop_through_tracking_params_terminates() {
+        let server = MockServer::start(|request| {
+            let page: usize = request.path.split(['?', '&'])
+                .find_map(|pair| pair.strip_prefix("page="))
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(1);
+            // Every next link gets fresh tracking params; the last page links back to the first
+            let next = if page == 3 { 1 } else { page + 1 };
+            MockResponse::html(&format!(
+                r#"<html><body><h1>Page {}</h1><a href="/list?utm_source=pager&utm_content={}&page={}">Next</a></body></html>"#,
+                page, page, next
+            ))
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/list?page=1")],
+            enable_pagination: true,
+            max_pages: 10,
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert_eq!(session.results.len(), 3);
+        assert_eq!(server.hits("/list?page=1"), 1);
+        assert!(session.cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_load_more_button_is_followed() {
+        let server = MockServer::start(|request| {
+            if request.path == "/list" {
+                MockResponse::html(r#"<html><body><h1>Products</h1><ul><li>One</li></ul>
+                    <button class="load-more" data-url="/list/chunk?page=2">Load more</button></body></html>"#)
+            } else {
+                MockResponse::html("<html><body><ul><li>Two</li></ul></body></html>")
+            }
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/list")],
+            enable_pagination: true,
+            max_pages: 5,
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert_eq!(session.results.len(), 2);
+        assert_eq!(session.results[1].url, server.url("/list/chunk?page=2"));
+        assert_eq!(server.hits("/list/chunk?page=2"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_interceptor_rewrites_url() {
+        struct CacheBuster;
+
+        impl RequestInterceptor for CacheBuster {
+            fn intercept(&self, request: &mut reqwest::Request) -> anyhow::Result<()> {
+                request.url_mut().query_pairs_mut().append_pair("cb", "42");
+                request.headers_mut().insert("X-Signature", "signed".parse()?);
+                Ok(())
+            }
+        }
+
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Fresh</h1></body></html>")
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/page")],
+            rate_limit: 100.0,
+            request_interceptor: Some(Arc::new(CacheBuster)),
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert_eq!(session.results.len(), 1);
+        assert_eq!(server.hits("/page?cb=42"), 1);
+        assert_eq!(server.requests()[0].header("X-Signature"), Some("signed"));
+    }
+
+    #[tokio::test]
+    async fn test_extraction_timings_are_recorded() {
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Timed</h1><p>Some content to parse and detect.</p></body></html>")
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/")],
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        let timings = &session.results[0].timings;
+        assert!(timings.fetch_ms > 0.0);
+        assert!(timings.parse_ms >= 0.0 && timings.parse_ms.is_finite());
+        assert!(timings.detect_ms >= 0.0 && timings.detect_ms.is_finite());
+    }
+
+    #[tokio::test]
+    async fn test_capture_headers() {
+        let server = MockServer::start(|_| {
+            MockResponse::html("<html><body><h1>Guarded</h1></body></html>")
+                .with_header("X-Bot-Check", "passed")
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/")],
+            rate_limit: 100.0,
+            capture_headers: true,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        let headers = &session.results[0].headers;
+        assert_eq!(headers.get("x-bot-check").map(String::as_str), Some("passed"));
+        assert!(headers.get("content-type").is_some_and(|ct| ct.starts_with("text/html")));
+
+        // Off by default
+        let config = ScrapingConfig {
+            urls: vec![server.url("/")],
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+        assert!(session.results[0].headers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_items_shortfall_flags_incomplete_listing() {
+        let server = MockServer::start(|_| {
+            let cards: String = (1..=3)
+                .map(|i| format!(r#"<div class="product"><h3>Item {}</h3></div>"#, i))
+                .collect();
+            MockResponse::html(&format!(
+                "<html><body><h1>Shoes</h1><p>Showing 1-3 of 250 results</p>{}</body></html>",
+                cards
+            ))
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/shoes")],
+            rate_limit: 100.0,
+            item_selector: Some(".product".to_string()),
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert_eq!(session.results[0].item_count, Some(3));
+        assert_eq!(session.total_items_expected, Some(250));
+        assert_eq!(session.items_collected, 3);
+        assert_eq!(session.items_shortfall, Some(247));
+    }
+
+    #[tokio::test]
+    async fn test_items_shortfall_is_scoped_per_listing() {
+        let server = MockServer::start(|request| {
+            let (total, shown) = if request.path == "/shoes" { (10, 3) } else { (5, 5) };
+            let cards: String = (1..=shown)
+                .map(|i| format!(r#"<div class="product"><h3>Item {}</h3></div>"#, i))
+                .collect();
+            MockResponse::html(&format!(
+                "<html><body><p>Showing 1-{} of {} products</p><p>2 items in your cart</p>{}</body></html>",
+                shown, total, cards
+            ))
+        }).await;
+
+        let config_for = |item_selector: Option<&str>| ScrapingConfig {
+            urls: vec![server.url("/shoes"), server.url("/hats")],
+            rate_limit: 100.0,
+            item_selector: item_selector.map(str::to_string),
+            ..ScrapingConfig::default()
+        };
+
+        // 3 of 10 shoes and 5 of 5 hats: the hats don't hide the missing shoes
+        let config = config_for(Some(".product"));
+        let session = WebScraper::new(&config, false).unwrap().scrape(config).await.unwrap();
+        assert_eq!(session.total_items_expected, Some(15));
+        assert_eq!(session.items_collected, 8);
+        assert_eq!(session.items_shortfall, Some(7));
+
+        // Nothing to count items with: no shortfall claimed
+        let config = config_for(None);
+        let session = WebScraper::new(&config, false).unwrap().scrape(config).await.unwrap();
+        assert_eq!(session.results[0].item_count, None);
+        assert_eq!(session.total_items_expected, None);
+        assert_eq!(session.items_shortfall, None);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_scrape_reports_in_input_order() {
+        let server = MockServer::start(|request| {
+            // Earlier URLs answer slower, so they complete last
+            let index: u64 = request.path.rsplit('/').next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            let delay = Duration::from_millis(20 * (6 - index));
+            let response = if request.path.starts_with("/fail") {
+                MockResponse::status(500)
+            } else {
+                MockResponse::html(&format!("<html><body><h1>Page {}</h1></body></html>", index))
+            };
+            response.with_delay(delay)
+        }).await;
+
+        let urls: Vec<String> = (0..6)
+            .map(|i| server.url(&format!("/{}/{}", if i % 3 == 1 { "fail" } else { "ok" }, i)))
+            .collect();
+        let config = ScrapingConfig {
+            urls: urls.clone(),
+            rate_limit: 1000.0,
+            concurrency: 4,
+            ..ScrapingConfig::default()
+        };
+
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let scraper = WebScraper::new(&config, false).unwrap();
+            let session = scraper.scrape(config.clone()).await.unwrap();
+            let urls: Vec<String> = session.results.iter().map(|r| r.url.clone()).collect();
+            runs.push((urls, session.errors));
+        }
+
+        let expected_ok: Vec<String> = urls.iter().filter(|u| u.contains("/ok/")).cloned().collect();
+        assert_eq!(runs[0].0, expected_ok);
+        assert_eq!(runs[0].1.len(), 2);
+        assert!(runs[0].1[0].contains("/fail/1") && runs[0].1[1].contains("/fail/4"));
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[tokio::test]
+    async fn test_store_raw_html() {
+        let html = "<html><body><h1>Mismatch</h1><p>Exactly as served.</p></body></html>";
+        let server = MockServer::start(move |_| MockResponse::html(html)).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/")],
+            rate_limit: 100.0,
+            store_raw_html: true,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+        assert_eq!(session.results[0].raw_html.as_deref(), Some(html));
+
+        // Off by default
+        let config = ScrapingConfig {
+            urls: vec![server.url("/")],
+            rate_limit: 100.0,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+        assert!(session.results[0].raw_html.is_none());
+        assert!(!serde_json::to_string(&session.results[0]).unwrap().contains("raw_html"));
+    }
+
+    #[tokio::test]
+    async fn test_amp_fetch_is_rate_limited() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/post/amp" => MockResponse::html("<html><body><h1>Light</h1></body></html>"),
+            _ => MockResponse::html(r#"<html><head><link rel="amphtml" href="/post/amp"></head><body><h1>Full</h1></body></html>"#),
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/post")],
+            rate_limit: 5.0,
+            prefer_amp: true,
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        scraper.scrape(config).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].path, "/post/amp");
+        assert!(requests[1].received - requests[0].received >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_field_selectors_read_the_amp_copy() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/item/amp" => MockResponse::html(r#"<html><body><span class="price">$12.00</span></body></html>"#),
+            _ => MockResponse::html(r#"<html><head><link rel="amphtml" href="/item/amp"></head>
+                <body><span class="price">$15.00</span></body></html>"#),
+        }).await;
+
+        let config = ScrapingConfig {
+            urls: vec![server.url("/item")],
+            rate_limit: 100.0,
+            prefer_amp: true,
+            field_selectors: Some(HashMap::from([("price".to_string(), ".price".to_string())])),
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert_eq!(session.results[0].fields["price"], vec!["$12.00".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_shared_host_limiter_adds_to_own_rate_limit() {
+        let server = MockServer::start(|_| MockResponse::html("<html><body><h1>Paced</h1></body></html>")).await;
+
+        // A fast shared budget doesn't speed up a scraper that asked for 5 rps
+        let shared = Arc::new(DomainRateLimiter::new(100.0));
+        let scraper_for = |urls: Vec<String>, rate_limit: f64, shared: &Arc<DomainRateLimiter>| {
+            let config = ScrapingConfig {
+                urls,
+                rate_limit,
+                domain_rate_limiter: Some(shared.clone()),
+                ..ScrapingConfig::default()
+            };
+            (WebScraper::new(&config, false).unwrap(), config)
+        };
+        let (slow, slow_config) = scraper_for(vec![server.url("/a"), server.url("/b")], 5.0, &shared);
+        let (fast, fast_config) = scraper_for(vec![server.url("/c")], 100.0, &shared);
+
+        let start = Instant::now();
+        let (slow_session, fast_session) = tokio::join!(slow.scrape(slow_config), fast.scrape(fast_config));
+        assert_eq!(slow_session.unwrap().results.len(), 2);
+        assert_eq!(fast_session.unwrap().results.len(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(380));
+
+        // A slow shared budget still spaces two fast scrapers hitting one host
+        let shared = Arc::new(DomainRateLimiter::new(5.0));
+        let (first, first_config) = scraper_for(vec![server.url("/d")], 100.0, &shared);
+        let (second, second_config) = scraper_for(vec![server.url("/e")], 100.0, &shared);
+
+        let start = Instant::now();
+        let (first_session, second_session) = tokio::join!(first.scrape(first_config), second.scrape(second_config));
+        assert!(first_session.is_ok() && second_session.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(180));
+    }
+
+    #[tokio::test]
+    async fn test_unrepresentable_max_duration_is_rejected() {
+        let server = MockServer::start(|_| MockResponse::html("<html><body><h1>Ok</h1></body></html>")).await;
+        let config_with = |max_duration_secs| ScrapingConfig {
+            urls: vec![server.url("/")],
+            rate_limit: 100.0,
+            max_duration_secs: Some(max_duration_secs),
+            ..ScrapingConfig::default()
+        };
+
+        for secs in [f64::INFINITY, f64::NAN, 1e20, -1.0] {
+            let config = config_with(secs);
+            let scraper = WebScraper::new(&config, false).unwrap();
+            assert!(matches!(scraper.scrape(config).await, Err(ScraperError::Config(_))), "{}", secs);
+        }
+
+        // Representable but beyond the clock: no deadline at all
+        let config = config_with(1e18);
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+        assert!(!session.timed_out);
+        assert_eq!(session.results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cache_serves_repeat_fetches() {
+        let server = MockServer::start(|_| MockResponse::html("<html><body><h1>Cached</h1></body></html>")).await;
+
+        let config = ScrapingConfig {
+            fetch_cache: Some(Arc::new(FetchCache::default())),
+            ..ScrapingConfig::default()
+        };
+        let scraper = WebScraper::new(&config, false).unwrap();
+
+        let first = scraper.fetch_page(&server.url("/")).await.unwrap();
+        let second = scraper.fetch_page(&server.url("/")).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(server.hits("/"), 1);
+    }
+
+    struct MockRenderer;
+
+    impl RenderBackend for MockRenderer {
+        fn render<'a>(&'a self, _url: &'a str) -> futures::future::BoxFuture<'a, anyhow::Result<String>> {
+            Box::pin(async move {
+                let html: anyhow::Result<String> = Ok(r#"
+                    <html><body>
+                        <h1>Rendered Title</h1>
+                        <p>Content that only exists after JavaScript runs.</p>
+                    </body></html>
+                "#.to_string());
+                html
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_backend_replaces_fetch() {
+        let config = ScrapingConfig {
+            urls: vec!["https://spa.example.com".to_string()],
+            rate_limit: 100.0,
+            render_backend: Some(Arc::new(MockRenderer)),
+            ..ScrapingConfig::default()
+        };
+
+        let scraper = WebScraper::new(&config, false).unwrap();
+        let session = scraper.scrape(config).await.unwrap();
+
+        assert!(session.errors.is_empty());
+        assert_eq!(session.results[0].content.title.as_deref(), Some("Rendered Title"));
+        assert!(session.results[0].content.content.iter().any(|c| c.contains("JavaScript runs")));
+    }
 }
@@ -1,6 +1,13 @@
 use scraper::{Html, Selector, ElementRef};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::error::Result;
+use crate::auto_selectors::{hides_subtree, is_hidden, visible_text};
+use crate::json_ld;
+use crate::utils::{cached_selector, truncate_chars};
 
 /// Structural analysis of HTML pages with intelligent scoring
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +17,18 @@ pub struct StructureAnalysis {
     pub sections: Vec<Section>,
     pub recommendations: Recommendations,
     pub debug_info: Option<DebugInfo>,
+    /// Total items declared by a JSON-LD `ItemList` (`numberOfItems`, else its element count)
+    #[serde(default)]
+    pub item_count: Option<usize>,
+    /// URLs of the `ItemList` entries, in list order
+    #[serde(default)]
+    pub item_urls: Vec<String>,
+    /// JSON-LD `publisher.name`, else `author.name`
+    #[serde(default)]
+    pub publisher: Option<String>,
+    /// The page exceeded `max_html_bytes`; only its beginning was analyzed
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +68,9 @@ pub struct SectionStats {
     pub density_score: f64,
     pub link_density: f64,
     pub element_count: usize,
+    /// Separate places on the page carrying this exact text (1 = unique)
+    #[serde(default)]
+    pub repeat_count: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,7 +82,7 @@ pub struct Recommendations {
     pub confidence_level: ConfidenceLevel,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ExtractionMode {
     Article,
@@ -71,6 +93,59 @@ pub enum ExtractionMode {
     Generic,
 }
 
+impl ExtractionMode {
+    pub const ALL: [ExtractionMode; 6] = [
+        ExtractionMode::Article,
+        ExtractionMode::Product,
+        ExtractionMode::Forum,
+        ExtractionMode::ListPage,
+        ExtractionMode::Documentation,
+        ExtractionMode::Generic,
+    ];
+
+    /// The snake_case name, matching the serde representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExtractionMode::Article => "article",
+            ExtractionMode::Product => "product",
+            ExtractionMode::Forum => "forum",
+            ExtractionMode::ListPage => "list_page",
+            ExtractionMode::Documentation => "documentation",
+            ExtractionMode::Generic => "generic",
+        }
+    }
+}
+
+impl std::fmt::Display for ExtractionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseExtractionModeError(pub String);
+
+impl std::fmt::Display for ParseExtractionModeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown extraction mode: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseExtractionModeError {}
+
+impl std::str::FromStr for ExtractionMode {
+    type Err = ParseExtractionModeError;
+
+    /// Accepts the snake_case name, and the `Debug` form ("ListPage") older
+    /// profiles were stored with
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let key = s.trim().replace('_', "").to_lowercase();
+        Self::ALL.into_iter()
+            .find(|mode| mode.as_str().replace('_', "") == key)
+            .ok_or_else(|| ParseExtractionModeError(s.to_string()))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ConfidenceLevel {
@@ -97,14 +172,70 @@ pub struct ScoringDetail {
     pub final_score: f64,
 }
 
+/// On-page SEO metrics of one page, from `StructureAnalyzer::audit`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeoAudit {
+    pub url: String,
+    /// Characters in `<title>` (0 when missing)
+    pub title_length: usize,
+    /// Characters in `<meta name="description">` (0 when missing)
+    pub meta_description_length: usize,
+    pub h1_count: usize,
+    /// Words of visible text in the body
+    pub word_count: usize,
+    pub image_count: usize,
+    pub images_with_alt: usize,
+    /// Fraction of images with non-empty alt text (1.0 without images)
+    pub image_alt_coverage: f64,
+    pub internal_links: usize,
+    pub external_links: usize,
+    /// Fraction of links pointing at the page's own host (0.0 without links)
+    pub internal_link_ratio: f64,
+    /// Problems found: "missing_title", "missing_meta_description", "missing_h1",
+    /// "multiple_h1" and "images_missing_alt"
+    pub issues: Vec<String>,
+}
+
+/// Relative weights of the signals behind main-content/article scores.
+/// Scores are normalized by the weight total, so only the ratios matter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringWeights {
+    /// Text per element
+    pub text_density: f64,
+    /// Reward for few links relative to text
+    pub link_density: f64,
+    pub paragraphs: f64,
+    pub text_length: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            text_density: 0.3,
+            link_density: 0.3,
+            paragraphs: 0.2,
+            text_length: 0.2,
+        }
+    }
+}
+
 pub struct StructureAnalyzer {
     min_content_length: usize,
     min_word_count: usize,
     detect_comments: bool,
     detect_metadata: bool,
     debug_mode: bool,
+    include_empty_sections: bool,
+    max_html_bytes: Option<usize>,
+    preview_length: usize,
+    min_confidence: f64,
+    weights: ScoringWeights,
 }
 
+/// Pages beyond this are cut before parsing unless `with_max_html_bytes` says otherwise
+pub const DEFAULT_MAX_HTML_BYTES: usize = 5 * 1024 * 1024;
+
 impl Default for StructureAnalyzer {
     fn default() -> Self {
         Self {
@@ -113,6 +244,11 @@ impl Default for StructureAnalyzer {
             detect_comments: true,
             detect_metadata: true,
             debug_mode: false,
+            include_empty_sections: false,
+            max_html_bytes: Some(DEFAULT_MAX_HTML_BYTES),
+            preview_length: 200,
+            min_confidence: 0.0,
+            weights: ScoringWeights::default(),
         }
     }
 }
@@ -133,19 +269,72 @@ impl StructureAnalyzer {
             detect_comments,
             detect_metadata: true,
             debug_mode,
+            include_empty_sections: false,
+            max_html_bytes: Some(DEFAULT_MAX_HTML_BYTES),
+            preview_length: 200,
+            min_confidence: 0.0,
+            weights: ScoringWeights::default(),
         }
     }
 
+    pub fn with_weights(mut self, weights: ScoringWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Keep structural sections below `min_content_length` (with their low
+    /// scores) instead of dropping them; useful when debugging a layout
+    pub fn with_empty_sections(mut self, include: bool) -> Self {
+        self.include_empty_sections = include;
+        self
+    }
+
+    /// Only analyze the first `max` bytes of a page (`None` for no limit), so
+    /// bloated pages can't stall the analysis. Cut pages are flagged `truncated`.
+    pub fn with_max_html_bytes(mut self, max: Option<usize>) -> Self {
+        self.max_html_bytes = max;
+        self
+    }
+
+    /// Don't recommend a main content section whose confidence is below `min`
+    /// (0–1); a page with only such sections gets `ConfidenceLevel::VeryLow`.
+    /// 0, the default, accepts any match.
+    pub fn with_min_confidence(mut self, min: f64) -> Self {
+        self.min_confidence = min;
+        self
+    }
+
+    /// Characters of section text kept in `Section::preview` (200 by default)
+    pub fn with_preview_length(mut self, length: usize) -> Self {
+        self.preview_length = length;
+        self
+    }
+
     /// Analyze HTML structure and return scored sections
     pub fn analyze(&self, html: &str, url: &str) -> StructureAnalysis {
         let start_time = std::time::Instant::now();
+
+        let mut truncated = false;
+        let mut html = html;
+        if let Some(max) = self.max_html_bytes.filter(|max| html.len() > *max) {
+            log::warn!("{} is {} bytes, analyzing only the first {}", url, html.len(), max);
+            let mut end = max;
+            while !html.is_char_boundary(end) {
+                end -= 1;
+            }
+            html = &html[..end];
+            truncated = true;
+        }
+
         let document = Html::parse_document(html);
 
         // Find all potential content sections
         let sections = self.find_sections(&document);
 
         // Generate recommendations
-        let recommendations = self.generate_recommendations(&sections);
+        let (item_count, item_urls) = item_list(&document);
+        let mut recommendations = self.generate_recommendations(&sections);
+        prefer_list_mode(&mut recommendations, item_count);
 
         // Build debug info if enabled
         let debug_info = if self.debug_mode {
@@ -165,6 +354,172 @@ impl StructureAnalyzer {
             sections,
             recommendations,
             debug_info,
+            item_count,
+            item_urls,
+            publisher: publisher(&document),
+            truncated,
+        }
+    }
+
+    /// SEO metrics of a page: title and description lengths, H1s, image alt
+    /// coverage and the internal/external link split
+    pub fn audit(&self, html: &str, url: &str) -> SeoAudit {
+        let document = Html::parse_document(html);
+        let select = |selector: &str| -> Vec<ElementRef> {
+            cached_selector(selector).map_or_else(Vec::new, |s| document.select(&s).collect())
+        };
+
+        let title_length = select("title").first()
+            .map_or(0, |title| title.text().collect::<String>().trim().chars().count());
+        let meta_description_length = select("meta[name='description'][content]").first()
+            .and_then(|meta| meta.value().attr("content"))
+            .map_or(0, |content| content.trim().chars().count());
+        let h1_count = select("h1").len();
+        let word_count = select("body").first()
+            .map_or(0, |body| visible_text(*body).split_whitespace().count());
+
+        let images = select("img");
+        let images_with_alt = images.iter()
+            .filter(|img| img.value().attr("alt").is_some_and(|alt| !alt.trim().is_empty()))
+            .count();
+
+        let base = url::Url::parse(url).ok();
+        let (mut internal_links, mut external_links) = (0, 0);
+        for href in select("a[href]").iter().filter_map(|a| a.value().attr("href")) {
+            let Some(target) = base.as_ref().and_then(|base| base.join(href.trim()).ok()) else {
+                continue;
+            };
+            // Only links to other pages count, not mailto:, javascript: or same-page anchors
+            if !matches!(target.scheme(), "http" | "https") || href.trim().starts_with('#') {
+                continue;
+            }
+            if base.as_ref().is_some_and(|base| base.host_str() == target.host_str()) {
+                internal_links += 1;
+            } else {
+                external_links += 1;
+            }
+        }
+
+        let mut issues = Vec::new();
+        if title_length == 0 {
+            issues.push("missing_title".to_string());
+        }
+        if meta_description_length == 0 {
+            issues.push("missing_meta_description".to_string());
+        }
+        match h1_count {
+            0 => issues.push("missing_h1".to_string()),
+            1 => {}
+            _ => issues.push("multiple_h1".to_string()),
+        }
+        if images_with_alt < images.len() {
+            issues.push("images_missing_alt".to_string());
+        }
+
+        let total_links = internal_links + external_links;
+        SeoAudit {
+            url: url.to_string(),
+            title_length,
+            meta_description_length,
+            h1_count,
+            word_count,
+            image_count: images.len(),
+            images_with_alt,
+            image_alt_coverage: if images.is_empty() { 1.0 } else { images_with_alt as f64 / images.len() as f64 },
+            internal_links,
+            external_links,
+            internal_link_ratio: if total_links == 0 { 0.0 } else { internal_links as f64 / total_links as f64 },
+            issues,
+        }
+    }
+
+    /// Analyze a saved HTML file; the analysis URL is the file's `file://` URL.
+    /// Gzipped files (`.gz`, or gzip magic bytes) are decompressed first.
+    pub fn analyze_file(&self, path: impl AsRef<Path>) -> Result<StructureAnalysis> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+
+        let gzipped = bytes.starts_with(&[0x1f, 0x8b])
+            || path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+        let html = if gzipped {
+            let mut html = String::new();
+            flate2::read::GzDecoder::new(bytes.as_slice()).read_to_string(&mut html)?;
+            html
+        } else {
+            String::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        };
+
+        let url = std::fs::canonicalize(path)
+            .ok()
+            .and_then(|p| url::Url::from_file_path(p).ok())
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| path.display().to_string());
+
+        Ok(self.analyze(&html, &url))
+    }
+
+    /// Analyze a stream of `{"url": ..., "html": ...}` NDJSON records, writing one
+    /// analysis per line. Records are handled one at a time so memory stays flat
+    /// however large the corpus; malformed lines are logged and skipped.
+    /// Returns the number of analyses written.
+    pub fn analyze_ndjson(&self, input: impl BufRead, mut output: impl Write) -> Result<usize> {
+        let mut written = 0;
+
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: HtmlRecord = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(e) => {
+                    log::warn!("Skipping NDJSON line {}: {}", index + 1, e);
+                    continue;
+                }
+            };
+
+            let analysis = self.analyze(&record.html, &record.url);
+            serde_json::to_writer(&mut output, &analysis)?;
+            output.write_all(b"\n")?;
+            written += 1;
+        }
+
+        output.flush()?;
+        Ok(written)
+    }
+
+    /// `analyze_ndjson` from one file into another
+    pub fn analyze_ndjson_file(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<usize> {
+        let (input, output) = (input.as_ref(), output.as_ref());
+        let reader = std::fs::File::open(input)?;
+        let writer = std::fs::File::create(output)?;
+
+        self.analyze_ndjson(BufReader::new(reader), BufWriter::new(writer))
+    }
+
+    /// Re-score a stored analysis with different weights, without refetching
+    /// or reparsing the page
+    pub fn rescore(&self, analysis: &StructureAnalysis, weights: ScoringWeights) -> StructureAnalysis {
+        let mut sections = analysis.sections.clone();
+        for section in &mut sections {
+            section.score = self.score_with(&section.stats, &section.section_type, &weights);
+        }
+        sort_by_score(&mut sections);
+
+        let mut recommendations = self.generate_recommendations(&sections);
+        prefer_list_mode(&mut recommendations, analysis.item_count);
+
+        StructureAnalysis {
+            url: analysis.url.clone(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            sections,
+            recommendations,
+            debug_info: analysis.debug_info.clone(),
+            item_count: analysis.item_count,
+            item_urls: analysis.item_urls.clone(),
+            publisher: analysis.publisher.clone(),
+            truncated: analysis.truncated,
         }
     }
 
@@ -198,12 +553,15 @@ impl StructureAnalyzer {
             (".comment-list", SectionType::Comments),
         ];
 
+        let repeats = self.repeated_blocks(document, &structural_selectors);
+
         for (selector_str, section_type) in structural_selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                for element in document.select(&selector) {
-                    if let Some(section) = self.analyze_element(element, selector_str, section_type.clone()) {
+            if let Some(selector) = cached_selector(selector_str) {
+                for element in document.select(&selector).filter(|e| !is_hidden(*e)) {
+                    if let Some(section) = self.analyze_element(element, selector_str, section_type.clone(), &repeats) {
                         // Only include sections with meaningful content
-                        if section.stats.text_length >= self.min_content_length
+                        if self.include_empty_sections
+                            || section.stats.text_length >= self.min_content_length
                             || matches!(section.section_type, SectionType::Header | SectionType::Footer | SectionType::Navigation) {
                             sections.push(section);
                         }
@@ -218,21 +576,54 @@ impl StructureAnalyzer {
         }
 
         // Sort by score (highest first)
-        sections.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        sort_by_score(&mut sections);
 
         // Remove duplicates (nested elements)
         self.deduplicate_sections(sections)
     }
 
-    fn analyze_element(&self, element: ElementRef, selector: &str, mut section_type: SectionType) -> Option<Section> {
-        let text: String = element.text().collect();
+    /// How many separate places on the page carry each structural block's text.
+    /// A block repeated verbatim (a menu in both header and footer) is boilerplate.
+    fn repeated_blocks(&self, document: &Html, selectors: &[(&str, SectionType)]) -> HashMap<String, usize> {
+        let mut blocks = HashMap::new();
+        for (selector_str, _) in selectors {
+            if let Some(selector) = cached_selector(selector_str) {
+                for element in document.select(&selector).filter(|e| !is_hidden(*e)) {
+                    blocks.entry(element.id()).or_insert_with(|| (element, block_key(element)));
+                }
+            }
+        }
+
+        let mut counts = HashMap::new();
+        for (element, text) in blocks.values() {
+            // A wrapper and the block it wraps are one place, not two
+            let nested = element.ancestors()
+                .any(|ancestor| blocks.get(&ancestor.id()).is_some_and(|(_, t)| t == text));
+
+            if !text.is_empty() && !nested {
+                *counts.entry(text.clone()).or_insert(0) += 1;
+            }
+        }
+
+        counts
+    }
+
+    fn analyze_element(
+        &self,
+        element: ElementRef,
+        selector: &str,
+        mut section_type: SectionType,
+        repeats: &HashMap<String, usize>,
+    ) -> Option<Section> {
+        let text = visible_text(element);
         let text = text.trim();
 
         if text.is_empty() {
             return None;
         }
 
-        let stats = self.calculate_stats(element);
+        let mut stats = self.calculate_stats(element);
+        stats.repeat_count = repeats.get(&block_key(element)).copied().unwrap_or(1);
 
         // Adjust section type based on stats
         if stats.text_length > 500 && stats.density_score > 0.7 {
@@ -245,13 +636,7 @@ impl StructureAnalyzer {
         let score = self.calculate_score(&stats, &section_type);
         let confidence = self.calculate_confidence(&stats, &section_type);
 
-        // Generate preview (first 200 chars, respecting UTF-8 boundaries)
-        let preview = if text.chars().count() > 200 {
-            let truncated: String = text.chars().take(200).collect();
-            format!("{}...", truncated)
-        } else {
-            text.to_string()
-        };
+        let preview = preview(text, self.preview_length);
 
         Some(Section {
             selector: selector.to_string(),
@@ -267,8 +652,8 @@ impl StructureAnalyzer {
     fn analyze_divs(&self, document: &Html) -> Vec<Section> {
         let mut sections = Vec::new();
 
-        if let Ok(selector) = Selector::parse("div") {
-            for element in document.select(&selector) {
+        if let Some(selector) = cached_selector("div") {
+            for element in document.select(&selector).filter(|e| !is_hidden(*e)) {
                 let stats = self.calculate_stats(element);
 
                 // Only consider divs with substantial content
@@ -279,15 +664,11 @@ impl StructureAnalyzer {
                     let score = self.calculate_score(&stats, &SectionType::MainContent);
 
                     if score > 0.5 {
-                        let text: String = element.text().collect();
-                        let preview = if text.len() > 200 {
-                            format!("{}...", &text[..200].trim())
-                        } else {
-                            text.trim().to_string()
-                        };
+                        let preview = preview(visible_text(element).trim(), self.preview_length);
 
-                        // Try to generate a unique selector
-                        let selector_str = self.generate_selector(element);
+                        // Cheap on purpose: a uniqueness-checked path would search the
+                        // whole document again for every qualifying div
+                        let selector_str = div_selector(element);
 
                         sections.push(Section {
                             selector: selector_str,
@@ -307,19 +688,12 @@ impl StructureAnalyzer {
     }
 
     fn calculate_stats(&self, element: ElementRef) -> SectionStats {
-        let text: String = element.text().collect();
+        let text = visible_text(element);
         let text_length = text.trim().len();
         let word_count = text.split_whitespace().count();
 
-        // Count elements
-        let link_count = self.count_descendants(element, "a");
-        let image_count = self.count_descendants(element, "img");
-        let paragraph_count = self.count_descendants(element, "p");
-        let heading_count = self.count_descendants(element, "h1")
-            + self.count_descendants(element, "h2")
-            + self.count_descendants(element, "h3");
-
-        let element_count = self.count_all_descendants(element);
+        let TagCounts { link_count, image_count, paragraph_count, heading_count, element_count } =
+            count_tags(element);
 
         // Calculate density score (text per element ratio)
         let density_score = if element_count > 0 {
@@ -345,25 +719,38 @@ impl StructureAnalyzer {
             density_score,
             link_density,
             element_count,
+            repeat_count: 1,
         }
     }
 
     fn calculate_score(&self, stats: &SectionStats, section_type: &SectionType) -> f64 {
+        self.score_with(stats, section_type, &self.weights)
+    }
+
+    fn score_with(&self, stats: &SectionStats, section_type: &SectionType, weights: &ScoringWeights) -> f64 {
         let mut score = 0.0;
 
         match section_type {
             SectionType::Article | SectionType::MainContent => {
                 // Favor high text density
-                score += stats.density_score * 0.3;
+                score += stats.density_score * weights.text_density;
 
                 // Favor low link density
-                score += (1.0 - stats.link_density.min(1.0)) * 0.3;
+                score += (1.0 - stats.link_density.min(1.0)) * weights.link_density;
 
                 // Favor multiple paragraphs
-                score += (stats.paragraph_count.min(10) as f64 / 10.0) * 0.2;
+                score += (stats.paragraph_count.min(10) as f64 / 10.0) * weights.paragraphs;
 
                 // Favor longer content
-                score += (stats.text_length.min(5000) as f64 / 5000.0) * 0.2;
+                score += (stats.text_length.min(5000) as f64 / 5000.0) * weights.text_length;
+
+                let total = weights.text_density + weights.link_density + weights.paragraphs + weights.text_length;
+                if total > 0.0 {
+                    score /= total;
+                }
+
+                // Text repeated elsewhere on the page is template boilerplate
+                score /= stats.repeat_count.max(1) as f64;
             }
             SectionType::Sidebar => {
                 // Favor high link count
@@ -414,10 +801,14 @@ impl StructureAnalyzer {
     }
 
     fn generate_recommendations(&self, sections: &[Section]) -> Recommendations {
+        let is_content = |s: &&Section| matches!(s.section_type, SectionType::Article | SectionType::MainContent);
         let best_main_content = sections
             .iter()
-            .find(|s| matches!(s.section_type, SectionType::Article | SectionType::MainContent))
+            .filter(is_content)
+            .find(|s| s.confidence >= self.min_confidence)
             .map(|s| s.selector.clone());
+        // Content was found, but nothing trustworthy enough to recommend
+        let only_weak_content = best_main_content.is_none() && sections.iter().any(|s| is_content(&s));
 
         let best_title = Some("h1, h2, title".to_string());
 
@@ -438,7 +829,9 @@ impl StructureAnalyzer {
         };
 
         // Determine confidence level
-        let confidence_level = if let Some(main) = sections.first() {
+        let confidence_level = if only_weak_content {
+            ConfidenceLevel::VeryLow
+        } else if let Some(main) = sections.first() {
             if main.score > 0.8 {
                 ConfidenceLevel::VeryHigh
             } else if main.score > 0.6 {
@@ -467,13 +860,7 @@ impl StructureAnalyzer {
 
         for section in sections.drain(..) {
             // Use first 100 chars as fingerprint
-            let fingerprint = if section.preview.len() > 100 {
-                &section.preview[..100]
-            } else {
-                &section.preview
-            };
-
-            if seen_previews.insert(fingerprint.to_string()) {
+            if seen_previews.insert(truncate_chars(&section.preview, 100)) {
                 result.push(section);
             }
         }
@@ -481,34 +868,205 @@ impl StructureAnalyzer {
         result
     }
 
-    fn count_descendants(&self, element: ElementRef, tag: &str) -> usize {
-        if let Ok(selector) = Selector::parse(tag) {
-            element.select(&selector).count()
-        } else {
-            0
+    fn count_elements(&self, document: &Html) -> usize {
+        document.root_element().descendants().count()
+    }
+}
+
+/// First `length` characters of a section's text, with an ellipsis when cut
+fn preview(text: &str, length: usize) -> String {
+    let truncated = truncate_chars(text, length);
+    if truncated.len() < text.len() {
+        format!("{}...", truncated)
+    } else {
+        truncated
+    }
+}
+
+#[derive(Default)]
+struct TagCounts {
+    link_count: usize,
+    image_count: usize,
+    paragraph_count: usize,
+    heading_count: usize,
+    /// Every node of the subtree, text nodes and hidden ones included
+    element_count: usize,
+}
+
+/// All of `calculate_stats`' counts in a single walk of the subtree. Links,
+/// images, paragraphs and headings inside hidden subtrees are not counted.
+fn count_tags(element: ElementRef) -> TagCounts {
+    let mut counts = TagCounts { element_count: 1, ..TagCounts::default() };
+    let mut pending: Vec<_> = element.children().collect();
+
+    while let Some(node) = pending.pop() {
+        let Some(child) = ElementRef::wrap(node) else {
+            counts.element_count += 1;
+            continue;
+        };
+
+        if hides_subtree(child) {
+            counts.element_count += child.descendants().count();
+            continue;
         }
+
+        counts.element_count += 1;
+        match child.value().name() {
+            "a" => counts.link_count += 1,
+            "img" => counts.image_count += 1,
+            "p" => counts.paragraph_count += 1,
+            "h1" | "h2" | "h3" => counts.heading_count += 1,
+            _ => {}
+        }
+        pending.extend(child.children());
     }
 
-    fn count_all_descendants(&self, element: ElementRef) -> usize {
-        element.descendants().count()
+    counts
+}
+
+/// Selector for a content div from its own attributes: `#id`, else its first class,
+/// else the tag name. Not guaranteed to be unique.
+fn div_selector(element: ElementRef) -> String {
+    if let Some(id) = element.value().id() {
+        return format!("#{}", id);
     }
 
-    fn count_elements(&self, document: &Html) -> usize {
-        document.root_element().descendants().count()
+    if let Some(class) = element.value().classes().next() {
+        return format!(".{}", class);
     }
 
-    fn generate_selector(&self, element: ElementRef) -> String {
-        // Try to generate a meaningful selector
-        if let Some(id) = element.value().id() {
-            return format!("#{}", id);
+    element.value().name().to_string()
+}
+
+/// A CSS selector matching exactly `element` in `document`: its `#id` when that is
+/// unique, else a `>` path of tag and class steps, adding `:nth-of-type` where
+/// siblings would be ambiguous
+pub fn generate_selector(document: &Html, element: ElementRef) -> String {
+    let is_unique = |selector: &str| {
+        Selector::parse(selector).is_ok_and(|parsed| {
+            let mut matches = document.select(&parsed);
+            matches.next().is_some_and(|m| m.id() == element.id()) && matches.next().is_none()
+        })
+    };
+
+    let mut steps: Vec<String> = Vec::new();
+    let mut current = Some(element);
+
+    while let Some(el) = current {
+        if let Some(id) = el.value().id().filter(|id| is_css_ident(id)) {
+            let anchored = std::iter::once(format!("#{}", id))
+                .chain(steps.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(" > ");
+            if is_unique(&anchored) {
+                return anchored;
+            }
         }
 
-        if let Some(classes) = element.value().classes().next() {
-            return format!(".{}", classes);
+        let mut step = el.value().name().to_string();
+        for class in el.value().classes().filter(|c| is_css_ident(c)).take(2) {
+            step.push('.');
+            step.push_str(class);
+        }
+        steps.insert(0, step.clone());
+        if is_unique(&steps.join(" > ")) {
+            return steps.join(" > ");
         }
 
-        element.value().name().to_string()
+        if let Some(position) = nth_of_type(el) {
+            steps[0] = format!("{}:nth-of-type({})", step, position);
+            if is_unique(&steps.join(" > ")) {
+                return steps.join(" > ");
+            }
+        }
+
+        current = el.parent().and_then(ElementRef::wrap);
+    }
+
+    steps.join(" > ")
+}
+
+/// 1-based position among same-tag siblings, when there are any
+fn nth_of_type(element: ElementRef) -> Option<usize> {
+    let name = element.value().name();
+    let parent = element.parent()?;
+    let same_tag: Vec<_> = parent.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|sibling| sibling.value().name() == name)
+        .collect();
+
+    if same_tag.len() < 2 {
+        return None;
     }
+    same_tag.iter().position(|sibling| sibling.id() == element.id()).map(|i| i + 1)
+}
+
+/// Whether an id or class can go into a selector without escaping
+fn is_css_ident(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '-')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// One saved page in an NDJSON corpus
+#[derive(Deserialize)]
+struct HtmlRecord {
+    url: String,
+    html: String,
+}
+
+/// Whitespace-collapsed visible text, for comparing blocks
+fn block_key(element: ElementRef) -> String {
+    visible_text(element).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Item count and entry URLs of the first JSON-LD `ItemList` on the page
+fn item_list(document: &Html) -> (Option<usize>, Vec<String>) {
+    let Some(list) = json_ld::items(document).into_iter().find(|item| json_ld::has_type(item, "ItemList")) else {
+        return (None, Vec::new());
+    };
+
+    let elements = json_ld::values(list.get("itemListElement"));
+    let urls: Vec<String> = elements.iter()
+        .filter_map(|element| {
+            // ListItem { url } / ListItem { item: "url" } / ListItem { item: { url | @id } }
+            element.get("url")
+                .or_else(|| element.get("item").filter(|item| item.is_string()))
+                .or_else(|| element.get("item").and_then(|item| item.get("url").or_else(|| item.get("@id"))))
+                .or_else(|| Some(*element).filter(|e| e.is_string()))
+                .and_then(|url| url.as_str())
+                .map(|url| url.to_string())
+        })
+        .collect();
+
+    let count = json_ld::number(list.get("numberOfItems"))
+        .map(|n| n as usize)
+        .or_else(|| (!elements.is_empty()).then_some(elements.len()));
+
+    (count, urls)
+}
+
+/// Who publishes the page according to its JSON-LD: the first `publisher`
+/// name, falling back to the first `author` name
+fn publisher(document: &Html) -> Option<String> {
+    let items = json_ld::items(document);
+    ["publisher", "author"].iter()
+        .find_map(|property| items.iter().find_map(|item| json_ld::text(item.get(*property))))
+}
+
+/// A declared item list marks a listing unless a stronger mode was detected
+fn prefer_list_mode(recommendations: &mut Recommendations, item_count: Option<usize>) {
+    if item_count.is_some() && matches!(recommendations.suggested_mode, ExtractionMode::Generic) {
+        recommendations.suggested_mode = ExtractionMode::ListPage;
+    }
+}
+
+/// Highest score first; ties broken by selector so the order is deterministic
+fn sort_by_score(sections: &mut [Section]) {
+    sections.sort_by(|a, b| {
+        b.score.partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.selector.cmp(&b.selector))
+    });
 }
 
 #[cfg(test)]
@@ -544,4 +1102,342 @@ mod tests {
         assert!(!analysis.sections.is_empty());
         assert!(analysis.recommendations.best_main_content.is_some());
     }
+
+    #[test]
+    fn test_content_divs_use_their_own_id_or_class() {
+        let paragraph = "<p>A paragraph of story text that goes on for a while to count as real content.</p>";
+        let html = format!(
+            r#"<html><body><div id="story"><p>Lead story.</p>{0}</div><div class="story-body"><p>Second story.</p>{0}</div></body></html>"#,
+            paragraph.repeat(6)
+        );
+
+        let analysis = StructureAnalyzer::new().analyze(&html, "https://example.com");
+        let selectors: Vec<&str> = analysis.sections.iter().map(|s| s.selector.as_str()).collect();
+        assert!(selectors.contains(&"#story"), "{:?}", selectors);
+        assert!(selectors.contains(&".story-body"), "{:?}", selectors);
+    }
+
+    fn article_section(selector: &str, stats: SectionStats) -> Section {
+        Section {
+            selector: selector.to_string(),
+            section_type: SectionType::Article,
+            score: 0.0,
+            confidence: 0.5,
+            stats,
+            preview: String::new(),
+            xpath: None,
+        }
+    }
+
+    fn plain_stats() -> SectionStats {
+        SectionStats {
+            text_length: 1000,
+            word_count: 160,
+            link_count: 0,
+            image_count: 0,
+            paragraph_count: 2,
+            heading_count: 0,
+            density_score: 0.5,
+            link_density: 0.0,
+            element_count: 20,
+            repeat_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_low_confidence_content_is_not_recommended() {
+        let weak = Section {
+            confidence: 0.55,
+            score: 0.7,
+            ..article_section("div.teaser", plain_stats())
+        };
+
+        let strict = StructureAnalyzer::new().with_min_confidence(0.7);
+        let recommendations = strict.generate_recommendations(std::slice::from_ref(&weak));
+        assert!(recommendations.best_main_content.is_none());
+        assert!(matches!(recommendations.confidence_level, ConfidenceLevel::VeryLow));
+
+        let strong = Section { confidence: 0.9, ..article_section("article", plain_stats()) };
+        let recommendations = strict.generate_recommendations(&[weak.clone(), strong]);
+        assert_eq!(recommendations.best_main_content.as_deref(), Some("article"));
+
+        // No threshold by default
+        let lenient = StructureAnalyzer::new().generate_recommendations(&[weak]);
+        assert_eq!(lenient.best_main_content.as_deref(), Some("div.teaser"));
+        assert!(matches!(lenient.confidence_level, ConfidenceLevel::High));
+    }
+
+    #[test]
+    fn test_rescore_with_boosted_link_density() {
+        // Long and dense but link-heavy
+        let dense = article_section(".dense", SectionStats {
+            text_length: 5000,
+            word_count: 800,
+            link_count: 50,
+            image_count: 0,
+            paragraph_count: 10,
+            heading_count: 1,
+            density_score: 1.0,
+            link_density: 0.5,
+            element_count: 100,
+            repeat_count: 1,
+        });
+        // Short but link-free
+        let clean = article_section(".clean", SectionStats {
+            text_length: 1000,
+            word_count: 160,
+            link_count: 0,
+            image_count: 0,
+            paragraph_count: 2,
+            heading_count: 0,
+            density_score: 0.5,
+            link_density: 0.0,
+            element_count: 20,
+            repeat_count: 1,
+        });
+
+        let analyzer = StructureAnalyzer::new();
+        let analysis = StructureAnalysis {
+            url: "https://example.com".to_string(),
+            timestamp: String::new(),
+            sections: vec![clean, dense],
+            recommendations: analyzer.generate_recommendations(&[]),
+            debug_info: None,
+            item_count: None,
+            item_urls: Vec::new(),
+            publisher: None,
+            truncated: false,
+        };
+
+        let default = analyzer.rescore(&analysis, ScoringWeights::default());
+        assert_eq!(default.sections[0].selector, ".dense");
+        assert_eq!(default.recommendations.best_main_content.as_deref(), Some(".dense"));
+
+        let boosted = analyzer.rescore(&analysis, ScoringWeights {
+            link_density: 3.0,
+            ..ScoringWeights::default()
+        });
+        assert_eq!(boosted.sections[0].selector, ".clean");
+        assert_eq!(boosted.recommendations.best_main_content.as_deref(), Some(".clean"));
+        assert!(boosted.sections.iter().all(|s| (0.0..=1.0).contains(&s.score)));
+    }
+
+    #[test]
+    fn test_json_ld_item_list() {
+        let html = r#"
+            <html><head>
+                <script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "ItemList",
+                    "numberOfItems": 120,
+                    "itemListElement": [
+                        {"@type": "ListItem", "position": 1, "url": "https://shop.example.com/p/1"},
+                        {"@type": "ListItem", "position": 2, "item": {"@id": "https://shop.example.com/p/2", "name": "Two"}}
+                    ]
+                }
+                </script>
+            </head><body><ul><li>One</li><li>Two</li></ul></body></html>
+        "#;
+
+        let analysis = StructureAnalyzer::new().analyze(html, "https://shop.example.com/list");
+
+        assert_eq!(analysis.item_count, Some(120));
+        assert_eq!(analysis.item_urls.first().map(String::as_str), Some("https://shop.example.com/p/1"));
+        assert_eq!(analysis.item_urls.len(), 2);
+        assert!(matches!(analysis.recommendations.suggested_mode, ExtractionMode::ListPage));
+    }
+
+    #[test]
+    fn test_include_empty_sections_keeps_small_sidebar() {
+        let html = format!(
+            "<html><body><article><p>{}</p></article><div class='sidebar'>Tags: rust</div></body></html>",
+            "Long enough article text for the default threshold. ".repeat(6),
+        );
+
+        let default = StructureAnalyzer::new().analyze(&html, "https://example.com");
+        assert!(!default.sections.iter().any(|s| s.selector == ".sidebar"));
+
+        let all = StructureAnalyzer::new()
+            .with_empty_sections(true)
+            .analyze(&html, "https://example.com");
+        let sidebar = all.sections.iter().find(|s| s.selector == ".sidebar").unwrap();
+        assert!(sidebar.stats.text_length < 200);
+        assert!(all.sections.iter().any(|s| s.selector == "article"));
+    }
+
+    #[test]
+    fn test_repeated_blocks_score_lower() {
+        let menu = "Home Products Pricing Customers Careers Press Contact Support Documentation Blog Status ".repeat(3);
+        let article = "Our quarterly report covers revenue, hiring and the roadmap for next year. ".repeat(3);
+        let html = format!(
+            "<html><body>\
+             <div class='content'><p>{menu}</p></div>\
+             <article><p>{article}</p></article>\
+             <div class='content'><p>{menu}</p></div>\
+             </body></html>"
+        );
+
+        let analysis = StructureAnalyzer::new().analyze(&html, "https://example.com");
+        let article = analysis.sections.iter().find(|s| s.selector == "article").unwrap();
+        let menu = analysis.sections.iter().find(|s| s.selector == ".content").unwrap();
+
+        assert_eq!(menu.stats.repeat_count, 2);
+        assert_eq!(article.stats.repeat_count, 1);
+        assert!(menu.score < article.score);
+        assert_eq!(analysis.recommendations.best_main_content.as_deref(), Some("article"));
+    }
+
+    #[test]
+    fn test_analyze_ndjson_emits_one_analysis_per_record() {
+        let input = [
+            serde_json::json!({ "url": "https://a.example/post", "html": "<html><body><article><p>First</p></article></body></html>" }),
+            serde_json::json!({ "url": "https://b.example/list", "html": "<html><body><ul><li>Second</li></ul></body></html>" }),
+        ]
+        .iter()
+        .map(|record| record.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let mut output = Vec::new();
+        let written = StructureAnalyzer::new().analyze_ndjson(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(written, 2);
+
+        let analyses: Vec<StructureAnalysis> = String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].url, "https://a.example/post");
+        assert_eq!(analyses[1].url, "https://b.example/list");
+    }
+
+    #[test]
+    fn test_large_document_analysis_is_bounded() {
+        let paragraph = "<p>Filler paragraph with a <a href=\"/x\">link</a> and some words.</p>";
+        let block = format!("<div class=\"block\"><div><h2>Heading</h2>{}</div></div>", paragraph.repeat(20));
+        // No <main> or <article>, so every block goes through the div fallback
+        let page = |blocks: usize| format!("<html><body>{}</body></html>", block.repeat(blocks));
+        let timed = |html: &str| {
+            let started = std::time::Instant::now();
+            let analysis = StructureAnalyzer::new().analyze(html, "https://example.com/huge");
+            (started.elapsed(), analysis)
+        };
+
+        let (small_time, _) = timed(&page(500));
+        let html = page(2_000);
+        assert!(html.len() > 2 * 1024 * 1024);
+        let (large_time, analysis) = timed(&html);
+
+        // Four times the blocks: linear work takes about four times as long,
+        // quadratic work sixteen
+        assert!(large_time < small_time * 8, "500 blocks took {:?}, 2000 took {:?}", small_time, large_time);
+        assert!(!analysis.truncated);
+        assert!(!analysis.sections.is_empty());
+
+        let capped = StructureAnalyzer::new()
+            .with_max_html_bytes(Some(64 * 1024))
+            .analyze(&html, "https://example.com/huge");
+        assert!(capped.truncated);
+        assert!(!capped.sections.is_empty());
+    }
+
+    #[test]
+    fn test_previews_cut_multibyte_text_safely() {
+        // 199 ASCII bytes put a 2-byte 'é' across byte 200; fallback divs used to slice bytes there
+        let sentence = format!("{}é{}", "a".repeat(199), " mot".repeat(80));
+        let paragraphs = format!("<p>{}</p>", sentence).repeat(4);
+        let html = format!("<html><body><div class=\"story\">{}</div></body></html>", paragraphs);
+
+        let analysis = StructureAnalyzer::new().analyze(&html, "https://example.fr/");
+        let section = analysis.sections.iter().find(|s| s.selector.contains("story")).unwrap();
+
+        assert!(section.preview.ends_with("..."));
+        assert_eq!(section.preview.trim_end_matches("...").chars().count(), 200);
+        assert!(section.preview.contains('é'));
+    }
+
+    #[test]
+    fn test_analyze_file_decompresses_gzip() {
+        let html = format!(
+            "<html><head><title>Archived</title></head><body><article>{}</article></body></html>",
+            format!("<p>{}</p>", "Archived article text kept for later analysis. ".repeat(10)).repeat(4)
+        );
+        let dir = std::env::temp_dir().join(format!("analyze-gz-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let plain_path = dir.join("page.html");
+        std::fs::write(&plain_path, &html).unwrap();
+        let gz_path = dir.join("page.html.gz");
+        let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(&gz_path).unwrap(), flate2::Compression::default());
+        encoder.write_all(html.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let analyzer = StructureAnalyzer::new();
+        let plain = analyzer.analyze_file(&plain_path).unwrap();
+        let gzipped = analyzer.analyze_file(&gz_path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!plain.sections.is_empty());
+        assert_eq!(serde_json::to_value(&gzipped.sections).unwrap(), serde_json::to_value(&plain.sections).unwrap());
+        assert_eq!(
+            serde_json::to_value(&gzipped.recommendations).unwrap(),
+            serde_json::to_value(&plain.recommendations).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_audit_flags_missing_h1_and_alt_text() {
+        let html = r##"<html><head><title>Garden Tools</title>
+            <meta name="description" content="Spades, rakes and hoes for every garden."></head>
+            <body><h2>Our tools</h2><p>Sturdy tools for the garden.</p>
+            <img src="/spade.jpg" alt="Spade"><img src="/rake.jpg" alt=""><img src="/hoe.jpg">
+            <img src="/fork.jpg" alt="Fork">
+            <a href="/spades">Spades</a><a href="https://shop.example/rakes">Rakes</a>
+            <a href="https://partner.example/">Partner</a><a href="#top">Top</a><a href="mailto:a@shop.example">Mail</a>
+            </body></html>"##;
+
+        let audit = StructureAnalyzer::new().audit(html, "https://shop.example/tools");
+
+        assert_eq!(audit.h1_count, 0);
+        assert!(audit.issues.contains(&"missing_h1".to_string()));
+        assert!(audit.issues.contains(&"images_missing_alt".to_string()));
+        assert!(!audit.issues.contains(&"missing_title".to_string()));
+        assert_eq!(audit.title_length, "Garden Tools".len());
+        assert_eq!((audit.image_count, audit.images_with_alt), (4, 2));
+        assert!((audit.image_alt_coverage - 0.5).abs() < f64::EPSILON);
+        assert_eq!((audit.internal_links, audit.external_links), (2, 1));
+    }
+
+    #[test]
+    fn test_preview_length_is_configurable() {
+        let paragraphs = format!("<p>{}</p>", "Plenty of article text to preview here. ".repeat(20)).repeat(4);
+        let html = format!("<html><body><article>{}</article></body></html>", paragraphs);
+
+        let analysis = StructureAnalyzer::new().with_preview_length(50).analyze(&html, "https://example.com/");
+
+        assert!(!analysis.sections.is_empty());
+        for section in &analysis.sections {
+            assert!(section.preview.ends_with("..."));
+            assert_eq!(section.preview.trim_end_matches("...").chars().count(), 50);
+        }
+    }
+
+    #[test]
+    fn test_template_content_excluded_from_stats() {
+        let visible = "Readers see this paragraph. ".repeat(8);
+        let html = format!(
+            "<html><body><article><p>{}</p><template><p>{}</p><a href='/x'>x</a></template></article></body></html>",
+            visible,
+            "Inert template markup. ".repeat(20),
+        );
+
+        let analysis = StructureAnalyzer::new().analyze(&html, "https://example.com");
+        let article = analysis.sections.iter().find(|s| s.selector == "article").unwrap();
+
+        assert_eq!(article.stats.text_length, visible.trim().len());
+        assert_eq!(article.stats.paragraph_count, 1);
+        assert_eq!(article.stats.link_count, 0);
+        assert!(!article.preview.contains("Inert"));
+    }
 }
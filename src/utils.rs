@@ -1,5 +1,9 @@
 use rand::Rng;
-use std::time::Duration;
+use scraper::Selector;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 /// User agent rotation for avoiding detection
@@ -17,19 +21,70 @@ pub fn get_random_user_agent() -> &'static str {
     USER_AGENTS[index]
 }
 
-/// Rate limiter for polite scraping
+/// Rate limiter for polite scraping: a fixed delay before every request, or a
+/// token bucket that allows short bursts
 pub struct RateLimiter {
     delay_ms: u64,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+struct TokenBucket {
+    capacity: f64,
+    /// Tokens added per second
+    refill_rate: f64,
+    /// May go negative: each waiter reserves a future token before sleeping
+    tokens: f64,
+    last_refill: Instant,
 }
 
 impl RateLimiter {
     pub fn new(requests_per_second: f64) -> Self {
         let delay_ms = (1000.0 / requests_per_second) as u64;
-        Self { delay_ms }
+        Self { delay_ms, bucket: None }
+    }
+
+    /// Allow `capacity` requests back to back, then refill at `per_minute` requests per minute
+    pub fn token_bucket(capacity: u32, per_minute: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+
+        Self {
+            delay_ms: (60_000.0 / per_minute) as u64,
+            bucket: Some(Mutex::new(TokenBucket {
+                capacity,
+                refill_rate: per_minute / 60.0,
+                tokens: capacity,
+                last_refill: Instant::now(),
+            })),
+        }
     }
 
     pub async fn wait(&self) {
-        sleep(Duration::from_millis(self.delay_ms)).await;
+        let bucket = match self.bucket {
+            Some(ref bucket) => bucket,
+            None => {
+                sleep(Duration::from_millis(self.delay_ms)).await;
+                return;
+            }
+        };
+
+        let delay = {
+            let mut bucket = bucket.lock().unwrap();
+            let now = Instant::now();
+            let refilled = now.duration_since(bucket.last_refill).as_secs_f64() * bucket.refill_rate;
+            bucket.tokens = (bucket.tokens + refilled).min(bucket.capacity);
+            bucket.last_refill = now;
+
+            bucket.tokens -= 1.0;
+            if bucket.tokens >= 0.0 || bucket.refill_rate <= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(-bucket.tokens / bucket.refill_rate)
+            }
+        };
+
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
     }
 }
 
@@ -39,10 +94,210 @@ impl Default for RateLimiter {
     }
 }
 
+/// Per-host politeness budget shared by every scraper in the process, so
+/// concurrent jobs against one host are spaced as a whole
+#[derive(Debug)]
+pub struct DomainRateLimiter {
+    interval: Duration,
+    next_slot: Mutex<HashMap<String, Instant>>,
+    /// Fraction of `interval` each gap may vary by; 0 = fixed spacing
+    jitter: f64,
+    /// Host -> state of its jitter sequence, seeded from the host name
+    jitter_state: Mutex<HashMap<String, u64>>,
+}
+
+impl DomainRateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        let interval = if requests_per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / requests_per_second)
+        } else {
+            Duration::ZERO
+        };
+
+        Self {
+            interval,
+            next_slot: Mutex::new(HashMap::new()),
+            jitter: 0.0,
+            jitter_state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Vary each gap by up to `fraction` of the interval (0.3 = ±30%). Every host
+    /// follows its own sequence seeded from its name: the same from run to run,
+    /// but different from other hosts'. Non-finite fractions mean no jitter.
+    pub fn with_domain_jitter(mut self, fraction: f64) -> Self {
+        self.jitter = if fraction.is_finite() { fraction.clamp(0.0, 1.0) } else { 0.0 };
+        self
+    }
+
+    /// Gap to leave after the next request to `host`
+    fn host_interval(&self, host: &str) -> Duration {
+        if self.jitter <= 0.0 || self.interval.is_zero() {
+            return self.interval;
+        }
+
+        let mut states = self.jitter_state.lock().unwrap();
+        let state = states.entry(host.to_string()).or_insert_with(|| fnv1a(host));
+
+        // splitmix64: tiny, and stable unlike rand's generators across versions
+        *state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+
+        let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+        self.interval.mul_f64(1.0 + self.jitter * (2.0 * unit - 1.0))
+    }
+
+    /// Wait until the URL's host may be requested again, reserving the slot
+    pub async fn wait(&self, url: &str) {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_default();
+
+        let delay = {
+            let mut slots = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = slots.get(&host).copied().filter(|s| *s > now).unwrap_or(now);
+            let interval = self.host_interval(&host);
+            slots.insert(host, slot + interval);
+            slot - now
+        };
+
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+    }
+}
+
+impl Default for DomainRateLimiter {
+    fn default() -> Self {
+        Self::new(2.0)
+    }
+}
+
+/// Default bounds of a `FetchCache`
+pub const DEFAULT_FETCH_CACHE_ENTRIES: usize = 64;
+pub const DEFAULT_FETCH_CACHE_BYTES: usize = 16 * 1024 * 1024;
+
+/// A response body as fetched, with its captured headers
+#[derive(Debug, Clone, Default)]
+pub struct FetchedPage {
+    pub body: String,
+    pub headers: HashMap<String, String>,
+    /// Where the request ended up after following redirects
+    pub final_url: String,
+    /// `Content-Type` response header, whether or not headers are captured
+    pub content_type: Option<String>,
+}
+
+/// Recently fetched pages keyed by requested URL, so helper
+/// refetches within one run don't hit the network again. Least recently used
+/// entries are evicted once either bound is exceeded.
+#[derive(Debug)]
+pub struct FetchCache {
+    max_entries: usize,
+    max_bytes: usize,
+    state: Mutex<FetchCacheState>,
+}
+
+#[derive(Debug, Default)]
+struct FetchCacheState {
+    entries: HashMap<String, FetchedPage>,
+    /// Least recently used first
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl FetchCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            state: Mutex::new(FetchCacheState::default()),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Option<FetchedPage> {
+        let mut state = self.state.lock().unwrap();
+        let cached = state.entries.get(url).cloned()?;
+        state.order.retain(|key| key != url);
+        state.order.push_back(url.to_string());
+        Some(cached)
+    }
+
+    pub fn insert(&self, url: &str, page: FetchedPage) {
+        // Bodies larger than the whole budget would only evict everything else
+        if self.max_entries == 0 || page.body.len() > self.max_bytes {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(previous) = state.entries.remove(url) {
+            state.bytes -= previous.body.len();
+            state.order.retain(|key| key != url);
+        }
+
+        state.bytes += page.body.len();
+        state.entries.insert(url.to_string(), page);
+        state.order.push_back(url.to_string());
+
+        while state.entries.len() > self.max_entries || state.bytes > self.max_bytes {
+            let Some(oldest) = state.order.pop_front() else { break };
+            if let Some(evicted) = state.entries.remove(&oldest) {
+                state.bytes -= evicted.body.len();
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for FetchCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FETCH_CACHE_ENTRIES, DEFAULT_FETCH_CACHE_BYTES)
+    }
+}
+
+/// Filenames that servers commonly map to the directory URL itself
+const INDEX_FILES: &[&str] = &["index.html", "index.htm", "index.php", "default.aspx"];
+
 /// Normalize URL by removing fragments and trailing slashes
 pub fn normalize_url(url: &str) -> String {
+    normalize_url_with_options(url, false)
+}
+
+/// Like `normalize_url`, optionally folding index pages (`/docs/index.html`)
+/// into their directory form (`/docs`). Off by default since some sites serve
+/// different content for the two.
+pub fn normalize_url_with_options(url: &str, fold_index_pages: bool) -> String {
     let mut normalized = url.trim().to_string();
 
+    // Canonical scheme and host: lowercased, IDN hosts in punycode
+    if let Ok(parsed) = url::Url::parse(&normalized) {
+        normalized = parsed.to_string();
+    }
+
+    if fold_index_pages {
+        if let Ok(mut parsed) = url::Url::parse(&normalized) {
+            let path = parsed.path().to_string();
+            let (dir, file) = path.rsplit_once('/').unwrap_or(("", path.as_str()));
+
+            if INDEX_FILES.iter().any(|index| index.eq_ignore_ascii_case(file)) {
+                parsed.set_path(&format!("{}/", dir));
+                normalized = parsed.to_string();
+            }
+        }
+    }
+
     // Remove fragment (#)
     if let Some(pos) = normalized.find('#') {
         normalized.truncate(pos);
@@ -56,10 +311,349 @@ pub fn normalize_url(url: &str) -> String {
     normalized
 }
 
+/// Canonical form of a host name for comparisons: lowercased, internationalized
+/// names in punycode (`münchen.de` -> `xn--mnchen-3ya.de`), no trailing dot
+pub fn normalize_domain(host: &str) -> String {
+    let host = host.trim().trim_end_matches('.');
+    match url::Host::parse(host) {
+        Ok(url::Host::Domain(domain)) => domain,
+        Ok(ip) => ip.to_string(),
+        Err(_) => host.to_lowercase(),
+    }
+}
+
+/// Click-id and referral parameters that never change which page is served;
+/// anything starting with `utm_` is dropped as well
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "ref_src", "_ga"];
+
+/// `normalize_url`, plus dropping tracking parameters (and `ignored_params`) and
+/// sorting what is left, so re-ordered or re-tagged links to the same page compare equal
+pub fn normalize_url_query(url: &str, ignored_params: &[String]) -> String {
+    let normalized = normalize_url(url);
+    let Ok(mut parsed) = url::Url::parse(&normalized) else {
+        return normalized;
+    };
+
+    let mut pairs: Vec<(String, String)> = parsed.query_pairs()
+        .filter(|(key, _)| {
+            let key = key.to_lowercase();
+            !key.starts_with("utm_")
+                && !TRACKING_PARAMS.contains(&key.as_str())
+                && !ignored_params.iter().any(|p| p.eq_ignore_ascii_case(&key))
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    normalize_url(parsed.as_str())
+}
+
+/// How whitespace in extracted text is cleaned up
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitespacePolicy {
+    /// Leave text as parsed, only trimming the ends
+    Raw,
+    /// Collapse every run of whitespace (including NBSP) into one space
+    #[default]
+    Collapse,
+    /// Keep line breaks but tidy each line and drop repeated blank lines
+    Preserve,
+}
+
+/// Apply a whitespace policy to a block of extracted text
+pub fn normalize_whitespace(text: &str, policy: WhitespacePolicy) -> String {
+    match policy {
+        WhitespacePolicy::Raw => text.trim().to_string(),
+        // `split_whitespace` also splits on NBSP (U+00A0)
+        WhitespacePolicy::Collapse => text.split_whitespace().collect::<Vec<_>>().join(" "),
+        WhitespacePolicy::Preserve => {
+            let mut lines: Vec<String> = Vec::new();
+            for line in text.lines() {
+                let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+                if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+                    continue;
+                }
+                lines.push(line);
+            }
+
+            while lines.last().is_some_and(|l| l.is_empty()) {
+                lines.pop();
+            }
+
+            lines.join("\n")
+        }
+    }
+}
+
+/// The first `n` characters of `s`. Never splits a multibyte character.
+pub fn truncate_chars(s: &str, n: usize) -> String {
+    match s.char_indices().nth(n) {
+        Some((end, _)) => s[..end].to_string(),
+        None => s.to_string(),
+    }
+}
+
+/// Stable 64-bit FNV-1a hash, hex encoded. Unlike `DefaultHasher` it is
+/// identical across runs and Rust versions, so it is safe to persist.
+pub fn content_hash(text: &str) -> String {
+    format!("{:016x}", fnv1a(text))
+}
+
+fn fnv1a(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Stop caching new selectors past this many, in case they are generated per page
+const SELECTOR_CACHE_LIMIT: usize = 4096;
+
+static SELECTOR_CACHE: LazyLock<RwLock<HashMap<String, Option<Arc<Selector>>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// `Selector::parse`, compiled once per process: later calls with the same
+/// string reuse the parsed selector. Invalid selectors are remembered as `None`.
+pub fn cached_selector(selector: &str) -> Option<Arc<Selector>> {
+    if let Some(cached) = SELECTOR_CACHE.read().unwrap().get(selector) {
+        return cached.clone();
+    }
+
+    let parsed = Selector::parse(selector).ok().map(Arc::new);
+    let mut cache = SELECTOR_CACHE.write().unwrap();
+    if cache.len() < SELECTOR_CACHE_LIMIT {
+        cache.insert(selector.to_string(), parsed.clone());
+    }
+    parsed
+}
+
+static URL_ATTRIBUTE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r#"(?i)(\s)(href|src|srcset)(\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap()
+});
+
+/// Rewrite relative `href`, `src` and `srcset` attributes in an HTML fragment
+/// to absolute URLs, so extracted HTML still works when rendered elsewhere.
+/// Fragment-only links (`#section`) are left alone.
+pub fn rewrite_urls(html: &str, base_url: &str) -> String {
+    let base = match url::Url::parse(base_url) {
+        Ok(base) => base,
+        Err(_) => return html.to_string(),
+    };
+
+    let absolute = |value: &str| -> String {
+        let value = value.trim();
+        if value.is_empty() || value.starts_with('#') {
+            return value.to_string();
+        }
+        base.join(value).map(|u| u.to_string()).unwrap_or_else(|_| value.to_string())
+    };
+
+    URL_ATTRIBUTE.replace_all(html, |caps: &regex::Captures| {
+        let (value, quote) = match caps.get(4) {
+            Some(v) => (v.as_str(), '"'),
+            None => (caps.get(5).map_or("", |v| v.as_str()), '\''),
+        };
+
+        let rewritten = if caps[2].eq_ignore_ascii_case("srcset") {
+            // "small.jpg 480w, large.jpg 1080w": rewrite the URL of each candidate
+            value.split(',')
+                .map(|candidate| {
+                    let candidate = candidate.trim();
+                    match candidate.split_once(char::is_whitespace) {
+                        Some((url, descriptor)) => format!("{} {}", absolute(url), descriptor.trim()),
+                        None => absolute(candidate),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            absolute(value)
+        };
+
+        format!("{}{}{}{}{}{}", &caps[1], &caps[2], &caps[3], quote, rewritten, quote)
+    })
+    .into_owned()
+}
+
+/// Tags kept by `sanitize_html`; anything else is unwrapped to its children
+const SAFE_TAGS: &[&str] = &[
+    "a", "abbr", "article", "b", "blockquote", "br", "caption", "cite", "code", "dd", "del", "div",
+    "dl", "dt", "em", "figcaption", "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header",
+    "hr", "i", "img", "ins", "li", "mark", "ol", "p", "pre", "q", "s", "section", "small", "span",
+    "strong", "sub", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "time", "tr", "u", "ul",
+];
+
+/// Tags dropped together with everything inside them
+const DROPPED_TAGS: &[&str] = &[
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "applet", "template",
+    "noscript", "svg", "math", "form", "input", "button", "textarea", "select", "link", "meta", "base",
+];
+
+/// Attributes kept on any safe tag, and those kept only on specific tags
+const SAFE_ATTRIBUTES: &[&str] = &["class", "id", "title", "lang", "dir"];
+const SAFE_TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("img", &["src", "srcset", "alt", "width", "height"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("ol", &["start"]),
+    ("blockquote", &["cite"]),
+    ("q", &["cite"]),
+    ("time", &["datetime"]),
+];
+
+/// HTML elements without a closing tag
+const VOID_TAGS: &[&str] = &["br", "hr", "img"];
+
+/// Reduce an extracted HTML fragment to an allowlist of tags and attributes
+/// so it can be rendered by a frontend: scripts, styles, embeds and forms are
+/// removed with their content, event handlers (`onerror=`) and inline styles
+/// are dropped, and `javascript:`/`data:` URLs are stripped. Unknown tags are
+/// unwrapped, keeping their text.
+pub fn sanitize_html(html: &str) -> String {
+    let fragment = scraper::Html::parse_fragment(html);
+    let mut output = String::with_capacity(html.len());
+    sanitize_children(fragment.root_element(), &mut output);
+    output
+}
+
+fn sanitize_children(element: scraper::ElementRef, output: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            scraper::Node::Text(text) => output.push_str(&escape_html(text, false)),
+            scraper::Node::Element(_) => {
+                if let Some(child) = scraper::ElementRef::wrap(child) {
+                    sanitize_element(child, output);
+                }
+            }
+            // Comments, doctypes and processing instructions
+            _ => {}
+        }
+    }
+}
+
+fn sanitize_element(element: scraper::ElementRef, output: &mut String) {
+    let tag = element.value().name().to_ascii_lowercase();
+    if DROPPED_TAGS.contains(&tag.as_str()) {
+        return;
+    }
+    if !SAFE_TAGS.contains(&tag.as_str()) {
+        sanitize_children(element, output);
+        return;
+    }
+
+    let tag_attributes = SAFE_TAG_ATTRIBUTES.iter()
+        .find(|(name, _)| *name == tag)
+        .map_or(&[][..], |(_, attributes)| *attributes);
+
+    output.push('<');
+    output.push_str(&tag);
+    for (name, value) in element.value().attrs() {
+        let name = name.to_ascii_lowercase();
+        if !SAFE_ATTRIBUTES.contains(&name.as_str()) && !tag_attributes.contains(&name.as_str()) {
+            continue;
+        }
+
+        let safe = match name.as_str() {
+            "href" | "cite" => is_safe_url(value, false),
+            "src" => is_safe_url(value, true),
+            "srcset" => value.split(',').all(|candidate| {
+                is_safe_url(candidate.split_whitespace().next().unwrap_or(""), true)
+            }),
+            _ => true,
+        };
+        if safe {
+            output.push_str(&format!(" {}=\"{}\"", name, escape_html(value, true)));
+        }
+    }
+    output.push('>');
+
+    if VOID_TAGS.contains(&tag.as_str()) {
+        return;
+    }
+    sanitize_children(element, output);
+    output.push_str(&format!("</{}>", tag));
+}
+
+/// Relative URLs and http(s)/mailto/tel links are safe; images may also use `data:image/`
+fn is_safe_url(value: &str, image: bool) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme ("java\tscript:")
+    let compact: String = value.chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    let scheme_end = compact.find(|c: char| matches!(c, ':' | '/' | '?' | '#'));
+    match scheme_end {
+        Some(end) if compact[end..].starts_with(':') => {
+            let scheme = &compact[..end];
+            matches!(scheme, "http" | "https" | "mailto" | "tel")
+                || (image && compact.starts_with("data:image/") && !compact.starts_with("data:image/svg"))
+        }
+        _ => true,
+    }
+}
+
+fn escape_html(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_truncate_chars_on_multibyte_boundaries() {
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+        assert_eq!(truncate_chars("日本語のテキスト", 3), "日本語");
+        assert_eq!(truncate_chars("👍🏽ok", 1), "👍");
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("", 5), "");
+        assert_eq!(truncate_chars("abc", 0), "");
+    }
+
+    #[test]
+    fn test_cached_selector_skips_reparsing() {
+        let selector = "article > div.content p:not(.ad), section[data-role='main'] li a[href^='/']";
+
+        let first = cached_selector(selector).unwrap();
+        assert!(Arc::ptr_eq(&first, &cached_selector(selector).unwrap()));
+        assert!(cached_selector("div[").is_none());
+
+        let started = Instant::now();
+        for _ in 0..2_000 {
+            Selector::parse(selector).unwrap();
+        }
+        let parsing = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..2_000 {
+            cached_selector(selector).unwrap();
+        }
+        let cached = started.elapsed();
+
+        assert!(cached < parsing, "cached {:?} vs parsing {:?}", cached, parsing);
+    }
+
     #[test]
     fn test_random_user_agent() {
         let agent = get_random_user_agent();
@@ -75,4 +669,192 @@ mod tests {
         let default_limiter = RateLimiter::default();
         assert_eq!(default_limiter.delay_ms, 500);
     }
+
+    #[tokio::test]
+    async fn test_token_bucket_allows_burst_then_throttles() {
+        // 3 quick requests, then 10 per second
+        let limiter = RateLimiter::token_bucket(3, 600.0);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        let throttled = Instant::now();
+        limiter.wait().await;
+        limiter.wait().await;
+        assert!(throttled.elapsed() >= Duration::from_millis(180));
+    }
+
+    #[tokio::test]
+    async fn test_domain_rate_limiter_spaces_shared_host() {
+        let limiter = std::sync::Arc::new(DomainRateLimiter::new(10.0));
+        let start = Instant::now();
+
+        // Two scrapers hitting the same host twice each share one budget
+        let first = limiter.clone();
+        let second = limiter.clone();
+        tokio::join!(
+            async move {
+                first.wait("https://example.com/a").await;
+                first.wait("https://example.com/b").await;
+            },
+            async move {
+                second.wait("https://example.com/c").await;
+                second.wait("https://example.com/d").await;
+            },
+        );
+        assert!(start.elapsed() >= Duration::from_millis(300));
+
+        // Other hosts have their own budget
+        let other = Instant::now();
+        limiter.wait("https://other.example.org/").await;
+        assert!(other.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_domain_jitter_is_seeded_per_host() {
+        let sequence = |limiter: &DomainRateLimiter, host: &str| -> Vec<Duration> {
+            (0..8).map(|_| limiter.host_interval(host)).collect()
+        };
+
+        let first_run = DomainRateLimiter::new(10.0).with_domain_jitter(0.5);
+        let second_run = DomainRateLimiter::new(10.0).with_domain_jitter(0.5);
+
+        let alpha = sequence(&first_run, "alpha.example");
+        assert_eq!(alpha, sequence(&second_run, "alpha.example"));
+        assert_ne!(alpha, sequence(&first_run, "beta.example"));
+
+        // Varies within ±50% of the 100ms interval
+        assert!(alpha.windows(2).any(|pair| pair[0] != pair[1]));
+        assert!(alpha.iter().all(|gap| *gap >= Duration::from_millis(50) && *gap <= Duration::from_millis(150)));
+
+        let fixed = DomainRateLimiter::new(10.0);
+        assert!(sequence(&fixed, "alpha.example").iter().all(|gap| *gap == Duration::from_millis(100)));
+
+        for fraction in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let limiter = DomainRateLimiter::new(10.0).with_domain_jitter(fraction);
+            assert!(sequence(&limiter, "alpha.example").iter().all(|gap| *gap == Duration::from_millis(100)));
+        }
+    }
+
+    #[test]
+    fn test_normalize_whitespace_policies() {
+        let text = "  Hello\u{a0}\u{a0}world  \n\n\n  second   line \n";
+
+        assert_eq!(normalize_whitespace(text, WhitespacePolicy::Collapse), "Hello world second line");
+        assert_eq!(normalize_whitespace(text, WhitespacePolicy::Preserve), "Hello world\n\nsecond line");
+        assert_eq!(
+            normalize_whitespace(text, WhitespacePolicy::Raw),
+            "Hello\u{a0}\u{a0}world  \n\n\n  second   line"
+        );
+    }
+
+    #[test]
+    fn test_normalize_url_folds_index_pages() {
+        let fold = |url| normalize_url_with_options(url, true);
+
+        assert_eq!(fold("http://x.com/index.html"), "http://x.com");
+        assert_eq!(fold("http://x.com/docs/INDEX.PHP"), "http://x.com/docs");
+        assert_eq!(fold("http://x.com/app/default.aspx?tab=2"), "http://x.com/app/?tab=2");
+        assert_eq!(fold("http://x.com/docs/intro.html"), "http://x.com/docs/intro.html");
+
+        // Bare host and host-with-slash are the same page
+        assert_eq!(fold("http://x.com/"), fold("http://x.com"));
+
+        // Folding is opt-in
+        assert_eq!(normalize_url("http://x.com/index.html"), "http://x.com/index.html");
+    }
+
+    #[test]
+    fn test_idn_hosts_compare_equal() {
+        assert_eq!(normalize_domain("München.de"), "xn--mnchen-3ya.de");
+        assert_eq!(normalize_domain("münchen.de."), normalize_domain("XN--MNCHEN-3YA.DE"));
+        assert_eq!(
+            normalize_url("https://münchen.de/stadtplan/"),
+            normalize_url("https://xn--mnchen-3ya.de/stadtplan")
+        );
+    }
+
+    #[test]
+    fn test_normalize_url_query() {
+        let key = |url| normalize_url_query(url, &["session".to_string()]);
+
+        assert_eq!(key("http://x.com/list?page=2&utm_source=feed&fbclid=abc"), "http://x.com/list?page=2");
+        assert_eq!(key("http://x.com/list?sort=new&page=2"), key("http://x.com/list?page=2&sort=new"));
+        assert_eq!(key("http://x.com/list/?session=42"), "http://x.com/list");
+        assert_ne!(key("http://x.com/list?page=2"), key("http://x.com/list?page=3"));
+    }
+
+    #[test]
+    fn test_fetch_cache_evicts_least_recently_used() {
+        let page = |body: &str| FetchedPage { body: body.to_string(), ..FetchedPage::default() };
+        let cache = FetchCache::new(2, 10);
+        cache.insert("a", page("aaaa"));
+        cache.insert("b", page("bbbb"));
+        assert!(cache.get("a").is_some());
+
+        // Over the entry count: "b" is the least recently used
+        cache.insert("c", page("cc"));
+        assert!(cache.get("b").is_none());
+
+        // Over the byte budget: "a" goes next
+        cache.insert("d", page("dddddd"));
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("d").map(|page| page.body).as_deref(), Some("dddddd"));
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash("page one"), content_hash("page two"));
+    }
+
+    #[test]
+    fn test_rewrite_urls_makes_links_absolute() {
+        let html = r##"<div><a href="/about">About</a> <a href='next.html'>Next</a> <a href="#top">Top</a>
+            <img src="../img/a.png" srcset="../img/a.png 1x, /img/a@2x.png 2x">
+            <a href="https://other.com/x">Other</a></div>"##;
+
+        let rewritten = rewrite_urls(html, "https://example.com/blog/post/");
+
+        assert!(rewritten.contains(r#"href="https://example.com/about""#));
+        assert!(rewritten.contains("href='https://example.com/blog/post/next.html'"));
+        assert!(rewritten.contains(r##"href="#top""##));
+        assert!(rewritten.contains(r#"src="https://example.com/blog/img/a.png""#));
+        assert!(rewritten.contains(
+            r#"srcset="https://example.com/blog/img/a.png 1x, https://example.com/img/a@2x.png 2x""#
+        ));
+        assert!(rewritten.contains(r#"href="https://other.com/x""#));
+    }
+
+    #[test]
+    fn test_sanitize_html_strips_scripts_handlers_and_javascript_urls() {
+        let html = r#"<div class="post"><script>alert(1)</script><p>Hello <b>world</b></p>
+            <img src="/a.png" alt="A" onerror="alert(2)"><a href="javascript:alert(3)">bad</a>
+            <a href=" JaVa&#09;script:alert(4)">tab</a><a href="https://example.com/x" onclick="go()">ok</a>
+            <iframe src="https://evil.example"></iframe><font color="red">kept text</font></div>"#;
+
+        let clean = sanitize_html(html);
+
+        assert!(!clean.contains("<script") && !clean.contains("alert(1)"));
+        assert!(!clean.contains("onerror") && !clean.contains("onclick"));
+        assert!(!clean.to_lowercase().contains("script:"));
+        assert!(!clean.contains("iframe"));
+        assert!(clean.contains(r#"<div class="post">"#));
+        assert!(clean.contains("<p>Hello <b>world</b></p>"));
+        assert!(clean.contains(r#"<img src="/a.png" alt="A">"#));
+        assert!(clean.contains(r#"<a href="https://example.com/x">ok</a>"#));
+        assert!(clean.contains("<a>bad</a>"));
+        assert!(clean.contains("kept text") && !clean.contains("<font"));
+    }
+
+    #[test]
+    fn test_sanitize_html_escapes_text_and_attributes() {
+        assert_eq!(sanitize_html("<p title='a\"b'>1 &lt; 2</p>"), r#"<p title="a&quot;b">1 &lt; 2</p>"#);
+        assert_eq!(sanitize_html("<!-- note --><span>x</span>"), "<span>x</span>");
+    }
 }
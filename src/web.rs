@@ -2,14 +2,22 @@ use actix_cors::Cors;
 use actix_web::{middleware, web, App, HttpResponse, HttpServer, Result};
 use rust_web_scraper::etsy::{EtsyScraper, EtsyScrapingResult};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// Name results are saved under unless the request names another template.
+/// `{category}` is the category slug from the URL, `{timestamp}` the scrape time.
+const DEFAULT_FILENAME_TEMPLATE: &str = "etsy_{category}_{timestamp}.json";
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ScrapeRequest {
     category_url: String,
     max_pages: usize,
     #[serde(default)]
     fetch_reviews: bool,
+    /// File name inside the server's output directory; it can't name another directory
+    #[serde(default)]
+    filename_template: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,11 +25,59 @@ struct ScrapeResponse {
     success: bool,
     message: String,
     data: Option<EtsyScrapingResult>,
+    #[serde(default)]
+    output_path: Option<String>,
+}
+
+/// Category part of an Etsy URL: `https://www.etsy.com/c/jewelry/necklaces?ref=x`
+/// -> `jewelry-necklaces`. Anything that isn't a category page becomes `etsy`.
+fn category_slug(category_url: &str) -> String {
+    let path = url::Url::parse(category_url)
+        .map(|u| u.path().to_string())
+        .unwrap_or_default();
+
+    let slug = path.trim_matches('/')
+        .trim_start_matches("c/")
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() { "etsy".to_string() } else { slug }
+}
+
+/// Render `template` into a file name inside `dir`. Names that are empty, absolute,
+/// or contain path separators or `..` are rejected.
+fn output_path(
+    dir: &Path,
+    template: &str,
+    category_url: &str,
+    timestamp: chrono::DateTime<chrono::Local>,
+) -> Result<PathBuf, String> {
+    let name = template
+        .replace("{category}", &category_slug(category_url))
+        .replace("{timestamp}", &timestamp.format("%Y%m%d_%H%M%S_%3f").to_string());
+
+    if name.is_empty() || name == "." || name.contains("..") || name.contains(['/', '\\']) || Path::new(&name).is_absolute() {
+        return Err(format!("Invalid output file name '{}'", name));
+    }
+    Ok(dir.join(name))
+}
+
+fn save_result(path: &Path, result: &EtsyScrapingResult) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(result)?)?;
+    Ok(())
 }
 
 #[derive(Clone)]
 struct AppState {
     results: Arc<Mutex<Option<EtsyScrapingResult>>>,
+    /// Directory every result file is written to (`OUTPUT_DIR`)
+    output_dir: PathBuf,
 }
 
 async fn index() -> Result<HttpResponse> {
@@ -284,7 +340,7 @@ async fn index() -> Result<HttpResponse> {
 
                 if (data.success) {
                     showStatus('success', data.message);
-                    displayResults(data.data);
+                    displayResults(data.data, data.output_path);
                 } else {
                     showStatus('error', data.message);
                 }
@@ -301,7 +357,7 @@ async fn index() -> Result<HttpResponse> {
             statusDiv.textContent = message;
         }
 
-        function displayResults(data) {
+        function displayResults(data, outputPath) {
             if (!data) return;
 
             const html = `
@@ -338,12 +394,13 @@ async fn index() -> Result<HttpResponse> {
 
                 <div style="margin-top: 20px; text-align: center;">
                     <p style="color: #666;">
-                        Data saved to: <code>output/etsy_reviews.json</code>
+                        Data saved to: <code id="outputPath"></code>
                     </p>
                 </div>
             `;
 
             resultsDiv.innerHTML = html;
+            document.getElementById('outputPath').textContent = outputPath;
             resultsDiv.className = 'results show';
         }
     </script>
@@ -364,6 +421,19 @@ async fn scrape_api(
         req.fetch_reviews
     );
 
+    let template = req.filename_template.as_deref().unwrap_or(DEFAULT_FILENAME_TEMPLATE);
+    let path = match output_path(&state.output_dir, template, &req.category_url, chrono::Local::now()) {
+        Ok(path) => path,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(ScrapeResponse {
+                success: false,
+                message,
+                data: None,
+                output_path: None,
+            }));
+        }
+    };
+
     // Create scraper with review fetching option
     let scraper = match EtsyScraper::with_options(true, req.fetch_reviews) {
         Ok(s) => s,
@@ -372,6 +442,7 @@ async fn scrape_api(
                 success: false,
                 message: format!("Failed to create scraper: {}", e),
                 data: None,
+                output_path: None,
             }));
         }
     };
@@ -380,9 +451,7 @@ async fn scrape_api(
     match scraper.scrape_category(&req.category_url, req.max_pages).await {
         Ok(result) => {
             // Save to file
-            let json = serde_json::to_string_pretty(&result)?;
-            std::fs::create_dir_all("output")?;
-            std::fs::write("output/etsy_reviews.json", json)?;
+            save_result(&path, &result).map_err(actix_web::error::ErrorInternalServerError)?;
 
             // Store in state
             *state.results.lock().unwrap() = Some(result.clone());
@@ -400,6 +469,7 @@ async fn scrape_api(
                     result.total_products, result.total_reviews
                 ),
                 data: Some(result),
+                output_path: Some(path.display().to_string()),
             }))
         }
         Err(e) => {
@@ -408,6 +478,7 @@ async fn scrape_api(
                 success: false,
                 message: format!("Scraping failed: {}", e),
                 data: None,
+                output_path: None,
             }))
         }
     }
@@ -417,8 +488,12 @@ async fn scrape_api(
 async fn main() -> std::io::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
+    let output_dir = std::env::var("OUTPUT_DIR")
+        .unwrap_or_else(|_| "output".to_string());
+
     let state = web::Data::new(AppState {
         results: Arc::new(Mutex::new(None)),
+        output_dir: PathBuf::from(output_dir),
     });
 
     log::info!("Starting Etsy Scraper Web Server at http://localhost:8080");
@@ -440,3 +515,55 @@ async fn main() -> std::io::Result<()> {
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categories_write_to_distinct_files() {
+        let dir = std::env::temp_dir().join(format!("etsy-output-{}", std::process::id()));
+        let now = chrono::Local::now();
+
+        let necklaces = output_path(&dir, DEFAULT_FILENAME_TEMPLATE, "https://www.etsy.com/c/jewelry/necklaces?ref=catnav", now).unwrap();
+        let mugs = output_path(&dir, DEFAULT_FILENAME_TEMPLATE, "https://www.etsy.com/c/home-and-living/mugs", now).unwrap();
+
+        assert_eq!(category_slug("https://www.etsy.com/c/jewelry/necklaces?ref=catnav"), "jewelry-necklaces");
+        assert_ne!(necklaces, mugs);
+
+        let result = EtsyScrapingResult {
+            timestamp: now.to_rfc3339(),
+            category_url: String::new(),
+            total_products: 0,
+            total_reviews: 0,
+            products: Vec::new(),
+            summary: rust_web_scraper::etsy::ScrapingSummary {
+                pages_scraped: 0,
+                products_with_reviews: 0,
+                average_rating: None,
+                time_taken_seconds: 0,
+            },
+        };
+        save_result(&necklaces, &result).unwrap();
+        save_result(&mugs, &result).unwrap();
+
+        assert!(necklaces.exists());
+        assert!(mugs.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_names_cannot_leave_output_dir() {
+        let dir = Path::new("output");
+        let now = chrono::Local::now();
+        let url = "https://www.etsy.com/c/jewelry/necklaces";
+
+        assert_eq!(
+            output_path(dir, "necklaces_{category}.json", url, now).unwrap(),
+            dir.join("necklaces_jewelry-necklaces.json")
+        );
+        for template in ["/etc/cron.d/x", "../../.ssh/authorized_keys", "nested/{category}.json", "..\\x.json", "..", ""] {
+            assert!(output_path(dir, template, url, now).is_err(), "{} accepted", template);
+        }
+    }
+}